@@ -0,0 +1,19 @@
+CREATE TABLE collections (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE collection_phrases (
+    collection_id UUID NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    phrase_id UUID NOT NULL REFERENCES phrases(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (collection_id, phrase_id),
+    -- Deferred so that reordering can shuffle positions inside one transaction
+    UNIQUE (collection_id, position) DEFERRABLE INITIALLY DEFERRED
+);
+
+CREATE INDEX idx_collection_phrases_phrase_id ON collection_phrases(phrase_id);
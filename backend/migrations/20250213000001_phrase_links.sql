@@ -0,0 +1,13 @@
+CREATE TABLE phrase_links (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    from_phrase_id UUID NOT NULL REFERENCES phrases(id) ON DELETE CASCADE,
+    to_phrase_id UUID NOT NULL REFERENCES phrases(id) ON DELETE CASCADE,
+    link_type TEXT NOT NULL CHECK (link_type IN ('synonym', 'antonym', 'variation', 'see_also')),
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (from_phrase_id <> to_phrase_id),
+    UNIQUE (from_phrase_id, to_phrase_id, link_type)
+);
+
+CREATE INDEX idx_phrase_links_from ON phrase_links(from_phrase_id);
+CREATE INDEX idx_phrase_links_to ON phrase_links(to_phrase_id);
@@ -0,0 +1,6 @@
+-- pgvector can only index `vector` columns up to 2000 dimensions, but `halfvec` up to 4000.
+-- Keep a half-precision copy of every embedding so an HNSW/IVFFlat index can be built on it.
+-- The index itself is opt-in and managed through /api/admin/vector-index.
+ALTER TABLE phrase_meanings
+    ADD COLUMN meaning_embedding_half halfvec(3072)
+    GENERATED ALWAYS AS (meaning_embedding::halfvec(3072)) STORED;
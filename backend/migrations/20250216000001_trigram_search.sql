@@ -0,0 +1,19 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Folds width (NFKC), katakana to hiragana and case, so that ｶﾌｶ, カフカ and かふか compare
+-- equal. Must stay in sync with services::text::normalize.
+CREATE FUNCTION eemee_normalize(input TEXT) RETURNS TEXT
+LANGUAGE sql IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT lower(translate(
+        normalize(input, NFKC),
+        'ァアィイゥウェエォオカガキギクグケゲコゴサザシジスズセゼソゾタダチヂッツヅテデトドナニヌネノハバパヒビピフブプヘベペホボポマミムメモャヤュユョヨラリルレロヮワヰヱヲンヴヵヶ',
+        'ぁあぃいぅうぇえぉおかがきぎくぐけげこごさざしじすずせぜそぞただちぢっつづてでとどなにぬねのはばぱひびぴふぶぷへべぺほぼぽまみむめもゃやゅゆょよらりるれろゎわゐゑをんゔゕゖ'
+    ))
+$$;
+
+CREATE INDEX idx_phrases_phrase_trgm ON phrases USING GIN (eemee_normalize(phrase) gin_trgm_ops);
+CREATE INDEX idx_phrases_source_trgm ON phrases USING GIN (eemee_normalize(source) gin_trgm_ops);
+CREATE INDEX idx_phrases_memo_trgm ON phrases USING GIN (eemee_normalize(memo) gin_trgm_ops);
+CREATE INDEX idx_phrase_meanings_meaning_trgm
+    ON phrase_meanings USING GIN (eemee_normalize(meaning) gin_trgm_ops);
@@ -0,0 +1,22 @@
+CREATE TABLE search_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    mode TEXT NOT NULL CHECK (mode IN ('semantic', 'text')),
+    query TEXT NOT NULL,
+    filters JSONB NOT NULL DEFAULT '{}',
+    result_count INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_search_history_created_at ON search_history(created_at DESC);
+
+CREATE TABLE saved_searches (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    mode TEXT NOT NULL CHECK (mode IN ('semantic', 'text')),
+    query TEXT NOT NULL,
+    filters JSONB NOT NULL DEFAULT '{}',
+    -- Cached on the first run of a semantic search; cleared when mode or query change
+    query_embedding vector(3072),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,6 @@
+-- Record which model produced each embedding, so backups can be restored without
+-- re-embedding and vectors from different models are never mixed.
+-- Every existing embedding came from text-embedding-3-large.
+ALTER TABLE phrase_meanings
+    ADD COLUMN embedding_model TEXT NOT NULL DEFAULT 'text-embedding-3-large';
+ALTER TABLE phrase_meanings ALTER COLUMN embedding_model DROP DEFAULT;
@@ -0,0 +1,25 @@
+-- Meanings are embedded in the background: saved as `pending` without an embedding, then
+-- filled in by a worker. `failed` once the job has run out of attempts.
+ALTER TABLE phrase_meanings
+    ALTER COLUMN meaning_embedding DROP NOT NULL,
+    ALTER COLUMN embedding_model DROP NOT NULL,
+    ADD COLUMN embedding_status TEXT NOT NULL DEFAULT 'ready'
+        CHECK (embedding_status IN ('pending', 'ready', 'failed'));
+ALTER TABLE phrase_meanings ALTER COLUMN embedding_status SET DEFAULT 'pending';
+ALTER TABLE phrase_meanings ADD CONSTRAINT phrase_meanings_embedding_ready
+    CHECK ((embedding_status = 'ready') = (meaning_embedding IS NOT NULL AND embedding_model IS NOT NULL));
+
+-- One job per pending meaning, claimed with FOR UPDATE SKIP LOCKED. Finished jobs are
+-- deleted; jobs out of attempts stay behind as `dead` for inspection.
+CREATE TABLE embedding_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    meaning_id UUID NOT NULL UNIQUE REFERENCES phrase_meanings(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_embedding_jobs_queued ON embedding_jobs(run_at) WHERE status = 'queued';
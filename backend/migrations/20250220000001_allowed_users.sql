@@ -0,0 +1,6 @@
+-- Accounts allowed to log in besides those in the configuration, managed with
+-- `eemee-admin users`. Stored lowercased.
+CREATE TABLE allowed_users (
+    email TEXT PRIMARY KEY CHECK (email = lower(email)),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
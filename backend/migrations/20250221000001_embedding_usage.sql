@@ -0,0 +1,11 @@
+-- One row per embedding API call, for budgets and cost tracking. `request_id` ties a call
+-- to the request that made it; background jobs have none.
+CREATE TABLE embedding_usage (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    model TEXT NOT NULL,
+    tokens BIGINT NOT NULL CHECK (tokens >= 0),
+    request_id TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_embedding_usage_created_at ON embedding_usage(created_at);
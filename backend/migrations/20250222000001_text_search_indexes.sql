@@ -0,0 +1,28 @@
+-- lower() follows the database's LC_CTYPE, which may fold non-ASCII letters differently
+-- from services::text::normalize, or not at all under C. The ICU root collation applies
+-- the Unicode default case mapping, as Rust's char::to_lowercase does.
+CREATE OR REPLACE FUNCTION eemee_normalize(input TEXT) RETURNS TEXT
+LANGUAGE sql IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT lower(translate(
+        normalize(input, NFKC),
+        'ァアィイゥウェエォオカガキギクグケゲコゴサザシジスズセゼソゾタダチヂッツヅテデトドナニヌネノハバパヒビピフブプヘベペホボポマミムメモャヤュユョヨラリルレロヮワヰヱヲンヴヵヶ',
+        'ぁあぃいぅうぇえぉおかがきぎくぐけげこごさざしじすずせぜそぞただちぢっつづてでとどなにぬねのはばぱひびぴふぶぷへべぺほぼぽまみむめもゃやゅゆょよらりるれろゎわゐゑをんゔゕゖ'
+    ) COLLATE "und-x-icu")
+$$;
+
+REINDEX INDEX idx_phrases_phrase_trgm;
+REINDEX INDEX idx_phrases_source_trgm;
+REINDEX INDEX idx_phrases_memo_trgm;
+REINDEX INDEX idx_phrase_meanings_meaning_trgm;
+
+-- All tags of a phrase as one string, so that text search can find candidate phrases by
+-- tag through a trigram index. array_to_string is only STABLE in general, but is
+-- immutable for text[].
+CREATE FUNCTION eemee_normalize_tags(tags TEXT[]) RETURNS TEXT
+LANGUAGE sql IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT eemee_normalize(array_to_string(tags, ' '))
+$$;
+
+CREATE INDEX idx_phrases_tags_trgm ON phrases USING GIN (eemee_normalize_tags(tags) gin_trgm_ops);
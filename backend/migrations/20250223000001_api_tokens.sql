@@ -0,0 +1,11 @@
+-- Bearer tokens for scripts calling the API, created with `eemee-admin tokens create`.
+-- Only a SHA-256 of each token is kept; a token acts as its user, who must still be
+-- allowed to log in.
+CREATE TABLE api_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    email TEXT NOT NULL CHECK (email = lower(email)),
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMPTZ
+);
@@ -0,0 +1,10 @@
+-- Deleted phrases, kept as a snapshot with their meanings, embeddings, collection
+-- memberships and links until `eemee-admin trash vacuum` removes them. They are out of
+-- `phrases`, so no query has to skip them.
+CREATE TABLE trash (
+    id UUID PRIMARY KEY,
+    snapshot JSONB NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_trash_deleted_at ON trash(deleted_at);
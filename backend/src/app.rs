@@ -0,0 +1,97 @@
+//! The full application router, shared by `main` and the integration tests.
+
+use std::sync::Arc;
+
+use axum::Router;
+use axum::http::{HeaderName, HeaderValue, Method, header};
+use axum::middleware;
+use axum::routing::{get, post};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::services::{ServeDir, ServeFile};
+use tower_sessions::cookie::SameSite;
+use tower_sessions::{Expiry, SessionManagerLayer, SessionStore};
+
+use crate::auth;
+use crate::config::Config;
+use crate::request_id::{self, REQUEST_ID_HEADER};
+use crate::routes;
+use crate::services::metrics;
+use crate::state::AppState;
+
+/// Routes with sessions, auth, CORS, metrics and request ids, falling back to the frontend.
+/// `/metrics` is only served when `expose_metrics` is set.
+pub fn build<S: SessionStore + Clone>(
+    state: Arc<AppState>,
+    config: &Config,
+    session_store: S,
+) -> Router {
+    build_with(state, config, session_store, |api| api)
+}
+
+/// Like [`build`], with `wrap_api` applied to the `/api` routes outside the auth check
+/// but inside the session layer, e.g. for tests to log a session in.
+pub fn build_with<S: SessionStore + Clone>(
+    state: Arc<AppState>,
+    config: &Config,
+    session_store: S,
+    wrap_api: impl FnOnce(Router) -> Router,
+) -> Router {
+    let session_layer = SessionManagerLayer::new(session_store)
+        .with_secure(config.session.secure_cookies)
+        .with_same_site(SameSite::Lax)
+        .with_expiry(Expiry::OnInactivity(
+            tower_sessions::cookie::time::Duration::days(config.session.lifetime_days),
+        ));
+
+    // Auth routes (nested under /api/auth)
+    let auth_routes = Router::new()
+        .route("/google", get(auth::google_login))
+        .route("/callback", get(auth::google_callback))
+        .route("/logout", post(auth::logout))
+        .route("/me", get(auth::me))
+        .with_state(state.clone());
+
+    let api = Router::new()
+        .nest("/auth", auth_routes)
+        .merge(routes::api_router(state.clone()))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::middleware::require_auth,
+        ));
+
+    let index_file = format!("{}/index.html", config.static_dir);
+    let mut app = Router::new().nest("/api", wrap_api(api));
+    if config.expose_metrics {
+        app = app.merge(routes::metrics_router(state));
+    }
+    let mut app = app
+        .fallback_service(ServeDir::new(&config.static_dir).fallback(ServeFile::new(index_file)))
+        .layer(session_layer);
+    if let Some(cors) = cors_layer(config) {
+        app = app.layer(cors);
+    }
+    app.layer(middleware::from_fn(metrics::track_requests))
+        .layer(middleware::from_fn(request_id::propagate))
+}
+
+/// Lets the configured origins call the API with the session cookie; none when the
+/// frontend is served by this server.
+fn cors_layer(config: &Config) -> Option<CorsLayer> {
+    if config.cors.origins.is_empty() {
+        return None;
+    }
+    let origins = config
+        .cors
+        .origins
+        .iter()
+        .map(|o| HeaderValue::from_str(o).expect("validated on load"));
+    let request_id = HeaderName::from_static(REQUEST_ID_HEADER);
+    Some(
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_credentials(true)
+            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+            .allow_headers([header::CONTENT_TYPE, request_id.clone()])
+            .expose_headers([request_id]),
+    )
+}
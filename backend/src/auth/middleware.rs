@@ -1,30 +1,64 @@
-use axum::extract::Request;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::header;
 use axum::middleware::Next;
 use axum::response::Response;
 use tower_sessions::Session;
 
+use crate::auth::{AuthenticatedUser, SESSION_EMAIL_KEY};
 use crate::error::AppError;
+use crate::services::{tokens, users};
+use crate::state::AppState;
+
+/// Whether an `/api` path is served without a login.
+pub fn is_public(path: &str) -> bool {
+    path.starts_with("/auth/")
+        || path == "/health"
+        || path.starts_with("/health/")
+        || path == "/openapi.json"
+}
 
+/// Lets a request through with a logged-in session or an API token, recording whose it
+/// is as an [`AuthenticatedUser`].
 pub async fn require_auth(
+    State(state): State<Arc<AppState>>,
     session: Session,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
     let path = request.uri().path();
 
     // Skip auth for public endpoints
-    if path.starts_with("/auth/") || path == "/health" {
+    if is_public(path) {
         return Ok(next.run(request).await);
     }
 
-    let email: Option<String> = session
-        .get("email")
-        .await
-        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let email = match bearer {
+        // A bad token is refused rather than falling back to the session
+        Some(secret) => match tokens::authenticate(&state.pool, secret.trim()).await? {
+            Some(email)
+                if users::is_allowed(&state.pool, &state.allowed_emails, &email).await? =>
+            {
+                Some(email)
+            }
+            _ => None,
+        },
+        None => session
+            .get(SESSION_EMAIL_KEY)
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?,
+    };
 
-    if email.is_none() {
+    let Some(email) = email else {
         return Err(AppError::Unauthorized);
-    }
+    };
+    request.extensions_mut().insert(AuthenticatedUser { email });
 
     Ok(next.run(request).await)
 }
@@ -11,9 +11,16 @@ use serde::Deserialize;
 use tower_sessions::Session;
 
 use crate::error::AppError;
+use crate::services::users;
 use crate::state::AppState;
 
-const SESSION_EMAIL_KEY: &str = "email";
+pub(crate) const SESSION_EMAIL_KEY: &str = "email";
+
+/// Who made a request, by session or API token; set by [`middleware::require_auth`].
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub email: String,
+}
 
 #[derive(Deserialize)]
 pub struct CallbackQuery {
@@ -61,7 +68,7 @@ pub async fn google_callback(
         .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
-    if !state.allowed_emails.contains(&user_info.email) {
+    if !users::is_allowed(&state.pool, &state.allowed_emails, &user_info.email).await? {
         return Err(AppError::Forbidden(format!(
             "Email {} is not allowed",
             user_info.email
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use clap::Parser;
+use sqlx::postgres::PgPoolOptions;
+
+use eemee_backend::cli::{self, Cli, Context};
+use eemee_backend::config::AdminConfig;
+use eemee_backend::logging;
+use eemee_backend::services::embedding::Embedder;
+use eemee_backend::services::usage::MeteredEmbedder;
+
+#[tokio::main]
+async fn main() {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+    let config = AdminConfig::load().unwrap_or_else(|e| exit(e));
+    logging::init_stderr(config.log_format);
+    // Checked before connecting, so a missing API key fails fast
+    let embedding = cli
+        .command
+        .needs_embedder()
+        .then(|| config.embedding().unwrap_or_else(|e| exit(e)));
+
+    let pool = PgPoolOptions::new()
+        .max_connections(config.database.max_connections)
+        .connect(&config.database.url)
+        .await
+        .unwrap_or_else(|e| exit(format!("Cannot connect to the database: {e}")));
+
+    let ctx = Context {
+        pool: pool.clone(),
+        embedder: embedding.map(|embedding| {
+            Arc::new(MeteredEmbedder::new(embedding.embedder(), pool.clone())) as Arc<dyn Embedder>
+        }),
+        allowed_emails: config.allowed_emails,
+    };
+    let result = cli::run(cli.command, &ctx).await;
+    pool.close().await;
+    match result {
+        Ok(report) => println!("{report}"),
+        Err(e) => exit(e),
+    }
+}
+
+fn exit(error: impl std::fmt::Display) -> ! {
+    eprintln!("{error}");
+    std::process::exit(1);
+}
@@ -0,0 +1,629 @@
+//! `eemee-admin`: maintenance commands run straight against the database, without the
+//! HTTP server. It reads the server's configuration and goes through the same services,
+//! so imports, restores and re-embedding behave exactly as they do over the API.
+
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use sqlx::PgPool;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::backup::RestoreMode;
+use crate::models::phrase::{ExportQuery, MarkdownGroup, Phrase, PhraseFilter};
+use crate::models::stats::LibraryStats;
+use crate::services::anki::ApkgWriter;
+use crate::services::embedding::Embedder;
+use crate::services::export::{Encoder, ExportFormat};
+use crate::services::markdown::VaultWriter;
+use crate::services::{backup, db, import, jobs, tokens, trash, users};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "eemee-admin",
+    version,
+    about = "Maintenance tasks for an eemee library"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Apply pending database migrations.
+    Migrate,
+    /// Add files to the library.
+    #[command(subcommand)]
+    Import(ImportCommand),
+    /// Write the library to a file.
+    #[command(subcommand)]
+    Export(ExportCommand),
+    /// Queue meanings to be embedded again with the configured model.
+    ///
+    /// Their embeddings are cleared, so search leaves them out until they are embedded.
+    Reembed {
+        /// Every meaning, not only those from another model or that failed.
+        #[arg(long)]
+        all: bool,
+        /// Embed them now rather than leaving them to the server's workers.
+        #[arg(long)]
+        run: bool,
+    },
+    /// Phrases deleted through the API, kept until they are vacuumed.
+    #[command(subcommand)]
+    Trash(TrashCommand),
+    /// Accounts allowed to log in besides those in the configuration.
+    #[command(subcommand)]
+    Users(UsersCommand),
+    /// Bearer tokens for scripts calling the API.
+    #[command(subcommand)]
+    Tokens(TokensCommand),
+    /// Print library size and embedding progress.
+    Stats {
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCommand {
+    /// Highlights of a Kindle `My Clippings.txt`, as drafts.
+    Kindle { file: PathBuf },
+    /// A backup from `export backup` or `GET /api/admin/backup`.
+    Backup {
+        file: PathBuf,
+        /// `merge` upserts by id; `replace` deletes the library first.
+        #[arg(long, default_value = "merge", value_parser = snake_case::<RestoreMode>)]
+        mode: RestoreMode,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ExportCommand {
+    /// Phrases, optionally filtered, in any format `GET /api/export` supports.
+    Phrases {
+        output: PathBuf,
+        #[arg(long, default_value = "json",
+              value_parser = ["json", "ndjson", "csv", "anki", "markdown"])]
+        format: String,
+        /// Markdown only: one file per `phrase` or per `source`.
+        #[arg(long, default_value = "phrase", value_parser = snake_case::<MarkdownGroup>)]
+        group: MarkdownGroup,
+        /// Only phrases created at or after this RFC 3339 instant.
+        #[arg(long)]
+        from: Option<DateTime<Utc>>,
+        /// Only phrases created before this RFC 3339 instant.
+        #[arg(long)]
+        to: Option<DateTime<Utc>>,
+        #[arg(long)]
+        collection: Option<Uuid>,
+        /// Phrases must carry every given tag.
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Case-insensitive substring of the source.
+        #[arg(long)]
+        source: Option<String>,
+    },
+    /// The whole library with its embeddings, restorable with `import backup`.
+    Backup { output: PathBuf },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrashCommand {
+    List,
+    /// Put a phrase back, into the collections and links that still exist.
+    Restore {
+        id: Uuid,
+    },
+    /// Delete for good what has been in the trash this long.
+    Vacuum {
+        #[arg(long, default_value_t = 30)]
+        older_than_days: u32,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum UsersCommand {
+    List,
+    Add {
+        email: String,
+    },
+    /// Existing sessions stay logged in until they expire.
+    Remove {
+        email: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TokensCommand {
+    List,
+    /// Prints the token, which cannot be shown again.
+    Create {
+        /// The allowed user the token acts as.
+        email: String,
+        /// What the token is for, e.g. the script using it.
+        #[arg(long)]
+        name: String,
+    },
+    /// Takes effect on the token's next request.
+    Revoke {
+        id: Uuid,
+    },
+}
+
+impl Command {
+    /// Whether the command calls the embedding API, so needs its settings. Kindle
+    /// highlights arrive as drafts, which are not embedded until they are kept.
+    pub fn needs_embedder(&self) -> bool {
+        matches!(
+            self,
+            Command::Reembed { .. } | Command::Import(ImportCommand::Backup { .. })
+        )
+    }
+}
+
+/// Parses a value the way the API's query strings do, e.g. `replace` for a `RestoreMode`.
+fn snake_case<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).map_err(|e| e.to_string())
+}
+
+/// What the commands run against.
+pub struct Context {
+    pub pool: PgPool,
+    /// Only set for commands that [need one](Command::needs_embedder).
+    pub embedder: Option<Arc<dyn Embedder>>,
+    /// `allowed_emails` from the configuration.
+    pub allowed_emails: Vec<String>,
+}
+
+impl Context {
+    fn embedder(&self) -> Result<&dyn Embedder, AppError> {
+        self.embedder
+            .as_deref()
+            .ok_or_else(|| AppError::Internal("No embedder configured".to_string()))
+    }
+}
+
+/// Runs `command`, returning the report to print.
+pub async fn run(command: Command, ctx: &Context) -> Result<String, AppError> {
+    match command {
+        Command::Migrate => migrate(&ctx.pool).await,
+        Command::Import(ImportCommand::Kindle { file }) => {
+            let clippings = tokio::fs::read_to_string(&file)
+                .await
+                .map_err(|e| io_error(&file, e))?;
+            let summary = import::import_kindle(&ctx.pool, &clippings).await?;
+            Ok(format!(
+                "Imported {} drafts ({} duplicates, {} skipped)",
+                summary.imported, summary.duplicates, summary.skipped
+            ))
+        }
+        Command::Import(ImportCommand::Backup { file, mode }) => {
+            let bytes = tokio::fs::read(&file)
+                .await
+                .map_err(|e| io_error(&file, e))?;
+            let archive = backup::parse(&bytes)?;
+            backup::validate(&archive, ctx.embedder()?.model())?;
+            let summary = backup::restore(&ctx.pool, &archive, mode).await?;
+            Ok(format!(
+                "Restored ({mode:?}): {} phrases, {} meanings, {} collections, {} links, \
+                 {} saved searches",
+                summary.phrases,
+                summary.meanings,
+                summary.collections,
+                summary.links,
+                summary.saved_searches
+            ))
+        }
+        Command::Export(ExportCommand::Phrases {
+            output,
+            format,
+            group,
+            from,
+            to,
+            collection,
+            tags,
+            source,
+        }) => {
+            let query = ExportQuery {
+                format,
+                group,
+                from,
+                to,
+                filter: PhraseFilter {
+                    collection_id: collection,
+                    tags,
+                    source,
+                },
+            };
+            let count = export_phrases(&ctx.pool, &query, &output).await?;
+            Ok(format!("Exported {count} phrases to {}", output.display()))
+        }
+        Command::Export(ExportCommand::Backup { output }) => {
+            let file = tokio::fs::File::create(&output)
+                .await
+                .map_err(|e| io_error(&output, e))?;
+            let mut file = BufWriter::new(file);
+            // Written as it is read, like the HTTP download
+            let sink = futures::sink::unfold(&mut file, async |file, bytes: Vec<u8>| {
+                file.write_all(&bytes).await?;
+                Ok::<_, std::io::Error>(file)
+            });
+            let phrases = backup::write_backup(&ctx.pool, &mut pin!(sink)).await?;
+            file.flush().await.map_err(|e| io_error(&output, e))?;
+            Ok(format!(
+                "Backed up {phrases} phrases to {}",
+                output.display()
+            ))
+        }
+        Command::Reembed { all, run } => {
+            let embedder = ctx.embedder()?;
+            let model = embedder.model();
+            let queued = jobs::requeue_meanings(&ctx.pool, model, all).await?;
+            let mut report = format!("Queued {queued} meanings for {model}");
+            if run {
+                let ran = jobs::drain(&ctx.pool, embedder).await?;
+                report.push_str(&format!("\nRan {ran} embedding jobs"));
+            }
+            Ok(report)
+        }
+        Command::Trash(command) => manage_trash(command, ctx).await,
+        Command::Users(command) => manage_users(command, ctx).await,
+        Command::Tokens(command) => manage_tokens(command, ctx).await,
+        Command::Stats { json } => {
+            let stats = db::library_stats(&ctx.pool).await?;
+            if json {
+                serde_json::to_string_pretty(&stats).map_err(|e| AppError::Internal(e.to_string()))
+            } else {
+                Ok(format_stats(&stats))
+            }
+        }
+    }
+}
+
+async fn migrate(pool: &PgPool) -> Result<String, AppError> {
+    let before = applied_migrations(pool).await?;
+    db::MIGRATOR
+        .run(pool)
+        .await
+        .map_err(|e| AppError::Internal(format!("Migration failed: {e}")))?;
+    let applied = applied_migrations(pool).await? - before;
+    Ok(format!("Applied {applied} migrations"))
+}
+
+async fn applied_migrations(pool: &PgPool) -> Result<i64, AppError> {
+    // The table only exists once the first migration has run
+    let count = sqlx::query_scalar(
+        "SELECT CASE WHEN to_regclass('_sqlx_migrations') IS NULL THEN 0
+                     ELSE (SELECT COUNT(*) FROM _sqlx_migrations WHERE success) END",
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+/// Writes phrases to `path` as `GET /api/export` would, returning how many.
+async fn export_phrases(
+    pool: &PgPool,
+    query: &ExportQuery,
+    path: &Path,
+) -> Result<usize, AppError> {
+    let mut rows = db::stream_phrases(pool, &query.filter, query.from, query.to);
+    let mut count = 0;
+    let bytes = match query.format.as_str() {
+        "anki" => {
+            let mut writer = ApkgWriter::create().await?;
+            while let Some(row) = rows.next().await {
+                writer.add(&Phrase::from(row?)).await?;
+                count += 1;
+            }
+            writer.finish().await?
+        }
+        "markdown" => {
+            let mut writer = VaultWriter::new(query.group);
+            while let Some(row) = rows.next().await {
+                writer.add(Phrase::from(row?))?;
+                count += 1;
+            }
+            writer.finish()?
+        }
+        format => {
+            // Streamed like the HTTP export, so a large library is never held in memory
+            let mut encoder = Encoder::new(ExportFormat::parse(format));
+            let file = tokio::fs::File::create(path)
+                .await
+                .map_err(|e| io_error(path, e))?;
+            let mut file = BufWriter::new(file);
+            let write_err = |e| io_error(path, e);
+            file.write_all(&encoder.header()?)
+                .await
+                .map_err(write_err)?;
+            while let Some(row) = rows.next().await {
+                let chunk = encoder.row(&Phrase::from(row?))?;
+                file.write_all(&chunk).await.map_err(write_err)?;
+                count += 1;
+            }
+            file.write_all(&encoder.footer()).await.map_err(write_err)?;
+            file.flush().await.map_err(write_err)?;
+            return Ok(count);
+        }
+    };
+    write_file(path, &bytes).await?;
+    Ok(count)
+}
+
+async fn manage_trash(command: TrashCommand, ctx: &Context) -> Result<String, AppError> {
+    match command {
+        TrashCommand::List => {
+            let entries = trash::list_trash(&ctx.pool).await?;
+            if entries.is_empty() {
+                return Ok("The trash is empty".to_string());
+            }
+            let lines: Vec<String> = entries
+                .iter()
+                .map(|entry| {
+                    format!(
+                        "{}\t{}\tdeleted {}",
+                        entry.id,
+                        entry.phrase,
+                        entry.deleted_at.format("%Y-%m-%d")
+                    )
+                })
+                .collect();
+            Ok(lines.join("\n"))
+        }
+        TrashCommand::Restore { id } => {
+            let restored = trash::restore_phrase(&ctx.pool, id).await?;
+            Ok(format!("Restored {:?}", restored.phrase.phrase))
+        }
+        TrashCommand::Vacuum { older_than_days } => {
+            let deleted = trash::vacuum(&ctx.pool, older_than_days).await?;
+            Ok(format!(
+                "Deleted {deleted} phrases trashed at least {older_than_days} days ago"
+            ))
+        }
+    }
+}
+
+async fn manage_users(command: UsersCommand, ctx: &Context) -> Result<String, AppError> {
+    match command {
+        UsersCommand::List => {
+            let mut lines: Vec<String> = ctx
+                .allowed_emails
+                .iter()
+                .map(|email| format!("{email}\t(configuration)"))
+                .collect();
+            for user in users::list_allowed_users(&ctx.pool).await? {
+                lines.push(format!(
+                    "{}\tadded {}",
+                    user.email,
+                    user.created_at.format("%Y-%m-%d")
+                ));
+            }
+            if lines.is_empty() {
+                return Ok("No users are allowed to log in".to_string());
+            }
+            Ok(lines.join("\n"))
+        }
+        UsersCommand::Add { email } => {
+            if users::add_allowed_user(&ctx.pool, &email).await? {
+                Ok(format!("Allowed {email} to log in"))
+            } else {
+                Ok(format!("{email} was already allowed"))
+            }
+        }
+        UsersCommand::Remove { email } => {
+            if ctx.allowed_emails.contains(&email) {
+                return Err(AppError::BadRequest(
+                    format!(
+                        "{email} is allowed by the configuration; remove it from allowed_emails"
+                    )
+                    .into(),
+                ));
+            }
+            users::remove_allowed_user(&ctx.pool, &email).await?;
+            Ok(format!("{email} can no longer log in"))
+        }
+    }
+}
+
+async fn manage_tokens(command: TokensCommand, ctx: &Context) -> Result<String, AppError> {
+    match command {
+        TokensCommand::List => {
+            let tokens = tokens::list_tokens(&ctx.pool).await?;
+            if tokens.is_empty() {
+                return Ok("No API tokens".to_string());
+            }
+            let lines: Vec<String> = tokens
+                .iter()
+                .map(|token| {
+                    let used = token.last_used_at.map_or("never used".to_string(), |at| {
+                        format!("used {}", at.format("%Y-%m-%d"))
+                    });
+                    format!(
+                        "{}\t{}\t{}\tcreated {}, {used}",
+                        token.id,
+                        token.email,
+                        token.name,
+                        token.created_at.format("%Y-%m-%d")
+                    )
+                })
+                .collect();
+            Ok(lines.join("\n"))
+        }
+        TokensCommand::Create { email, name } => {
+            let email = users::normalize_email(&email)?;
+            if !users::is_allowed(&ctx.pool, &ctx.allowed_emails, &email).await? {
+                return Err(AppError::BadRequest(
+                    format!("{email} is not allowed to log in; add it with `users add` first")
+                        .into(),
+                ));
+            }
+            let (token, secret) = tokens::create_token(&ctx.pool, &email, &name).await?;
+            Ok(format!(
+                "Created token {} for {email}. Send it as `Authorization: Bearer <token>`; \
+                 it is not shown again:\n{secret}",
+                token.id
+            ))
+        }
+        TokensCommand::Revoke { id } => {
+            tokens::revoke_token(&ctx.pool, id).await?;
+            Ok(format!("Revoked token {id}"))
+        }
+    }
+}
+
+pub fn format_stats(stats: &LibraryStats) -> String {
+    let mut lines = vec![
+        format!(
+            "Phrases         {} ({} drafts)",
+            stats.phrases, stats.drafts
+        ),
+        format!(
+            "Meanings        {} ({} pending, {} failed)",
+            stats.meanings, stats.pending_meanings, stats.failed_meanings
+        ),
+    ];
+    for model in &stats.embedding_models {
+        lines.push(format!("  {}  {}", model.model, model.meanings));
+    }
+    lines.extend([
+        format!("Collections     {}", stats.collections),
+        format!("Links           {}", stats.links),
+        format!("Saved searches  {}", stats.saved_searches),
+        format!("Search history  {}", stats.search_history),
+        format!(
+            "Embedding jobs  {} queued, {} dead",
+            stats.queued_jobs, stats.dead_jobs
+        ),
+    ]);
+    lines.join("\n")
+}
+
+async fn write_file(path: &Path, bytes: &[u8]) -> Result<(), AppError> {
+    tokio::fs::write(path, bytes)
+        .await
+        .map_err(|e| io_error(path, e))
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> AppError {
+    AppError::Internal(format!("{}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+    use crate::models::stats::ModelCount;
+
+    fn parse(args: &[&str]) -> Result<Command, clap::Error> {
+        Cli::try_parse_from(std::iter::once("eemee-admin").chain(args.iter().copied()))
+            .map(|cli| cli.command)
+    }
+
+    #[test]
+    fn definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_export_filters() {
+        let command = parse(&[
+            "export",
+            "phrases",
+            "out.md",
+            "--format",
+            "markdown",
+            "--group",
+            "source",
+            "--tag",
+            "idiom",
+            "--tag",
+            "work",
+            "--from",
+            "2025-01-01T00:00:00Z",
+        ])
+        .unwrap();
+        let Command::Export(ExportCommand::Phrases {
+            output,
+            format,
+            group,
+            from,
+            tags,
+            ..
+        }) = command
+        else {
+            panic!("{command:?}");
+        };
+        assert_eq!(output, PathBuf::from("out.md"));
+        assert_eq!(format, "markdown");
+        assert_eq!(group, MarkdownGroup::Source);
+        assert_eq!(from.unwrap().to_rfc3339(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(tags, ["idiom", "work"]);
+    }
+
+    #[test]
+    fn rejects_unknown_values() {
+        assert!(parse(&["export", "phrases", "out", "--format", "xml"]).is_err());
+        assert!(parse(&["import", "backup", "b.json", "--mode", "wipe"]).is_err());
+    }
+
+    #[test]
+    fn restore_mode_defaults_to_merge() {
+        let Command::Import(ImportCommand::Backup { mode, .. }) =
+            parse(&["import", "backup", "b.json"]).unwrap()
+        else {
+            panic!();
+        };
+        assert_eq!(mode, RestoreMode::Merge);
+        let Command::Import(ImportCommand::Backup { mode, .. }) =
+            parse(&["import", "backup", "b.json", "--mode", "replace"]).unwrap()
+        else {
+            panic!();
+        };
+        assert_eq!(mode, RestoreMode::Replace);
+    }
+
+    #[test]
+    fn trash_vacuum_keeps_a_month_by_default() {
+        let command = parse(&["trash", "vacuum"]).unwrap();
+        assert!(!command.needs_embedder());
+        let Command::Trash(TrashCommand::Vacuum { older_than_days }) = command else {
+            panic!("{command:?}");
+        };
+        assert_eq!(older_than_days, 30);
+    }
+
+    #[test]
+    fn formats_stats() {
+        let stats = LibraryStats {
+            phrases: 12,
+            drafts: 2,
+            meanings: 20,
+            pending_meanings: 1,
+            failed_meanings: 0,
+            collections: 3,
+            links: 4,
+            saved_searches: 1,
+            search_history: 9,
+            queued_jobs: 1,
+            dead_jobs: 0,
+            embedding_models: vec![ModelCount {
+                model: "text-embedding-3-large".to_string(),
+                meanings: 19,
+            }],
+        };
+        let report = format_stats(&stats);
+        assert!(report.starts_with("Phrases         12 (2 drafts)\n"));
+        assert!(report.contains("\n  text-embedding-3-large  19\n"));
+        assert!(report.ends_with("Embedding jobs  1 queued, 0 dead"));
+    }
+}
@@ -0,0 +1,721 @@
+//! Settings, read from an optional TOML file named by `CONFIG_FILE` and from environment
+//! variables, which take precedence. Everything is checked up front and every problem is
+//! reported at once, rather than the server dying on the first missing variable.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::HeaderValue;
+use oauth2::basic::BasicClient;
+use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl};
+use serde::Deserialize;
+
+use crate::logging::LogFormat;
+use crate::services::embedding::{Embedder, EmbeddingService};
+use crate::state::OAuthClient;
+
+const GOOGLE_AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub port: u16,
+    /// Built frontend, served for every path outside `/api`.
+    pub static_dir: String,
+    /// Google accounts allowed to log in, besides those added with `eemee-admin users`.
+    pub allowed_emails: Vec<String>,
+    pub log_format: LogFormat,
+    /// How long in-flight requests and background work get to finish after SIGTERM.
+    pub shutdown_timeout: Duration,
+    /// Serve Prometheus metrics at `/metrics`. Off by default, as the endpoint has no
+    /// login; turn it on where only the scraper can reach the port.
+    pub expose_metrics: bool,
+    pub database: DatabaseConfig,
+    pub session: SessionConfig,
+    pub cors: CorsConfig,
+    pub oauth: OAuthConfig,
+    pub embedding: EmbeddingConfig,
+    pub health: HealthConfig,
+    pub limits: LimitsConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    /// Send the session cookie over HTTPS only; needed in production.
+    pub secure_cookies: bool,
+    /// Sessions expire after this many days without a request.
+    pub lifetime_days: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CorsConfig {
+    /// Origins allowed to call the API with credentials, e.g. `http://localhost:5173`.
+    /// Empty when the frontend is served from the same origin.
+    pub origins: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub auth_url: String,
+    pub token_url: String,
+    pub redirect_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmbeddingConfig {
+    pub provider: EmbeddingProvider,
+    pub api_key: String,
+    /// Background workers embedding meanings.
+    pub workers: usize,
+    /// Longest we wait for one embedding API call.
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    /// Limit on each readiness check.
+    pub timeout: Duration,
+    /// Also check that the embedding API accepts our key on each readiness check. The
+    /// check is not billed, but is a request to the provider on every probe.
+    pub probe_embedder: bool,
+}
+
+/// Caps on what requests can spend on the embedding API.
+#[derive(Debug, Clone)]
+pub struct LimitsConfig {
+    /// Requests per minute each user may make to routes that embed text; 0 for no limit.
+    pub per_user_per_minute: u32,
+    /// The same, across all users.
+    pub global_per_minute: u32,
+    /// Embedding tokens per UTC day, after which semantic search is refused.
+    pub daily_token_budget: Option<u64>,
+    /// Embedding tokens per UTC calendar month.
+    pub monthly_token_budget: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingProvider {
+    OpenAi,
+}
+
+impl FromStr for EmbeddingProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "openai" => Ok(EmbeddingProvider::OpenAi),
+            other => Err(format!("Unknown embedding provider: {other}")),
+        }
+    }
+}
+
+/// Every problem found while loading the configuration.
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for problem in &self.0 {
+            write!(f, "\n  - {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// What `eemee-admin` needs before it knows which command runs: most commands only touch
+/// the database, so the OAuth and embedding settings are not required up front.
+#[derive(Debug, Clone)]
+pub struct AdminConfig {
+    pub log_format: LogFormat,
+    pub allowed_emails: Vec<String>,
+    pub database: DatabaseConfig,
+    embedding: RawEmbedding,
+}
+
+/// The config file, with every setting optional so the environment can fill gaps.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawConfig {
+    port: Option<u16>,
+    static_dir: Option<String>,
+    allowed_emails: Option<Vec<String>>,
+    log_format: Option<String>,
+    shutdown_timeout_secs: Option<u64>,
+    expose_metrics: Option<bool>,
+    database: RawDatabase,
+    session: RawSession,
+    cors: RawCors,
+    oauth: RawOAuth,
+    embedding: RawEmbedding,
+    health: RawHealth,
+    limits: RawLimits,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawDatabase {
+    url: Option<String>,
+    max_connections: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawSession {
+    secure_cookies: Option<bool>,
+    lifetime_days: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawCors {
+    origins: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawOAuth {
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    auth_url: Option<String>,
+    token_url: Option<String>,
+    redirect_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawEmbedding {
+    provider: Option<String>,
+    api_key: Option<String>,
+    workers: Option<usize>,
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawHealth {
+    timeout_secs: Option<u64>,
+    probe_embedder: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawLimits {
+    per_user_per_minute: Option<u32>,
+    global_per_minute: Option<u32>,
+    daily_token_budget: Option<u64>,
+    monthly_token_budget: Option<u64>,
+}
+
+impl Config {
+    /// Loads the file named by `CONFIG_FILE`, if set, then the process environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        let file = read_config_file()?;
+        Self::from_sources(file.as_deref(), |name| std::env::var(name).ok())
+    }
+
+    /// Builds the configuration from TOML text and a variable lookup, the latter winning.
+    pub fn from_sources(
+        file: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut problems = Vec::new();
+        let raw = RawConfig::read(file, &env, &mut problems);
+        let config = validate(raw, &mut problems);
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(problems))
+        }
+    }
+}
+
+impl AdminConfig {
+    /// Loads the same sources as [`Config::load`].
+    pub fn load() -> Result<Self, ConfigError> {
+        let file = read_config_file()?;
+        Self::from_sources(file.as_deref(), |name| std::env::var(name).ok())
+    }
+
+    /// Like [`Config::from_sources`], checking only the settings every command uses.
+    pub fn from_sources(
+        file: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut problems = Vec::new();
+        let raw = RawConfig::read(file, &env, &mut problems);
+        let config = AdminConfig {
+            log_format: validate_log_format(raw.log_format, &mut problems),
+            allowed_emails: trimmed(raw.allowed_emails.unwrap_or_default()),
+            database: validate_database(raw.database, &mut problems),
+            embedding: raw.embedding,
+        };
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(problems))
+        }
+    }
+
+    /// The embedding settings, checked now for the commands that call the provider.
+    pub fn embedding(&self) -> Result<EmbeddingConfig, ConfigError> {
+        let mut problems = Vec::new();
+        let embedding = validate_embedding(self.embedding.clone(), &mut problems);
+        if problems.is_empty() {
+            Ok(embedding)
+        } else {
+            Err(ConfigError(problems))
+        }
+    }
+}
+
+fn read_config_file() -> Result<Option<String>, ConfigError> {
+    match std::env::var("CONFIG_FILE") {
+        Ok(path) => std::fs::read_to_string(&path)
+            .map(Some)
+            .map_err(|e| ConfigError(vec![format!("Cannot read {path}: {e}")])),
+        Err(_) => Ok(None),
+    }
+}
+
+impl RawConfig {
+    /// Parses the file, if any, and applies the environment over it.
+    fn read(
+        file: Option<&str>,
+        env: &impl Fn(&str) -> Option<String>,
+        problems: &mut Vec<String>,
+    ) -> Self {
+        let mut raw = match file.map(toml::from_str::<RawConfig>) {
+            Some(Ok(raw)) => raw,
+            Some(Err(e)) => {
+                problems.push(format!("Config file: {}", e.message()));
+                RawConfig::default()
+            }
+            None => RawConfig::default(),
+        };
+
+        let mut overlay = Overlay { env, problems };
+        overlay.parse("PORT", &mut raw.port);
+        overlay.parse("STATIC_DIR", &mut raw.static_dir);
+        overlay.list("ALLOWED_EMAILS", &mut raw.allowed_emails);
+        overlay.parse("LOG_FORMAT", &mut raw.log_format);
+        overlay.parse("SHUTDOWN_TIMEOUT_SECS", &mut raw.shutdown_timeout_secs);
+        overlay.parse("EXPOSE_METRICS", &mut raw.expose_metrics);
+        overlay.parse("DATABASE_URL", &mut raw.database.url);
+        overlay.parse(
+            "DATABASE_MAX_CONNECTIONS",
+            &mut raw.database.max_connections,
+        );
+        overlay.parse("SESSION_SECURE_COOKIES", &mut raw.session.secure_cookies);
+        overlay.parse("SESSION_LIFETIME_DAYS", &mut raw.session.lifetime_days);
+        overlay.list("CORS_ORIGINS", &mut raw.cors.origins);
+        overlay.parse("GOOGLE_CLIENT_ID", &mut raw.oauth.client_id);
+        overlay.parse("GOOGLE_CLIENT_SECRET", &mut raw.oauth.client_secret);
+        overlay.parse("OAUTH_REDIRECT_URL", &mut raw.oauth.redirect_url);
+        overlay.parse("EMBEDDING_PROVIDER", &mut raw.embedding.provider);
+        overlay.parse("OPENAI_API_KEY", &mut raw.embedding.api_key);
+        overlay.parse("EMBEDDING_WORKERS", &mut raw.embedding.workers);
+        overlay.parse("EMBEDDING_TIMEOUT_SECS", &mut raw.embedding.timeout_secs);
+        overlay.parse("HEALTH_TIMEOUT_SECS", &mut raw.health.timeout_secs);
+        overlay.parse("HEALTH_PROBE_EMBEDDER", &mut raw.health.probe_embedder);
+        overlay.parse(
+            "RATE_LIMIT_PER_USER_PER_MINUTE",
+            &mut raw.limits.per_user_per_minute,
+        );
+        overlay.parse(
+            "RATE_LIMIT_GLOBAL_PER_MINUTE",
+            &mut raw.limits.global_per_minute,
+        );
+        overlay.parse(
+            "EMBEDDING_DAILY_TOKEN_BUDGET",
+            &mut raw.limits.daily_token_budget,
+        );
+        overlay.parse(
+            "EMBEDDING_MONTHLY_TOKEN_BUDGET",
+            &mut raw.limits.monthly_token_budget,
+        );
+        raw
+    }
+}
+
+impl OAuthConfig {
+    pub fn client(&self) -> OAuthClient {
+        BasicClient::new(ClientId::new(self.client_id.clone()))
+            .set_client_secret(ClientSecret::new(self.client_secret.clone()))
+            .set_auth_uri(AuthUrl::new(self.auth_url.clone()).expect("validated on load"))
+            .set_token_uri(TokenUrl::new(self.token_url.clone()).expect("validated on load"))
+            .set_redirect_uri(
+                RedirectUrl::new(self.redirect_url.clone()).expect("validated on load"),
+            )
+    }
+}
+
+impl EmbeddingConfig {
+    /// The configured provider's client.
+    pub fn embedder(&self) -> Arc<dyn Embedder> {
+        match self.provider {
+            EmbeddingProvider::OpenAi => {
+                Arc::new(EmbeddingService::new(self.api_key.clone(), self.timeout))
+            }
+        }
+    }
+}
+
+/// Applies environment variables over the file's values, noting any that don't parse.
+struct Overlay<'a, F> {
+    env: &'a F,
+    problems: &'a mut Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Overlay<'_, F> {
+    fn parse<T: FromStr>(&mut self, name: &str, slot: &mut Option<T>) {
+        if let Some(value) = (self.env)(name) {
+            match value.trim().parse() {
+                Ok(parsed) => *slot = Some(parsed),
+                Err(_) => self
+                    .problems
+                    .push(format!("{name}: invalid value {value:?}")),
+            }
+        }
+    }
+
+    /// A comma-separated list.
+    fn list(&mut self, name: &str, slot: &mut Option<Vec<String>>) {
+        if let Some(value) = (self.env)(name) {
+            *slot = Some(value.split(',').map(str::to_string).collect());
+        }
+    }
+}
+
+fn validate(raw: RawConfig, problems: &mut Vec<String>) -> Config {
+    let port = raw.port.unwrap_or(16789);
+
+    let log_format = validate_log_format(raw.log_format, problems);
+    let database = validate_database(raw.database, problems);
+
+    let session = SessionConfig {
+        secure_cookies: raw.session.secure_cookies.unwrap_or(false),
+        lifetime_days: raw.session.lifetime_days.unwrap_or(30),
+    };
+    if session.lifetime_days < 1 {
+        problems.push("session.lifetime_days must be at least 1".to_string());
+    }
+
+    let cors = CorsConfig {
+        origins: trimmed(raw.cors.origins.unwrap_or_default()),
+    };
+    for origin in &cors.origins {
+        let looks_like_origin = (origin.starts_with("http://") || origin.starts_with("https://"))
+            && !origin.ends_with('/')
+            && HeaderValue::from_str(origin).is_ok();
+        if !looks_like_origin {
+            problems.push(format!(
+                "cors.origins: {origin:?} is not an origin like https://example.com"
+            ));
+        }
+    }
+
+    let oauth = OAuthConfig {
+        client_id: required(
+            raw.oauth.client_id,
+            "oauth.client_id",
+            "GOOGLE_CLIENT_ID",
+            problems,
+        ),
+        client_secret: required(
+            raw.oauth.client_secret,
+            "oauth.client_secret",
+            "GOOGLE_CLIENT_SECRET",
+            problems,
+        ),
+        auth_url: raw
+            .oauth
+            .auth_url
+            .unwrap_or_else(|| GOOGLE_AUTH_URL.to_string()),
+        token_url: raw
+            .oauth
+            .token_url
+            .unwrap_or_else(|| GOOGLE_TOKEN_URL.to_string()),
+        redirect_url: raw
+            .oauth
+            .redirect_url
+            .unwrap_or_else(|| format!("http://localhost:{port}/api/auth/callback")),
+    };
+    for (key, url) in [
+        ("oauth.auth_url", &oauth.auth_url),
+        ("oauth.token_url", &oauth.token_url),
+        ("oauth.redirect_url", &oauth.redirect_url),
+    ] {
+        if let Err(e) = oauth2::url::Url::parse(url) {
+            problems.push(format!("{key}: {e}"));
+        }
+    }
+
+    let embedding = validate_embedding(raw.embedding, problems);
+
+    let health = HealthConfig {
+        timeout: Duration::from_secs(raw.health.timeout_secs.unwrap_or(2)),
+        probe_embedder: raw.health.probe_embedder.unwrap_or(false),
+    };
+    if health.timeout.is_zero() {
+        problems.push("health.timeout_secs must be at least 1".to_string());
+    }
+
+    let limits = LimitsConfig {
+        per_user_per_minute: raw.limits.per_user_per_minute.unwrap_or(60),
+        global_per_minute: raw.limits.global_per_minute.unwrap_or(120),
+        daily_token_budget: raw.limits.daily_token_budget,
+        monthly_token_budget: raw.limits.monthly_token_budget,
+    };
+    for (key, budget) in [
+        ("limits.daily_token_budget", limits.daily_token_budget),
+        ("limits.monthly_token_budget", limits.monthly_token_budget),
+    ] {
+        if budget == Some(0) {
+            problems.push(format!(
+                "{key} must be at least 1; leave it unset for no budget"
+            ));
+        }
+    }
+
+    Config {
+        port,
+        static_dir: raw
+            .static_dir
+            .unwrap_or_else(|| "../frontend/dist".to_string()),
+        allowed_emails: trimmed(raw.allowed_emails.unwrap_or_default()),
+        log_format,
+        shutdown_timeout: Duration::from_secs(raw.shutdown_timeout_secs.unwrap_or(30)),
+        expose_metrics: raw.expose_metrics.unwrap_or(false),
+        database,
+        session,
+        cors,
+        oauth,
+        embedding,
+        health,
+        limits,
+    }
+}
+
+fn validate_log_format(raw: Option<String>, problems: &mut Vec<String>) -> LogFormat {
+    raw.map(|f| {
+        f.parse().unwrap_or_else(|e| {
+            problems.push(format!("log_format: {e}"));
+            LogFormat::default()
+        })
+    })
+    .unwrap_or_default()
+}
+
+fn validate_database(raw: RawDatabase, problems: &mut Vec<String>) -> DatabaseConfig {
+    let database = DatabaseConfig {
+        url: required(raw.url, "database.url", "DATABASE_URL", problems),
+        max_connections: raw.max_connections.unwrap_or(5),
+    };
+    if database.max_connections == 0 {
+        problems.push("database.max_connections must be at least 1".to_string());
+    }
+    database
+}
+
+fn validate_embedding(raw: RawEmbedding, problems: &mut Vec<String>) -> EmbeddingConfig {
+    let provider = raw
+        .provider
+        .map(|p| {
+            p.parse().unwrap_or_else(|e| {
+                problems.push(format!("embedding.provider: {e}"));
+                EmbeddingProvider::OpenAi
+            })
+        })
+        .unwrap_or(EmbeddingProvider::OpenAi);
+    let api_key = match provider {
+        EmbeddingProvider::OpenAi => {
+            required(raw.api_key, "embedding.api_key", "OPENAI_API_KEY", problems)
+        }
+    };
+    let embedding = EmbeddingConfig {
+        provider,
+        api_key,
+        workers: raw.workers.unwrap_or(2),
+        timeout: Duration::from_secs(raw.timeout_secs.unwrap_or(30)),
+    };
+    if embedding.workers == 0 {
+        problems.push("embedding.workers must be at least 1".to_string());
+    }
+    if embedding.timeout.is_zero() {
+        problems.push("embedding.timeout_secs must be at least 1".to_string());
+    }
+    embedding
+}
+
+fn required(value: Option<String>, key: &str, env: &str, problems: &mut Vec<String>) -> String {
+    match value.filter(|v| !v.trim().is_empty()) {
+        Some(v) => v,
+        None => {
+            problems.push(format!("{key} (or {env}) is required"));
+            String::new()
+        }
+    }
+}
+
+fn trimmed(values: Vec<String>) -> Vec<String> {
+    values
+        .into_iter()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: Vec<(String, String)> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone())
+    }
+
+    const REQUIRED: &[(&str, &str)] = &[
+        ("DATABASE_URL", "postgres://localhost/eemee"),
+        ("GOOGLE_CLIENT_ID", "id"),
+        ("GOOGLE_CLIENT_SECRET", "secret"),
+        ("OPENAI_API_KEY", "sk-test"),
+    ];
+
+    #[test]
+    fn defaults_from_required_env() {
+        let config = Config::from_sources(None, env(REQUIRED)).unwrap();
+        assert_eq!(config.port, 16789);
+        assert_eq!(config.database.max_connections, 5);
+        assert!(!config.session.secure_cookies);
+        assert_eq!(config.session.lifetime_days, 30);
+        assert!(config.cors.origins.is_empty());
+        assert_eq!(
+            config.oauth.redirect_url,
+            "http://localhost:16789/api/auth/callback"
+        );
+        assert_eq!(config.embedding.provider, EmbeddingProvider::OpenAi);
+        assert_eq!(config.embedding.workers, 2);
+        assert_eq!(config.log_format, LogFormat::Text);
+        assert_eq!(config.shutdown_timeout, Duration::from_secs(30));
+        assert!(!config.expose_metrics);
+        assert_eq!(config.health.timeout, Duration::from_secs(2));
+        assert!(!config.health.probe_embedder);
+        assert_eq!(config.limits.per_user_per_minute, 60);
+        assert_eq!(config.limits.global_per_minute, 120);
+        assert_eq!(config.limits.daily_token_budget, None);
+        assert_eq!(config.limits.monthly_token_budget, None);
+    }
+
+    #[test]
+    fn reads_limits() {
+        let file = r#"
+            [limits]
+            per_user_per_minute = 0
+            monthly_token_budget = 5000000
+        "#;
+        let mut vars = REQUIRED.to_vec();
+        vars.push(("EMBEDDING_DAILY_TOKEN_BUDGET", "200000"));
+        let config = Config::from_sources(Some(file), env(&vars)).unwrap();
+        assert_eq!(config.limits.per_user_per_minute, 0);
+        assert_eq!(config.limits.daily_token_budget, Some(200_000));
+        assert_eq!(config.limits.monthly_token_budget, Some(5_000_000));
+
+        vars.push(("EMBEDDING_MONTHLY_TOKEN_BUDGET", "0"));
+        let err = Config::from_sources(None, env(&vars)).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("limits.monthly_token_budget must be at least 1"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let file = r#"
+            port = 8080
+            expose_metrics = true
+            allowed_emails = ["a@example.com"]
+
+            [database]
+            url = "postgres://file/eemee"
+            max_connections = 20
+
+            [session]
+            secure_cookies = true
+
+            [cors]
+            origins = ["https://app.example.com"]
+        "#;
+        let mut vars = REQUIRED.to_vec();
+        vars.push(("PORT", "9090"));
+        vars.push(("ALLOWED_EMAILS", "b@example.com, c@example.com"));
+        let config = Config::from_sources(Some(file), env(&vars)).unwrap();
+        assert_eq!(config.port, 9090);
+        assert_eq!(config.allowed_emails, ["b@example.com", "c@example.com"]);
+        assert!(config.expose_metrics);
+        assert_eq!(config.database.url, "postgres://localhost/eemee");
+        assert_eq!(config.database.max_connections, 20);
+        assert!(config.session.secure_cookies);
+        assert_eq!(config.cors.origins, ["https://app.example.com"]);
+    }
+
+    #[test]
+    fn reports_every_problem_at_once() {
+        let vars = [
+            ("DATABASE_MAX_CONNECTIONS", "0"),
+            ("SESSION_LIFETIME_DAYS", "soon"),
+            ("CORS_ORIGINS", "example.com"),
+            ("EMBEDDING_PROVIDER", "cohere"),
+            ("EMBEDDING_WORKERS", "0"),
+        ];
+        let err = Config::from_sources(None, env(&vars)).unwrap_err();
+        let text = err.to_string();
+        for expected in [
+            "SESSION_LIFETIME_DAYS: invalid value \"soon\"",
+            "database.url (or DATABASE_URL) is required",
+            "database.max_connections must be at least 1",
+            "cors.origins: \"example.com\"",
+            "oauth.client_id (or GOOGLE_CLIENT_ID) is required",
+            "embedding.provider: Unknown embedding provider: cohere",
+            "embedding.api_key (or OPENAI_API_KEY) is required",
+            "embedding.workers must be at least 1",
+        ] {
+            assert!(text.contains(expected), "missing {expected} in:\n{text}");
+        }
+    }
+
+    #[test]
+    fn unknown_file_keys_rejected() {
+        let err = Config::from_sources(Some("[database]\npool = 3\n"), env(REQUIRED)).unwrap_err();
+        assert!(err.0[0].contains("unknown field `pool`"), "{err}");
+    }
+
+    #[test]
+    fn admin_config_needs_only_the_database() {
+        let vars = [("DATABASE_URL", "postgres://localhost/eemee")];
+        let config = AdminConfig::from_sources(None, env(&vars)).unwrap();
+        assert_eq!(config.database.url, "postgres://localhost/eemee");
+        let err = config.embedding().unwrap_err();
+        assert_eq!(err.0, ["embedding.api_key (or OPENAI_API_KEY) is required"]);
+
+        let config = AdminConfig::from_sources(None, env(REQUIRED)).unwrap();
+        assert_eq!(config.embedding().unwrap().api_key, "sk-test");
+        assert!(AdminConfig::from_sources(None, env(&[])).is_err());
+    }
+}
@@ -1,6 +1,11 @@
-use axum::http::StatusCode;
+use std::time::Duration;
+
+use axum::http::{HeaderValue, StatusCode, header};
 use axum::response::{IntoResponse, Response};
-use serde_json::json;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::request_id;
 
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
@@ -14,7 +19,15 @@ pub enum AppError {
     Forbidden(String),
 
     #[error("Bad request: {0}")]
-    BadRequest(String),
+    BadRequest(BadInput),
+
+    /// Too many requests from this user or from everyone; retry after the given time.
+    #[error("Too many requests; retry in {} s", retry_after_secs(*retry_after))]
+    RateLimited { retry_after: Duration },
+
+    /// An embedding budget is used up until its period ends.
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
 
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
@@ -26,6 +39,63 @@ pub enum AppError {
     Internal(String),
 }
 
+/// What was wrong with a bad request, and the span of the input at fault when one is.
+#[derive(Debug)]
+pub struct BadInput {
+    pub message: String,
+    pub position: Option<ErrorPosition>,
+}
+
+impl BadInput {
+    /// A bad request caused by the chars `start..end` of the input.
+    pub fn at(message: impl Into<String>, start: usize, end: usize) -> Self {
+        BadInput {
+            message: message.into(),
+            position: Some(ErrorPosition { start, end }),
+        }
+    }
+}
+
+impl From<String> for BadInput {
+    fn from(message: String) -> Self {
+        BadInput {
+            message,
+            position: None,
+        }
+    }
+}
+
+impl From<&str> for BadInput {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+impl std::fmt::Display for BadInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// JSON body of every error response.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: String,
+    /// Part of the input at fault, for errors caused by one span of it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<ErrorPosition>,
+    /// Id of the request, also sent as `X-Request-Id`; quote it when reporting an error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Char offsets into the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ErrorPosition {
+    pub start: usize,
+    pub end: usize,
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, message) = match &self {
@@ -33,6 +103,8 @@ impl IntoResponse for AppError {
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, self.to_string()),
             AppError::Forbidden(_) => (StatusCode::FORBIDDEN, self.to_string()),
             AppError::BadRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            AppError::RateLimited { .. } => (StatusCode::TOO_MANY_REQUESTS, self.to_string()),
+            AppError::BudgetExceeded(_) => (StatusCode::PAYMENT_REQUIRED, self.to_string()),
             AppError::Database(e) => {
                 tracing::error!("Database error: {e}");
                 (
@@ -56,11 +128,34 @@ impl IntoResponse for AppError {
             }
         };
 
-        let body = axum::Json(json!({ "error": message }));
-        (status, body).into_response()
+        let retry_after = match &self {
+            AppError::RateLimited { retry_after } => Some(retry_after_secs(*retry_after)),
+            _ => None,
+        };
+        let position = match &self {
+            AppError::BadRequest(input) => input.position,
+            _ => None,
+        };
+        let body = ErrorBody {
+            error: message,
+            position,
+            request_id: request_id::current(),
+        };
+        let mut response = (status, axum::Json(body)).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
+/// Whole seconds for `Retry-After`, rounded up so a client never retries too early.
+fn retry_after_secs(retry_after: Duration) -> u64 {
+    retry_after.as_secs_f64().ceil().max(1.0) as u64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,6 +198,37 @@ mod tests {
         assert_eq!(body["error"], "Bad request: invalid");
     }
 
+    #[tokio::test]
+    async fn bad_request_at_includes_position() {
+        let err = AppError::BadRequest(BadInput::at("Unknown field", 5, 11));
+        let (status, body) = error_to_parts(err).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Bad request: Unknown field");
+        assert_eq!(body["position"]["start"], 5);
+        assert_eq!(body["position"]["end"], 11);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_returns_429_with_retry_after() {
+        let err = AppError::RateLimited {
+            retry_after: Duration::from_millis(1500),
+        };
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"], "Too many requests; retry in 2 s");
+    }
+
+    #[tokio::test]
+    async fn budget_exceeded_returns_402() {
+        let err = AppError::BudgetExceeded("daily limit".into());
+        let (status, body) = error_to_parts(err).await;
+        assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+        assert_eq!(body["error"], "Budget exceeded: daily limit");
+    }
+
     #[tokio::test]
     async fn database_error_hides_details() {
         let db_err = sqlx::Error::RowNotFound;
@@ -118,6 +244,20 @@ mod tests {
         assert_eq!(body["error"], "Embedding service error");
     }
 
+    #[tokio::test]
+    async fn request_id_added_within_a_request() {
+        let (_, body) = error_to_parts(AppError::NotFound).await;
+        assert!(body.get("request_id").is_none());
+
+        let response = request_id::scoped("req-1", async {
+            AppError::Internal("boom".into()).into_response()
+        })
+        .await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["request_id"], "req-1");
+    }
+
     #[tokio::test]
     async fn internal_error_hides_details() {
         let (status, body) = error_to_parts(AppError::Internal("secret details".into())).await;
@@ -1,6 +1,12 @@
+pub mod app;
 pub mod auth;
+pub mod cli;
+pub mod config;
 pub mod error;
+pub mod logging;
 pub mod models;
+pub mod request_id;
 pub mod routes;
 pub mod services;
+pub mod shutdown;
 pub mod state;
@@ -1,37 +1,38 @@
-use std::env;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-use axum::Router;
-use axum::middleware;
-use axum::routing::{get, post};
-use oauth2::basic::BasicClient;
-use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl};
 use sqlx::postgres::PgPoolOptions;
-use tower_http::services::{ServeDir, ServeFile};
-use tower_sessions::cookie::SameSite;
-use tower_sessions::{Expiry, SessionManagerLayer};
+use tokio_util::sync::CancellationToken;
 use tower_sessions_sqlx_store::PostgresStore;
 
-use eemee_backend::auth;
-use eemee_backend::routes;
-use eemee_backend::services::embedding::{Embedder, EmbeddingService};
+use eemee_backend::app;
+use eemee_backend::config::Config;
+use eemee_backend::logging;
+use eemee_backend::services::db;
+use eemee_backend::services::embedding::Embedder;
+use eemee_backend::services::jobs;
+use eemee_backend::services::metrics::InstrumentedEmbedder;
+use eemee_backend::services::usage::MeteredEmbedder;
+use eemee_backend::shutdown;
 use eemee_backend::state::AppState;
 
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
-    tracing_subscriber::fmt::init();
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+    logging::init(config.log_format);
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+        .max_connections(config.database.max_connections)
+        .connect(&config.database.url)
         .await
         .expect("Failed to connect to database");
 
     // Run migrations
-    sqlx::migrate!()
+    db::MIGRATOR
         .run(&pool)
         .await
         .expect("Failed to run migrations");
@@ -44,74 +45,60 @@ async fn main() {
         .await
         .expect("Failed to create session table");
 
-    let session_layer = SessionManagerLayer::new(session_store)
-        .with_secure(false)
-        .with_same_site(SameSite::Lax)
-        .with_expiry(Expiry::OnInactivity(
-            tower_sessions::cookie::time::Duration::days(30),
-        ));
-
-    // Embedding service
-    let openai_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set");
-    let embedding: Arc<dyn Embedder> = Arc::new(EmbeddingService::new(openai_key));
-
-    let port: u16 = env::var("PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(16789);
-
-    // OAuth client
-    let google_client_id =
-        ClientId::new(env::var("GOOGLE_CLIENT_ID").expect("GOOGLE_CLIENT_ID must be set"));
-    let google_client_secret = ClientSecret::new(
-        env::var("GOOGLE_CLIENT_SECRET").expect("GOOGLE_CLIENT_SECRET must be set"),
+    // Embedding service, with every call recorded in the usage ledger
+    let embedding: Arc<dyn Embedder> = Arc::new(MeteredEmbedder::new(
+        Arc::new(InstrumentedEmbedder(config.embedding.embedder())),
+        pool.clone(),
+    ));
+
+    // Cancelled on SIGTERM/SIGINT; the server and background tasks stop on it
+    let shutdown_token = CancellationToken::new();
+    tokio::spawn({
+        let token = shutdown_token.clone();
+        async move {
+            shutdown::signal().await;
+            tracing::info!("Shutdown signal received, draining");
+            token.cancel();
+        }
+    });
+
+    // Embedding workers
+    let workers = jobs::spawn_workers(
+        pool.clone(),
+        embedding.clone(),
+        config.embedding.workers,
+        shutdown_token.clone(),
     );
-    let auth_url =
-        AuthUrl::new("https://accounts.google.com/o/oauth2/v2/auth".to_string()).unwrap();
-    let token_url = TokenUrl::new("https://oauth2.googleapis.com/token".to_string()).unwrap();
-
-    let redirect_url = env::var("OAUTH_REDIRECT_URL")
-        .unwrap_or_else(|_| format!("http://localhost:{port}/api/auth/callback"));
-
-    let oauth_client = BasicClient::new(google_client_id)
-        .set_client_secret(google_client_secret)
-        .set_auth_uri(auth_url)
-        .set_token_uri(token_url)
-        .set_redirect_uri(RedirectUrl::new(redirect_url).unwrap());
-
-    // Allowed emails
-    let allowed_emails: Vec<String> = env::var("ALLOWED_EMAILS")
-        .unwrap_or_default()
-        .split(',')
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect();
 
-    let state = AppState::new(pool, embedding, oauth_client, allowed_emails);
-
-    let static_dir = env::var("STATIC_DIR").unwrap_or_else(|_| "../frontend/dist".to_string());
-    let index_file = format!("{static_dir}/index.html");
-
-    // Auth routes (nested under /api/auth)
-    let auth_routes = Router::new()
-        .route("/google", get(auth::google_login))
-        .route("/callback", get(auth::google_callback))
-        .route("/logout", post(auth::logout))
-        .route("/me", get(auth::me))
-        .with_state(state.clone());
-
-    let api = Router::new()
-        .nest("/auth", auth_routes)
-        .merge(routes::api_router(state))
-        .layer(middleware::from_fn(auth::middleware::require_auth));
-
-    let app = Router::new()
-        .nest("/api", api)
-        .fallback_service(ServeDir::new(&static_dir).fallback(ServeFile::new(index_file)))
-        .layer(session_layer);
+    let state = AppState::new(
+        pool.clone(),
+        embedding,
+        config.oauth.client(),
+        config.allowed_emails.clone(),
+        config.health.clone(),
+        config.limits.clone(),
+    );
+    let app = app::build(state, &config, session_store);
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
     tracing::info!("listening on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let server =
+        axum::serve(listener, app).with_graceful_shutdown(shutdown_token.clone().cancelled_owned());
+    let drained = shutdown::drain(server, workers, &shutdown_token, config.shutdown_timeout).await;
+
+    // Closing the pool waits for every connection to be returned, which abandoned work
+    // may never do
+    if !drained {
+        tracing::warn!("Shutdown incomplete; exiting without closing the database pool");
+        return;
+    }
+    if tokio::time::timeout(config.shutdown_timeout, pool.close())
+        .await
+        .is_err()
+    {
+        tracing::warn!("Timed out closing the database pool");
+        return;
+    }
+    tracing::info!("Shutdown complete");
 }
@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::models::link::LinkType;
+use crate::models::search::SearchMode;
+
+/// Version written into new backups. Bump it whenever the archive layout changes.
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Everything needed to rebuild the library, embeddings included, keyed by the original
+/// ids so links and collections survive a round trip.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Backup {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub phrases: Vec<BackupPhrase>,
+    #[serde(default)]
+    pub collections: Vec<BackupCollection>,
+    #[serde(default)]
+    pub links: Vec<BackupLink>,
+    /// Query embeddings are a cache and are recomputed on the next run.
+    #[serde(default)]
+    pub saved_searches: Vec<BackupSavedSearch>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BackupPhrase {
+    pub id: Uuid,
+    pub phrase: String,
+    pub source: Option<String>,
+    pub tags: Vec<String>,
+    pub memo: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[sqlx(skip)]
+    pub meanings: Vec<BackupMeaning>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BackupMeaning {
+    pub id: Uuid,
+    pub meaning: String,
+    /// `None` while the meaning waits to be embedded; it is queued again on restore.
+    pub embedding: Option<Vec<f32>>,
+    pub embedding_model: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BackupCollection {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Members in collection order.
+    #[sqlx(skip)]
+    pub phrases: Vec<BackupCollectionPhrase>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BackupCollectionPhrase {
+    pub phrase_id: Uuid,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BackupLink {
+    pub id: Uuid,
+    pub from_phrase_id: Uuid,
+    pub to_phrase_id: Uuid,
+    pub link_type: LinkType,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BackupSavedSearch {
+    pub id: Uuid,
+    pub name: String,
+    pub mode: SearchMode,
+    pub query: String,
+    pub filters: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreMode {
+    /// Upsert by id; records missing from the backup are kept.
+    #[default]
+    Merge,
+    /// Delete the whole library first.
+    Replace,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RestoreQuery {
+    #[serde(default)]
+    pub mode: RestoreMode,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RestoreSummary {
+    pub mode: RestoreMode,
+    pub phrases: usize,
+    pub meanings: usize,
+    pub collections: usize,
+    pub links: usize,
+    pub saved_searches: usize,
+}
@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::phrase::{Phrase, PhraseWithMeaningsRow};
+
+/// Collection with its member count, used both as a row and as the API response.
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct Collection {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub phrase_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Joined query result for a phrase inside a collection.
+#[derive(Debug, FromRow)]
+pub struct CollectionPhraseRow {
+    pub position: i32,
+    #[sqlx(flatten)]
+    pub phrase: PhraseWithMeaningsRow,
+}
+
+/// API response for a collection member, ordered by `position`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CollectionPhrase {
+    pub position: i32,
+    #[serde(flatten)]
+    pub phrase: Phrase,
+}
+
+impl From<CollectionPhraseRow> for CollectionPhrase {
+    fn from(row: CollectionPhraseRow) -> Self {
+        CollectionPhrase {
+            position: row.position,
+            phrase: Phrase::from(row.phrase),
+        }
+    }
+}
+
+/// API response for a single collection with its phrases.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CollectionDetail {
+    #[serde(flatten)]
+    pub collection: Collection,
+    pub phrases: Vec<CollectionPhrase>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateCollectionRequest {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateCollectionRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AddCollectionPhraseRequest {
+    pub phrase_id: Uuid,
+    /// Zero-based insertion point; appends when omitted.
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReorderCollectionRequest {
+    pub phrase_ids: Vec<Uuid>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::phrase::PhraseStatus;
+
+    fn sample_collection() -> Collection {
+        let now = Utc::now();
+        Collection {
+            id: Uuid::new_v4(),
+            name: "essay".to_string(),
+            description: Some("phrases for my essay".to_string()),
+            phrase_count: 1,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn collection_detail_serializes_flat_with_ordered_phrases() {
+        let now = Utc::now();
+        let row = CollectionPhraseRow {
+            position: 0,
+            phrase: PhraseWithMeaningsRow {
+                id: Uuid::new_v4(),
+                phrase: "hello".to_string(),
+                source: None,
+                tags: vec![],
+                memo: None,
+                created_at: now,
+                updated_at: now,
+                meanings: vec!["a greeting".to_string()],
+                status: PhraseStatus::Ready,
+            },
+        };
+        let detail = CollectionDetail {
+            collection: sample_collection(),
+            phrases: vec![row.into()],
+        };
+
+        let json = serde_json::to_value(&detail).unwrap();
+        assert_eq!(json["name"], "essay");
+        assert_eq!(json["phrase_count"], 1);
+        assert_eq!(json["phrases"][0]["position"], 0);
+        assert_eq!(json["phrases"][0]["phrase"], "hello");
+        assert_eq!(json["phrases"][0]["meanings"][0], "a greeting");
+    }
+
+    #[test]
+    fn create_collection_request_deserialize_minimal() {
+        let req: CreateCollectionRequest = serde_json::from_str(r#"{"name":"essay"}"#).unwrap();
+        assert_eq!(req.name, "essay");
+        assert_eq!(req.description, None);
+    }
+
+    #[test]
+    fn add_collection_phrase_request_defaults_to_append() {
+        let id = Uuid::new_v4();
+        let req: AddCollectionPhraseRequest =
+            serde_json::from_str(&format!(r#"{{"phrase_id":"{id}"}}"#)).unwrap();
+        assert_eq!(req.phrase_id, id);
+        assert_eq!(req.position, None);
+    }
+}
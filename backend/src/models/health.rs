@@ -0,0 +1,118 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+    /// Not checked, e.g. the embedder when probing it is disabled.
+    Skipped,
+}
+
+/// Outcome of checking one dependency.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Check {
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    pub fn ok(latency_ms: u64) -> Self {
+        Check {
+            status: CheckStatus::Ok,
+            latency_ms: Some(latency_ms),
+            error: None,
+        }
+    }
+
+    pub fn failed(latency_ms: u64, error: String) -> Self {
+        Check {
+            status: CheckStatus::Failed,
+            latency_ms: Some(latency_ms),
+            error: Some(error),
+        }
+    }
+
+    pub fn skipped() -> Self {
+        Check {
+            status: CheckStatus::Skipped,
+            latency_ms: None,
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Checks {
+    pub database: Check,
+    pub migrations: Check,
+    pub embedding: Check,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessStatus {
+    Ready,
+    NotReady,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Readiness {
+    pub status: ReadinessStatus,
+    pub checks: Checks,
+}
+
+impl Readiness {
+    /// Ready unless a check failed; skipped checks don't count against it.
+    pub fn new(checks: Checks) -> Self {
+        let failed = [&checks.database, &checks.migrations, &checks.embedding]
+            .iter()
+            .any(|c| c.status == CheckStatus::Failed);
+        let status = if failed {
+            ReadinessStatus::NotReady
+        } else {
+            ReadinessStatus::Ready
+        };
+        Readiness { status, checks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skipped_checks_do_not_block_readiness() {
+        let readiness = Readiness::new(Checks {
+            database: Check::ok(1),
+            migrations: Check::ok(2),
+            embedding: Check::skipped(),
+        });
+        assert_eq!(readiness.status, ReadinessStatus::Ready);
+
+        let json = serde_json::to_value(&readiness).unwrap();
+        assert_eq!(json["status"], "ready");
+        assert_eq!(json["checks"]["database"]["latency_ms"], 1);
+        assert_eq!(
+            json["checks"]["embedding"],
+            serde_json::json!({"status": "skipped"})
+        );
+    }
+
+    #[test]
+    fn any_failed_check_means_not_ready() {
+        let readiness = Readiness::new(Checks {
+            database: Check::ok(1),
+            migrations: Check::failed(2, "1 migration pending".to_string()),
+            embedding: Check::skipped(),
+        });
+        assert_eq!(readiness.status, ReadinessStatus::NotReady);
+        let json = serde_json::to_value(&readiness).unwrap();
+        assert_eq!(json["status"], "not_ready");
+        assert_eq!(json["checks"]["migrations"]["error"], "1 migration pending");
+    }
+}
@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A phrase saved without meanings, to be completed later.
+#[derive(Debug)]
+pub struct NewDraft {
+    pub phrase: String,
+    pub source: Option<String>,
+    pub memo: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Already in the library or repeated in the file.
+    pub duplicates: usize,
+    /// Notes, bookmarks, empty highlights and entries that could not be parsed.
+    pub skipped: usize,
+    pub phrase_ids: Vec<Uuid>,
+}
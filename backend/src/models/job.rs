@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a worker, possibly until `run_at` after a failure.
+    Queued,
+    /// Out of attempts; retried only on request.
+    Dead,
+}
+
+/// An embedding job with the meaning it embeds.
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct EmbeddingJob {
+    pub id: Uuid,
+    pub meaning_id: Uuid,
+    pub phrase_id: Uuid,
+    pub meaning: String,
+    pub status: JobStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct JobQuery {
+    pub status: Option<JobStatus>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+fn default_limit() -> i64 {
+    100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn job_query_parses_status() {
+        let query: JobQuery = serde_json::from_str(r#"{"status":"dead"}"#).unwrap();
+        assert_eq!(query.status, Some(JobStatus::Dead));
+        assert_eq!(query.limit, 100);
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum LinkType {
+    Synonym,
+    Antonym,
+    Variation,
+    SeeAlso,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkDirection {
+    Outgoing,
+    Incoming,
+}
+
+/// A link as seen from one of its phrases, joined with the phrase on the other end.
+#[derive(Debug, FromRow)]
+pub struct PhraseLinkRow {
+    pub id: Uuid,
+    pub link_type: LinkType,
+    pub note: Option<String>,
+    pub outgoing: bool,
+    pub other_id: Uuid,
+    pub other_phrase: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LinkedPhrase {
+    pub id: Uuid,
+    pub phrase: String,
+}
+
+/// API response for a link relative to the phrase it was requested through.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PhraseLink {
+    pub id: Uuid,
+    pub link_type: LinkType,
+    pub note: Option<String>,
+    pub direction: LinkDirection,
+    pub phrase: LinkedPhrase,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<PhraseLinkRow> for PhraseLink {
+    fn from(row: PhraseLinkRow) -> Self {
+        PhraseLink {
+            id: row.id,
+            link_type: row.link_type,
+            note: row.note,
+            direction: if row.outgoing {
+                LinkDirection::Outgoing
+            } else {
+                LinkDirection::Incoming
+            },
+            phrase: LinkedPhrase {
+                id: row.other_id,
+                phrase: row.other_phrase,
+            },
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateLinkRequest {
+    pub target_id: Uuid,
+    pub link_type: LinkType,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GraphQuery {
+    #[serde(default = "default_depth")]
+    pub depth: i32,
+}
+
+fn default_depth() -> i32 {
+    1
+}
+
+#[derive(Debug, FromRow, Serialize, ToSchema)]
+pub struct GraphNode {
+    pub id: Uuid,
+    pub phrase: String,
+    /// Number of hops from the requested phrase.
+    pub depth: i32,
+}
+
+#[derive(Debug, FromRow)]
+pub struct GraphEdgeRow {
+    pub id: Uuid,
+    pub from_phrase_id: Uuid,
+    pub to_phrase_id: Uuid,
+    pub link_type: LinkType,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GraphEdge {
+    pub id: Uuid,
+    pub from: Uuid,
+    pub to: Uuid,
+    pub link_type: LinkType,
+    pub note: Option<String>,
+}
+
+impl From<GraphEdgeRow> for GraphEdge {
+    fn from(row: GraphEdgeRow) -> Self {
+        GraphEdge {
+            id: row.id,
+            from: row.from_phrase_id,
+            to: row.to_phrase_id,
+            link_type: row.link_type,
+            note: row.note,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PhraseGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_type_serializes_in_snake_case() {
+        let json = serde_json::to_value(LinkType::SeeAlso).unwrap();
+        assert_eq!(json, "see_also");
+    }
+
+    #[test]
+    fn create_link_request_rejects_unknown_type() {
+        let id = Uuid::new_v4();
+        let json = format!(r#"{{"target_id":"{id}","link_type":"cousin"}}"#);
+        assert!(serde_json::from_str::<CreateLinkRequest>(&json).is_err());
+    }
+
+    #[test]
+    fn phrase_link_row_to_incoming_link() {
+        let row = PhraseLinkRow {
+            id: Uuid::new_v4(),
+            link_type: LinkType::SeeAlso,
+            note: None,
+            outgoing: false,
+            other_id: Uuid::new_v4(),
+            other_phrase: "rain".to_string(),
+            created_at: Utc::now(),
+        };
+
+        let link = PhraseLink::from(row);
+        assert_eq!(link.link_type, LinkType::SeeAlso);
+        assert_eq!(link.direction, LinkDirection::Incoming);
+        let json = serde_json::to_value(&link).unwrap();
+        assert_eq!(json["direction"], "incoming");
+        assert_eq!(json["phrase"]["phrase"], "rain");
+    }
+
+    #[test]
+    fn graph_query_default_depth() {
+        let query: GraphQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(query.depth, 1);
+    }
+}
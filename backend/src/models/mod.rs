@@ -1 +1,15 @@
+pub mod backup;
+pub mod collection;
+pub mod health;
+pub mod import;
+pub mod job;
+pub mod link;
 pub mod phrase;
+pub mod search;
+pub mod stats;
+pub mod tag;
+pub mod token;
+pub mod trash;
+pub mod usage;
+pub mod user;
+pub mod vector_index;
@@ -1,8 +1,12 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use pgvector::Vector;
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use crate::models::link::PhraseLink;
+
 /// Database row for the phrases table (no meaning/embedding columns after migration).
 #[derive(Debug, FromRow)]
 pub struct PhraseRow {
@@ -15,6 +19,20 @@ pub struct PhraseRow {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Where a phrase is in the embedding pipeline, from the states of its meanings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum PhraseStatus {
+    /// No meanings yet.
+    Draft,
+    /// Some meanings are waiting to be embedded; only embedded ones are searched.
+    Pending,
+    Ready,
+    /// Embedding a meaning ran out of attempts.
+    Failed,
+}
+
 /// Joined query result with aggregated meanings.
 #[derive(Debug, FromRow)]
 pub struct PhraseWithMeaningsRow {
@@ -26,10 +44,11 @@ pub struct PhraseWithMeaningsRow {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub meanings: Vec<String>,
+    pub status: PhraseStatus,
 }
 
 /// API response (no embedding).
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct Phrase {
     pub id: Uuid,
     pub phrase: String,
@@ -37,6 +56,7 @@ pub struct Phrase {
     pub source: Option<String>,
     pub tags: Vec<String>,
     pub memo: Option<String>,
+    pub status: PhraseStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -50,15 +70,84 @@ impl From<PhraseWithMeaningsRow> for Phrase {
             source: row.source,
             tags: row.tags,
             memo: row.memo,
+            status: row.status,
             created_at: row.created_at,
             updated_at: row.updated_at,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// Similarity search result row: the phrase plus its cosine similarity to the query.
+#[derive(Debug, FromRow)]
+pub struct ScoredPhraseRow {
+    #[sqlx(flatten)]
+    pub phrase: PhraseWithMeaningsRow,
+    pub score: f64,
+}
+
+/// The meaning of a phrase closest to a query vector.
+#[derive(Debug, FromRow)]
+pub struct NearestMeaningRow {
+    pub phrase_id: Uuid,
+    pub embedding: Vector,
+    pub similarity: f64,
+}
+
+/// API response for similarity results, best match first.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScoredPhrase {
+    #[serde(flatten)]
+    pub phrase: Phrase,
+    pub score: f64,
+}
+
+impl From<ScoredPhraseRow> for ScoredPhrase {
+    fn from(row: ScoredPhraseRow) -> Self {
+        ScoredPhrase {
+            phrase: Phrase::from(row.phrase),
+            score: row.score,
+        }
+    }
+}
+
+/// Char range of a match inside a [`Highlight`] snippet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Excerpt of one field around a text search match.
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+pub struct Highlight {
+    /// `phrase`, `meaning`, `tag`, `source` or `memo`.
+    pub field: &'static str,
+    pub snippet: String,
+    /// Offsets are in chars (Unicode scalar values) of `snippet`.
+    pub matches: Vec<MatchRange>,
+}
+
+/// API response for text search: the phrase, its relevance and where it matched.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TextSearchHit {
+    #[serde(flatten)]
+    pub phrase: Phrase,
+    pub score: f64,
+    pub highlights: Vec<Highlight>,
+}
+
+/// API response for a single phrase, including its links to other phrases.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PhraseDetail {
+    #[serde(flatten)]
+    pub phrase: Phrase,
+    pub links: Vec<PhraseLink>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreatePhraseRequest {
     pub phrase: String,
+    /// Empty to save a draft.
     pub meanings: Vec<String>,
     pub source: Option<String>,
     #[serde(default)]
@@ -66,7 +155,7 @@ pub struct CreatePhraseRequest {
     pub memo: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdatePhraseRequest {
     pub phrase: Option<String>,
     pub meanings: Option<Vec<String>>,
@@ -75,34 +164,230 @@ pub struct UpdatePhraseRequest {
     pub memo: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Filters shared by search and export.
+///
+/// Flattened into query-string structs, so every field must deserialize from a string.
+/// Saved searches store it as JSON.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PhraseFilter {
+    pub collection_id: Option<Uuid>,
+    /// Phrases must carry every one of these tags; comma-separated in query strings.
+    #[serde(default, deserialize_with = "deserialize_tag_list")]
+    #[param(value_type = Option<String>)]
+    pub tags: Vec<String>,
+    /// Case-insensitive substring of the source.
+    pub source: Option<String>,
+}
+
+/// Accepts a JSON array or, for query strings, a comma-separated list.
+fn deserialize_tag_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum TagList {
+        List(Vec<String>),
+        Csv(String),
+    }
+
+    Ok(match TagList::deserialize(deserializer)? {
+        TagList::List(tags) => tags,
+        TagList::Csv(tags) => tags
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect(),
+    })
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct SemanticSearchRequest {
+    /// Free-text query; counts as a positive query of weight 1. May be empty when
+    /// `queries` or `examples` are given.
+    #[serde(default)]
     pub query: String,
+    /// Additional queries. Negative weights steer away from a query.
+    #[serde(default)]
+    pub queries: Vec<WeightedQuery>,
+    /// Existing phrases used as examples, by the mean of their meaning vectors. Example
+    /// phrases are left out of the results.
+    #[serde(default)]
+    pub examples: Vec<WeightedExample>,
+    /// How several query vectors are combined.
+    #[serde(default)]
+    pub combine: CombineMode,
+    /// Between 1 and 100.
     #[serde(default = "default_limit")]
     pub limit: i64,
+    /// HNSW candidate list size. Setting this (or `probes`) opts into approximate search
+    /// over the half-precision embeddings, which can use the vector index.
+    pub ef_search: Option<i32>,
+    /// IVFFlat lists probed per query; see `ef_search`.
+    pub probes: Option<i32>,
+    /// Between 0 and 1. When set, results are reranked with maximal marginal relevance
+    /// over a larger candidate set, trading relevance for covering different senses.
+    pub diversity: Option<f64>,
+    /// Record the search in the history.
+    #[serde(default)]
+    pub record: bool,
+    #[serde(flatten)]
+    pub filter: PhraseFilter,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WeightedQuery {
+    pub text: String,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WeightedExample {
+    pub phrase_id: Uuid,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CombineMode {
+    /// Search with the weighted sum of the normalized vectors. Works with the vector
+    /// index and is the cheaper choice.
+    #[default]
+    Centroid,
+    /// Score each phrase by the weighted sum of its similarities to every vector, so a
+    /// phrase must be close to each positive query on its own. Always exact.
+    Sum,
+}
+
+/// Tuning for approximate (index-backed) semantic search.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnnParams {
+    pub ef_search: Option<i32>,
+    pub probes: Option<i32>,
+}
+
+impl SemanticSearchRequest {
+    /// Query texts with their weights, the free-text query first.
+    pub fn weighted_texts(&self) -> Vec<(&str, f32)> {
+        let main = (!self.query.trim().is_empty()).then_some((self.query.as_str(), 1.0));
+        main.into_iter()
+            .chain(self.queries.iter().map(|q| (q.text.as_str(), q.weight)))
+            .collect()
+    }
+
+    /// One-line description for the search history, e.g. `rain; snow (0.5); heat (-1)`.
+    pub fn summary(&self) -> String {
+        let texts = self
+            .weighted_texts()
+            .into_iter()
+            .map(|(text, weight)| (text.to_string(), weight));
+        let examples = self
+            .examples
+            .iter()
+            .map(|e| (format!("phrase:{}", e.phrase_id), e.weight));
+        texts
+            .chain(examples)
+            .map(|(label, weight)| {
+                if weight == 1.0 {
+                    label
+                } else {
+                    format!("{label} ({weight})")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Approximate search parameters, if the request opted in.
+    pub fn ann_params(&self) -> Option<AnnParams> {
+        (self.ef_search.is_some() || self.probes.is_some()).then_some(AnnParams {
+            ef_search: self.ef_search,
+            probes: self.probes,
+        })
+    }
 }
 
 fn default_limit() -> i64 {
     20
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct TextSearchQuery {
+    /// Query in the syntax parsed by [`crate::services::query::parse`].
     pub q: String,
     #[serde(default = "default_limit")]
     pub limit: i64,
+    /// Record the search in the history.
+    #[serde(default)]
+    pub record: bool,
+    // utoipa does not expand flattened params; handlers list `PhraseFilter` themselves
+    #[serde(flatten)]
+    #[param(ignore)]
+    pub filter: PhraseFilter,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct InboxQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    // utoipa does not expand flattened params; handlers list `PhraseFilter` themselves
+    #[serde(flatten)]
+    #[param(ignore)]
+    pub filter: PhraseFilter,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SimilarQuery {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    // utoipa does not expand flattened params; handlers list `PhraseFilter` themselves
+    #[serde(flatten)]
+    #[param(ignore)]
+    pub filter: PhraseFilter,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ExportQuery {
+    /// `json`, `ndjson`, `csv`, `anki` or `markdown`.
     #[serde(default = "default_format")]
     pub format: String,
+    /// Markdown only: one file per phrase or per source.
+    #[serde(default)]
+    pub group: MarkdownGroup,
+    /// Only phrases created at or after this instant.
+    pub from: Option<DateTime<Utc>>,
+    /// Only phrases created before this instant.
+    pub to: Option<DateTime<Utc>>,
+    // utoipa does not expand flattened params; handlers list `PhraseFilter` themselves
+    #[serde(flatten)]
+    #[param(ignore)]
+    pub filter: PhraseFilter,
 }
 
 fn default_format() -> String {
     "json".to_string()
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkdownGroup {
+    #[default]
+    Phrase,
+    Source,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,6 +401,7 @@ mod tests {
             id,
             phrase: "hello".to_string(),
             meanings: vec!["a greeting".to_string(), "an exclamation".to_string()],
+            status: PhraseStatus::Ready,
             source: Some("dictionary".to_string()),
             tags: vec!["greetings".to_string(), "common".to_string()],
             memo: Some("used frequently".to_string()),
@@ -141,6 +427,7 @@ mod tests {
             id: Uuid::new_v4(),
             phrase: "test".to_string(),
             meanings: vec!["a test".to_string()],
+            status: PhraseStatus::Ready,
             source: None,
             tags: vec![],
             memo: None,
@@ -160,6 +447,7 @@ mod tests {
             id: Uuid::new_v4(),
             phrase: "test".to_string(),
             meanings: vec!["a test".to_string()],
+            status: PhraseStatus::Ready,
             source: None,
             tags: vec![],
             memo: None,
@@ -210,6 +498,41 @@ mod tests {
         let req: SemanticSearchRequest = serde_json::from_str(json).unwrap();
         assert_eq!(req.query, "test");
         assert_eq!(req.limit, 20);
+        assert_eq!(req.ef_search, None);
+        assert_eq!(req.probes, None);
+    }
+
+    #[test]
+    fn semantic_search_request_with_weighted_queries_and_examples() {
+        let id = Uuid::new_v4();
+        let json = format!(
+            r#"{{"queries":[{{"text":"rain"}},{{"text":"heat","weight":-0.5}}],
+                "examples":[{{"phrase_id":"{id}"}}],"combine":"sum"}}"#
+        );
+        let req: SemanticSearchRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(req.query, "");
+        assert_eq!(req.combine, CombineMode::Sum);
+        assert_eq!(req.weighted_texts(), vec![("rain", 1.0), ("heat", -0.5)]);
+        assert_eq!(req.examples[0].weight, 1.0);
+        assert_eq!(req.summary(), format!("rain; heat (-0.5); phrase:{id}"));
+
+        let req: SemanticSearchRequest =
+            serde_json::from_str(r#"{"query":"snow","queries":[{"text":"ice"}]}"#).unwrap();
+        assert_eq!(req.combine, CombineMode::Centroid);
+        assert_eq!(req.weighted_texts(), vec![("snow", 1.0), ("ice", 1.0)]);
+        assert_eq!(req.summary(), "snow; ice");
+    }
+
+    #[test]
+    fn semantic_search_request_opts_into_ann_with_ef_search() {
+        let req: SemanticSearchRequest = serde_json::from_str(r#"{"query":"test"}"#).unwrap();
+        assert!(req.ann_params().is_none());
+
+        let req: SemanticSearchRequest =
+            serde_json::from_str(r#"{"query":"test","ef_search":100}"#).unwrap();
+        let params = req.ann_params().unwrap();
+        assert_eq!(params.ef_search, Some(100));
+        assert_eq!(params.probes, None);
     }
 
     #[test]
@@ -224,5 +547,102 @@ mod tests {
         let json = r#"{}"#;
         let query: ExportQuery = serde_json::from_str(json).unwrap();
         assert_eq!(query.format, "json");
+        assert_eq!(query.filter.collection_id, None);
+    }
+
+    #[test]
+    fn export_query_from_uri_with_date_range() {
+        let uri: axum::http::Uri =
+            "/export?format=ndjson&tags=a,b&from=2025-01-01T00:00:00Z&to=2025-02-01T09:30:00Z"
+                .parse()
+                .unwrap();
+        let axum::extract::Query(query) =
+            axum::extract::Query::<ExportQuery>::try_from_uri(&uri).unwrap();
+        assert_eq!(query.format, "ndjson");
+        assert_eq!(query.group, MarkdownGroup::Phrase);
+        assert_eq!(query.filter.tags, vec!["a", "b"]);
+        assert_eq!(
+            query.from.unwrap().to_rfc3339(),
+            "2025-01-01T00:00:00+00:00"
+        );
+        assert_eq!(query.to.unwrap().to_rfc3339(), "2025-02-01T09:30:00+00:00");
+    }
+
+    #[test]
+    fn text_search_query_from_uri_with_collection_filter() {
+        let id = Uuid::new_v4();
+        let uri: axum::http::Uri = format!("/search/text?q=rain&limit=5&collection_id={id}")
+            .parse()
+            .unwrap();
+        let axum::extract::Query(query) =
+            axum::extract::Query::<TextSearchQuery>::try_from_uri(&uri).unwrap();
+        assert_eq!(query.q, "rain");
+        assert_eq!(query.limit, 5);
+        assert_eq!(query.filter.collection_id, Some(id));
+    }
+
+    #[test]
+    fn text_search_query_record_flag_from_uri() {
+        let uri: axum::http::Uri = "/api/search/text?q=rain&record=true&tags=a,b"
+            .parse()
+            .unwrap();
+        let axum::extract::Query(query) =
+            axum::extract::Query::<TextSearchQuery>::try_from_uri(&uri).unwrap();
+        assert!(query.record);
+        assert_eq!(query.filter.tags, vec!["a", "b"]);
+
+        let query: TextSearchQuery = serde_json::from_str(r#"{"q":"rain"}"#).unwrap();
+        assert!(!query.record);
+    }
+
+    #[test]
+    fn similar_query_from_uri_with_tag_and_source_filters() {
+        let uri: axum::http::Uri = "/phrases/x/similar?tags=novel,%20rain&source=murakami"
+            .parse()
+            .unwrap();
+        let axum::extract::Query(query) =
+            axum::extract::Query::<SimilarQuery>::try_from_uri(&uri).unwrap();
+        assert_eq!(query.limit, 20);
+        assert_eq!(query.filter.tags, vec!["novel", "rain"]);
+        assert_eq!(query.filter.source, Some("murakami".to_string()));
+        assert_eq!(query.filter.collection_id, None);
+    }
+
+    #[test]
+    fn semantic_search_request_with_tag_array_filter() {
+        let json = r#"{"query":"rain","tags":["novel","rain"]}"#;
+        let req: SemanticSearchRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.filter.tags, vec!["novel", "rain"]);
+    }
+
+    #[test]
+    fn scored_phrase_serializes_flat_with_score() {
+        let now = Utc::now();
+        let row = ScoredPhraseRow {
+            phrase: PhraseWithMeaningsRow {
+                id: Uuid::new_v4(),
+                phrase: "drizzle".to_string(),
+                source: None,
+                tags: vec![],
+                memo: None,
+                created_at: now,
+                updated_at: now,
+                meanings: vec!["light rain".to_string()],
+                status: PhraseStatus::Ready,
+            },
+            score: 0.75,
+        };
+
+        let json = serde_json::to_value(ScoredPhrase::from(row)).unwrap();
+        assert_eq!(json["phrase"], "drizzle");
+        assert_eq!(json["score"], 0.75);
+    }
+
+    #[test]
+    fn semantic_search_request_with_collection_filter() {
+        let id = Uuid::new_v4();
+        let json = format!(r#"{{"query":"rain","collection_id":"{id}"}}"#);
+        let req: SemanticSearchRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(req.filter.collection_id, Some(id));
     }
 }
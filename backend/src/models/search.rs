@@ -0,0 +1,226 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use sqlx::types::Json;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::models::phrase::{Phrase, PhraseFilter, TextSearchHit};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum SearchMode {
+    Semantic,
+    Text,
+}
+
+impl SearchMode {
+    /// Label of the search metrics.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchMode::Semantic => "semantic",
+            SearchMode::Text => "text",
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct SearchHistoryRow {
+    pub id: Uuid,
+    pub mode: SearchMode,
+    pub query: String,
+    pub filters: Json<PhraseFilter>,
+    pub result_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// API response for one recorded search.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchHistoryEntry {
+    pub id: Uuid,
+    pub mode: SearchMode,
+    pub query: String,
+    pub filters: PhraseFilter,
+    pub result_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<SearchHistoryRow> for SearchHistoryEntry {
+    fn from(row: SearchHistoryRow) -> Self {
+        SearchHistoryEntry {
+            id: row.id,
+            mode: row.mode,
+            query: row.query,
+            filters: row.filters.0,
+            result_count: row.result_count,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    #[serde(default = "default_history_limit")]
+    pub limit: i64,
+}
+
+fn default_history_limit() -> i64 {
+    50
+}
+
+#[derive(Debug, FromRow)]
+pub struct SavedSearchRow {
+    pub id: Uuid,
+    pub name: String,
+    pub mode: SearchMode,
+    pub query: String,
+    pub filters: Json<PhraseFilter>,
+    pub has_embedding: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// API response for a saved search.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SavedSearch {
+    pub id: Uuid,
+    pub name: String,
+    pub mode: SearchMode,
+    pub query: String,
+    pub filters: PhraseFilter,
+    /// Whether re-runs can skip embedding the query.
+    pub has_embedding: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<SavedSearchRow> for SavedSearch {
+    fn from(row: SavedSearchRow) -> Self {
+        SavedSearch {
+            id: row.id,
+            name: row.name,
+            mode: row.mode,
+            query: row.query,
+            filters: row.filters.0,
+            has_embedding: row.has_embedding,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateSavedSearchRequest {
+    pub name: String,
+    pub mode: SearchMode,
+    pub query: String,
+    #[serde(default)]
+    pub filters: PhraseFilter,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSavedSearchRequest {
+    pub name: Option<String>,
+    pub mode: Option<SearchMode>,
+    pub query: Option<String>,
+    pub filters: Option<PhraseFilter>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RunSavedSearchQuery {
+    #[serde(default = "default_run_limit")]
+    pub limit: i64,
+    /// Also record the run in the search history.
+    #[serde(default)]
+    pub record: bool,
+}
+
+fn default_run_limit() -> i64 {
+    20
+}
+
+/// Results of a saved search, shaped like the matching search endpoint's response.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum SearchResults {
+    Semantic(Vec<Phrase>),
+    Text(Vec<TextSearchHit>),
+}
+
+impl SearchResults {
+    pub fn len(&self) -> usize {
+        match self {
+            SearchResults::Semantic(phrases) => phrases.len(),
+            SearchResults::Text(hits) => hits.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_mode_labels_match_the_api() {
+        for mode in [SearchMode::Semantic, SearchMode::Text] {
+            assert_eq!(serde_json::to_value(mode).unwrap(), mode.as_str());
+        }
+    }
+
+    #[test]
+    fn create_saved_search_defaults_to_no_filters() {
+        let req: CreateSavedSearchRequest =
+            serde_json::from_str(r#"{"name":"rain","mode":"text","query":"tag:weather"}"#).unwrap();
+        assert_eq!(req.mode, SearchMode::Text);
+        assert!(req.filters.tags.is_empty());
+        assert_eq!(req.filters.collection_id, None);
+    }
+
+    #[test]
+    fn saved_filters_round_trip_through_json() {
+        let filter = PhraseFilter {
+            collection_id: Some(Uuid::new_v4()),
+            tags: vec!["novel".to_string()],
+            source: Some("murakami".to_string()),
+        };
+        let json = serde_json::to_value(&filter).unwrap();
+        let back: PhraseFilter = serde_json::from_value(json).unwrap();
+        assert_eq!(back.collection_id, filter.collection_id);
+        assert_eq!(back.tags, filter.tags);
+        assert_eq!(back.source, filter.source);
+    }
+
+    #[test]
+    fn history_row_to_entry() {
+        let row = SearchHistoryRow {
+            id: Uuid::new_v4(),
+            mode: SearchMode::Semantic,
+            query: "loneliness".to_string(),
+            filters: Json(PhraseFilter::default()),
+            result_count: 3,
+            created_at: Utc::now(),
+        };
+        let entry = SearchHistoryEntry::from(row);
+        assert_eq!(entry.mode, SearchMode::Semantic);
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["filters"]["tags"], serde_json::json!([]));
+        assert_eq!(json["result_count"], 3);
+    }
+
+    #[test]
+    fn search_results_serialize_as_plain_array() {
+        let results = SearchResults::Semantic(vec![]);
+        assert_eq!(
+            serde_json::to_value(&results).unwrap(),
+            serde_json::json!([])
+        );
+        assert!(results.is_empty());
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+use sqlx::FromRow;
+
+/// Library size and embedding progress, printed by `eemee-admin stats`.
+#[derive(Debug, Serialize, FromRow)]
+pub struct LibraryStats {
+    pub phrases: i64,
+    /// Phrases without meanings.
+    pub drafts: i64,
+    pub meanings: i64,
+    pub pending_meanings: i64,
+    pub failed_meanings: i64,
+    pub collections: i64,
+    pub links: i64,
+    pub saved_searches: i64,
+    pub search_history: i64,
+    pub queued_jobs: i64,
+    pub dead_jobs: i64,
+    /// Ready embeddings by model, most used first.
+    #[sqlx(skip)]
+    pub embedding_models: Vec<ModelCount>,
+}
+
+/// Ready embeddings produced by one model.
+#[derive(Debug, Serialize, FromRow)]
+pub struct ModelCount {
+    pub model: String,
+    pub meanings: i64,
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TagSuggestRequest {
+    /// Draft meanings to embed, at most 10; used when `phrase_id` is absent.
+    #[serde(default)]
+    pub meanings: Vec<String>,
+    /// Existing phrase whose stored meaning vectors are used instead of embedding.
+    pub phrase_id: Option<Uuid>,
+    /// Number of nearest phrases consulted per meaning.
+    #[serde(default = "default_neighbours")]
+    pub k: i64,
+    #[serde(default = "default_suggestion_limit")]
+    pub limit: usize,
+}
+
+fn default_neighbours() -> i64 {
+    10
+}
+
+fn default_suggestion_limit() -> usize {
+    5
+}
+
+/// A nearby phrase and its best cosine similarity to any of the query meanings.
+#[derive(Debug, Clone, FromRow)]
+pub struct TagNeighbour {
+    pub id: Uuid,
+    pub tags: Vec<String>,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Serialize, PartialEq, ToSchema)]
+pub struct TagSuggestion {
+    pub tag: String,
+    /// Similarity-weighted share of neighbours carrying the tag, in `[0, 1]`.
+    pub score: f64,
+    /// Number of neighbours carrying the tag.
+    pub count: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_suggest_request_defaults() {
+        let req: TagSuggestRequest = serde_json::from_str(r#"{"meanings":["rain"]}"#).unwrap();
+        assert_eq!(req.meanings, vec!["rain"]);
+        assert_eq!(req.phrase_id, None);
+        assert_eq!(req.k, 10);
+        assert_eq!(req.limit, 5);
+    }
+}
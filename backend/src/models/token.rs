@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// An API token, created with `eemee-admin tokens create`. The token itself is only
+/// shown once, when it is created.
+#[derive(Debug, Serialize, FromRow)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
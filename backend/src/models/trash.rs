@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::models::backup::{BackupLink, BackupPhrase};
+
+/// A deleted phrase as it was, so it can be put back.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashedPhrase {
+    pub phrase: BackupPhrase,
+    pub collections: Vec<TrashedMembership>,
+    /// Links from and to the phrase.
+    pub links: Vec<BackupLink>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct TrashedMembership {
+    pub collection_id: Uuid,
+    pub added_at: DateTime<Utc>,
+}
+
+/// A phrase in the trash, as `eemee-admin trash list` shows it.
+#[derive(Debug, Serialize, FromRow)]
+pub struct TrashEntry {
+    pub id: Uuid,
+    pub phrase: String,
+    pub deleted_at: DateTime<Utc>,
+}
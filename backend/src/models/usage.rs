@@ -0,0 +1,18 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Embedding tokens spent in the current periods, in UTC.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, FromRow, ToSchema)]
+pub struct TokenTotals {
+    pub today: i64,
+    pub this_month: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EmbeddingUsage {
+    #[serde(flatten)]
+    pub totals: TokenTotals,
+    pub daily_budget: Option<u64>,
+    pub monthly_budget: Option<u64>,
+}
@@ -0,0 +1,10 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+
+/// An account allowed to log in, added with `eemee-admin users add`.
+#[derive(Debug, Serialize, FromRow)]
+pub struct AllowedUser {
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+}
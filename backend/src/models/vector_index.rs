@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexKind {
+    Hnsw,
+    Ivfflat,
+}
+
+impl IndexKind {
+    /// Access method name as reported by `pg_am`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IndexKind::Hnsw => "hnsw",
+            IndexKind::Ivfflat => "ivfflat",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hnsw" => Some(IndexKind::Hnsw),
+            "ivfflat" => Some(IndexKind::Ivfflat),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BuildIndexRequest {
+    #[serde(default = "default_kind")]
+    pub kind: IndexKind,
+    /// HNSW: max connections per layer.
+    pub m: Option<i32>,
+    /// HNSW: size of the candidate list while building.
+    pub ef_construction: Option<i32>,
+    /// IVFFlat: number of clusters; defaults to one per thousand meanings.
+    pub lists: Option<i32>,
+}
+
+fn default_kind() -> IndexKind {
+    IndexKind::Hnsw
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VectorIndexStatus {
+    pub exists: bool,
+    pub kind: Option<IndexKind>,
+    /// False while a concurrent build is running or after one failed.
+    pub valid: bool,
+    pub definition: Option<String>,
+    pub size_bytes: Option<i64>,
+    pub meanings: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_index_request_defaults_to_hnsw() {
+        let req: BuildIndexRequest = serde_json::from_str("{}").unwrap();
+        assert_eq!(req.kind, IndexKind::Hnsw);
+        assert_eq!(req.m, None);
+        assert_eq!(req.lists, None);
+    }
+
+    #[test]
+    fn index_kind_parse_matches_serde() {
+        for kind in [IndexKind::Hnsw, IndexKind::Ivfflat] {
+            assert_eq!(IndexKind::parse(kind.as_str()), Some(kind));
+            assert_eq!(serde_json::to_value(kind).unwrap(), kind.as_str());
+        }
+        assert_eq!(IndexKind::parse("btree"), None);
+    }
+}
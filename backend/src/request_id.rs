@@ -0,0 +1,93 @@
+//! Request ids, so a report of "Internal server error" can be tied to the logs.
+//!
+//! Every request gets an id, taken from an incoming `X-Request-Id` header when it looks
+//! sane and generated otherwise. The id is echoed back in the response header, added to
+//! error bodies and recorded on the `request` span that wraps the handler.
+
+use axum::extract::{MatchedPath, Request};
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest incoming id we accept; anything else is replaced.
+const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Id of the request being handled, if called from within one.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Runs `future` with `id` as the current request id.
+pub(crate) async fn scoped<F: Future>(id: &str, future: F) -> F::Output {
+    REQUEST_ID.scope(id.to_string(), future).await
+}
+
+/// Whether a client-supplied id is safe to log and echo back.
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+pub async fn propagate(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |p| p.as_str());
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        route,
+        status = tracing::field::Empty,
+    );
+
+    let mut response = scoped(&id, next.run(request).instrument(span.clone())).await;
+
+    span.record("status", response.status().as_u16());
+    let value = HeaderValue::from_str(&id).expect("request ids are visible ASCII");
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_uuids_and_simple_tokens() {
+        assert!(is_valid("6f1c0f2e-8d5b-4c7e-9a55-1f0d3b2c4e6a"));
+        assert!(is_valid("lb.01:req_42"));
+    }
+
+    #[test]
+    fn rejects_empty_long_or_odd_ids() {
+        assert!(!is_valid(""));
+        assert!(!is_valid(&"a".repeat(MAX_LEN + 1)));
+        assert!(!is_valid("two words"));
+        assert!(!is_valid("id\"}"));
+    }
+
+    #[tokio::test]
+    async fn current_is_scoped_to_the_request() {
+        assert_eq!(current(), None);
+        let inside = scoped("abc", async { current() }).await;
+        assert_eq!(inside.as_deref(), Some("abc"));
+    }
+}
@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use futures::channel::mpsc;
+use futures::{SinkExt, future};
+use uuid::Uuid;
+
+use crate::error::{AppError, ErrorBody};
+use crate::models::backup::{Backup, RestoreQuery, RestoreSummary};
+use crate::models::job::{EmbeddingJob, JobQuery};
+use crate::models::usage::EmbeddingUsage;
+use crate::models::vector_index::{BuildIndexRequest, VectorIndexStatus};
+use crate::services::{backup, jobs, usage, vector_index};
+use crate::state::AppState;
+
+/// Encoded phrases buffered ahead of a slow client before the database cursor pauses.
+const BACKUP_BUFFER: usize = 32;
+
+type Chunk = Result<Vec<u8>, AppError>;
+
+#[utoipa::path(
+    get,
+    path = "/admin/vector-index",
+    tag = "admin",
+    responses(
+        (status = 200, description = "The vector index, if any", body = VectorIndexStatus)
+    )
+)]
+pub async fn vector_index_status(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<VectorIndexStatus>, AppError> {
+    Ok(Json(vector_index::index_status(&state.pool).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/vector-index",
+    tag = "admin",
+    request_body = BuildIndexRequest,
+    responses(
+        (status = 200, description = "The new index", body = VectorIndexStatus),
+        (status = 400, description = "Invalid request", body = ErrorBody)
+    )
+)]
+pub async fn build_vector_index(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BuildIndexRequest>,
+) -> Result<Json<VectorIndexStatus>, AppError> {
+    Ok(Json(vector_index::build_index(&state.pool, &req).await?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/vector-index",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Done", body = serde_json::Value, example = json!({"ok": true}))
+    )
+)]
+pub async fn drop_vector_index(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    vector_index::drop_index(&state.pool).await?;
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+/// Embedding tokens spent today and this month, against the configured budgets.
+#[utoipa::path(
+    get,
+    path = "/admin/usage",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Tokens spent and the budgets", body = EmbeddingUsage)
+    )
+)]
+pub async fn embedding_usage(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EmbeddingUsage>, AppError> {
+    Ok(Json(usage::usage(&state.pool, &state.limits).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/backup",
+    tag = "admin",
+    responses(
+        (status = 200, description = "The whole library as a JSON download", body = Backup)
+    )
+)]
+pub async fn create_backup(State(state): State<Arc<AppState>>) -> Response {
+    let (tx, rx) = mpsc::channel::<Chunk>(BACKUP_BUFFER);
+    tokio::spawn(write_backup(state, tx));
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/json"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"eemee-backup.json\"",
+            ),
+        ],
+        Body::from_stream(rx),
+    )
+        .into_response()
+}
+
+/// Feeds the backup download. Once the headers are out the status can no longer change,
+/// so an error is logged and aborts the body instead.
+async fn write_backup(state: Arc<AppState>, mut tx: mpsc::Sender<Chunk>) {
+    let mut chunks = (&mut tx).with(|bytes| future::ok::<Chunk, mpsc::SendError>(Ok(bytes)));
+    let Err(e) = backup::write_backup(&state.pool, &mut chunks).await else {
+        return;
+    };
+    // Nobody to tell if the client went away
+    if !tx.is_closed() {
+        tracing::error!("Backup failed: {e}");
+        let _ = tx.send(Err(e)).await;
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/restore",
+    tag = "admin",
+    params(RestoreQuery),
+    request_body = Backup,
+    responses(
+        (status = 200, description = "What was restored", body = RestoreSummary),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Over 256 MiB; restore it with `eemee-admin import backup` instead")
+    )
+)]
+pub async fn restore_backup(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RestoreQuery>,
+    body: Bytes,
+) -> Result<Json<RestoreSummary>, AppError> {
+    let archive = backup::parse(&body)?;
+    backup::validate(&archive, state.embedding.model())?;
+    Ok(Json(
+        backup::restore(&state.pool, &archive, query.mode).await?,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/jobs",
+    tag = "admin",
+    params(JobQuery),
+    responses(
+        (status = 200, description = "Embedding jobs, dead ones first", body = Vec<EmbeddingJob>)
+    )
+)]
+pub async fn list_jobs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<JobQuery>,
+) -> Result<Json<Vec<EmbeddingJob>>, AppError> {
+    Ok(Json(jobs::list_jobs(&state.pool, &query).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/jobs/{id}/retry",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Done", body = serde_json::Value, example = json!({"ok": true})),
+        (status = 404, description = "Job not found", body = ErrorBody)
+    )
+)]
+pub async fn retry_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    jobs::retry_job(&state.pool, id).await?;
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use uuid::Uuid;
+
+use crate::error::{AppError, ErrorBody};
+use crate::models::collection::{
+    AddCollectionPhraseRequest, Collection, CollectionDetail, CollectionPhrase,
+    CreateCollectionRequest, ReorderCollectionRequest, UpdateCollectionRequest,
+};
+use crate::services::db;
+use crate::state::AppState;
+
+async fn load_detail(state: &AppState, id: Uuid) -> Result<CollectionDetail, AppError> {
+    let collection = db::get_collection(&state.pool, id).await?;
+    let rows = db::get_collection_phrases(&state.pool, id).await?;
+    Ok(CollectionDetail {
+        collection,
+        phrases: rows.into_iter().map(CollectionPhrase::from).collect(),
+    })
+}
+
+fn validate_name(name: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "Collection name must not be empty".into(),
+        ));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/collections",
+    tag = "collections",
+    responses(
+        (status = 200, description = "All collections", body = Vec<Collection>)
+    )
+)]
+pub async fn list_collections(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Collection>>, AppError> {
+    let rows = db::list_collections(&state.pool).await?;
+    Ok(Json(rows))
+}
+
+#[utoipa::path(
+    post,
+    path = "/collections",
+    tag = "collections",
+    request_body = CreateCollectionRequest,
+    responses(
+        (status = 200, description = "The new collection", body = Collection),
+        (status = 400, description = "Invalid request", body = ErrorBody)
+    )
+)]
+pub async fn create_collection(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateCollectionRequest>,
+) -> Result<Json<Collection>, AppError> {
+    validate_name(&req.name)?;
+    let row = db::create_collection(&state.pool, &req.name, req.description.as_deref()).await?;
+    Ok(Json(row))
+}
+
+#[utoipa::path(
+    get,
+    path = "/collections/{id}",
+    tag = "collections",
+    params(("id" = Uuid, Path, description = "Collection id")),
+    responses(
+        (status = 200, description = "The collection with its phrases", body = CollectionDetail),
+        (status = 404, description = "Collection not found", body = ErrorBody)
+    )
+)]
+pub async fn get_collection(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CollectionDetail>, AppError> {
+    Ok(Json(load_detail(&state, id).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/collections/{id}",
+    tag = "collections",
+    params(("id" = Uuid, Path, description = "Collection id")),
+    request_body = UpdateCollectionRequest,
+    responses(
+        (status = 200, description = "The updated collection", body = Collection),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Collection not found", body = ErrorBody)
+    )
+)]
+pub async fn update_collection(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateCollectionRequest>,
+) -> Result<Json<Collection>, AppError> {
+    if let Some(name) = &req.name {
+        validate_name(name)?;
+    }
+    let row = db::update_collection(
+        &state.pool,
+        id,
+        req.name.as_deref(),
+        req.description.as_deref(),
+    )
+    .await?;
+    Ok(Json(row))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/collections/{id}",
+    tag = "collections",
+    params(("id" = Uuid, Path, description = "Collection id")),
+    responses(
+        (status = 200, description = "Done", body = serde_json::Value, example = json!({"ok": true})),
+        (status = 404, description = "Collection not found", body = ErrorBody)
+    )
+)]
+pub async fn delete_collection(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    db::delete_collection(&state.pool, id).await?;
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/collections/{id}/phrases",
+    tag = "collections",
+    params(("id" = Uuid, Path, description = "Collection id")),
+    request_body = AddCollectionPhraseRequest,
+    responses(
+        (status = 200, description = "The collection with its phrases", body = CollectionDetail),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Collection or phrase not found", body = ErrorBody)
+    )
+)]
+pub async fn add_phrase(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<AddCollectionPhraseRequest>,
+) -> Result<Json<CollectionDetail>, AppError> {
+    db::add_phrase_to_collection(&state.pool, id, req.phrase_id, req.position).await?;
+    Ok(Json(load_detail(&state, id).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/collections/{id}/phrases",
+    tag = "collections",
+    params(("id" = Uuid, Path, description = "Collection id")),
+    request_body = ReorderCollectionRequest,
+    responses(
+        (status = 200, description = "The collection with its phrases", body = CollectionDetail),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Collection not found", body = ErrorBody)
+    )
+)]
+pub async fn reorder_phrases(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ReorderCollectionRequest>,
+) -> Result<Json<CollectionDetail>, AppError> {
+    db::reorder_collection(&state.pool, id, &req.phrase_ids).await?;
+    Ok(Json(load_detail(&state, id).await?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/collections/{id}/phrases/{phrase_id}",
+    tag = "collections",
+    params(("id" = Uuid, Path, description = "Collection id"), ("phrase_id" = Uuid, Path, description = "Phrase id")),
+    responses(
+        (status = 200, description = "The collection with its phrases", body = CollectionDetail),
+        (status = 404, description = "Collection or phrase not found", body = ErrorBody)
+    )
+)]
+pub async fn remove_phrase(
+    State(state): State<Arc<AppState>>,
+    Path((id, phrase_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<CollectionDetail>, AppError> {
+    db::remove_phrase_from_collection(&state.pool, id, phrase_id).await?;
+    Ok(Json(load_detail(&state, id).await?))
+}
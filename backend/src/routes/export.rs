@@ -1,83 +1,140 @@
 use std::sync::Arc;
 
+use axum::body::Body;
 use axum::extract::{Query, State};
 use axum::http::header;
 use axum::response::{IntoResponse, Response};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
 
-use crate::error::AppError;
-use crate::models::phrase::{ExportQuery, Phrase};
+use crate::error::{AppError, ErrorBody};
+use crate::models::phrase::{ExportQuery, Phrase, PhraseFilter};
+use crate::services::anki::ApkgWriter;
 use crate::services::db;
+use crate::services::export::{Encoder, ExportFormat};
+use crate::services::markdown::VaultWriter;
 use crate::state::AppState;
 
+/// Encoded chunks buffered ahead of a slow client before the database cursor pauses.
+const EXPORT_BUFFER: usize = 32;
+
+type Chunk = Result<Vec<u8>, AppError>;
+
+#[utoipa::path(
+    get,
+    path = "/export",
+    tag = "export",
+    params(ExportQuery, PhraseFilter),
+    responses(
+        (status = 200, description = "A download in the requested format: JSON, NDJSON, CSV, an Anki package or a zipped Markdown vault"),
+        (status = 400, description = "Invalid request", body = ErrorBody)
+    )
+)]
 pub async fn export(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ExportQuery>,
 ) -> Result<Response, AppError> {
-    let rows = db::get_all_phrases(&state.pool).await?;
-    let phrases: Vec<Phrase> = rows.into_iter().map(Phrase::from).collect();
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(AppError::BadRequest(
+            "from must not be later than to".into(),
+        ));
+    }
 
     match query.format.as_str() {
-        "csv" => {
-            let mut writer = csv::Writer::from_writer(Vec::new());
-            // Header
-            writer
-                .write_record([
-                    "id",
-                    "phrase",
-                    "meanings",
-                    "source",
-                    "tags",
-                    "memo",
-                    "created_at",
-                    "updated_at",
-                ])
-                .map_err(|e| AppError::Internal(e.to_string()))?;
+        "anki" => return export_anki(&state, &query).await,
+        "markdown" => return export_markdown(&state, &query).await,
+        _ => {}
+    }
 
-            for p in &phrases {
-                writer
-                    .write_record([
-                        &p.id.to_string(),
-                        &p.phrase,
-                        &p.meanings.join(" | "),
-                        p.source.as_deref().unwrap_or(""),
-                        &p.tags.join(", "),
-                        p.memo.as_deref().unwrap_or(""),
-                        &p.created_at.to_rfc3339(),
-                        &p.updated_at.to_rfc3339(),
-                    ])
-                    .map_err(|e| AppError::Internal(e.to_string()))?;
-            }
+    let format = ExportFormat::parse(&query.format);
+    let (tx, rx) = mpsc::channel::<Chunk>(EXPORT_BUFFER);
+    tokio::spawn(write_export(state, query, format, tx));
 
-            let csv_data = writer
-                .into_inner()
-                .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type()),
+            (header::CONTENT_DISPOSITION, format.content_disposition()),
+        ],
+        Body::from_stream(rx),
+    )
+        .into_response())
+}
 
-            Ok((
-                [
-                    (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
-                    (
-                        header::CONTENT_DISPOSITION,
-                        "attachment; filename=\"eemee-export.csv\"",
-                    ),
-                ],
-                csv_data,
-            )
-                .into_response())
-        }
-        _ => {
-            let json_data = serde_json::to_string_pretty(&phrases)
-                .map_err(|e| AppError::Internal(e.to_string()))?;
-            Ok((
-                [
-                    (header::CONTENT_TYPE, "application/json; charset=utf-8"),
-                    (
-                        header::CONTENT_DISPOSITION,
-                        "attachment; filename=\"eemee-export.json\"",
-                    ),
-                ],
-                json_data,
-            )
-                .into_response())
+/// Feeds the response body from a database cursor. Once the headers are out the status
+/// can no longer change, so an error is logged and aborts the body instead.
+async fn write_export(
+    state: Arc<AppState>,
+    query: ExportQuery,
+    format: ExportFormat,
+    mut tx: mpsc::Sender<Chunk>,
+) {
+    let mut encoder = Encoder::new(format);
+    let mut rows = db::stream_phrases(&state.pool, &query.filter, query.from, query.to);
+
+    let mut chunk = encoder.header();
+    while chunk.is_ok() {
+        let Some(row) = rows.next().await else {
+            break;
+        };
+        if tx.send(chunk).await.is_err() {
+            // The client went away; dropping the stream releases the connection
+            return;
         }
+        chunk = row.and_then(|row| encoder.row(&Phrase::from(row)));
+    }
+
+    let last = chunk.map(|mut bytes| {
+        bytes.extend(encoder.footer());
+        bytes
+    });
+    if let Err(e) = &last {
+        tracing::error!("Export failed: {e}");
     }
+    let _ = tx.send(last).await;
+}
+
+/// An `.apkg` is a SQLite database, so it is built in full before responding. Rows are
+/// still read from a cursor and written straight into the package.
+async fn export_anki(state: &AppState, query: &ExportQuery) -> Result<Response, AppError> {
+    let mut writer = ApkgWriter::create().await?;
+    let mut rows = db::stream_phrases(&state.pool, &query.filter, query.from, query.to);
+    while let Some(row) = rows.next().await {
+        writer.add(&Phrase::from(row?)).await?;
+    }
+    let package = writer.finish().await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/apkg"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"eemee.apkg\"",
+            ),
+        ],
+        package,
+    )
+        .into_response())
+}
+
+async fn export_markdown(state: &AppState, query: &ExportQuery) -> Result<Response, AppError> {
+    let mut writer = VaultWriter::new(query.group);
+    let mut rows = db::stream_phrases(&state.pool, &query.filter, query.from, query.to);
+    while let Some(row) = rows.next().await {
+        writer.add(Phrase::from(row?))?;
+    }
+    let archive = writer.finish()?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"eemee-markdown.zip\"",
+            ),
+        ],
+        archive,
+    )
+        .into_response())
 }
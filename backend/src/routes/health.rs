@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+
+use crate::models::health::{Readiness, ReadinessStatus};
+use crate::services::health;
+use crate::state::AppState;
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Always `ok`", body = String, content_type = "text/plain")
+    )
+)]
+pub async fn health() -> &'static str {
+    "ok"
+}
+
+/// Liveness: the process is up and serving. No dependency is checked, so an outage
+/// elsewhere doesn't get the container restarted.
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses(
+        (status = 200, description = "The process is serving", body = serde_json::Value, example = json!({"status": "alive"}))
+    )
+)]
+pub async fn live() -> Json<Value> {
+    Json(json!({ "status": "alive" }))
+}
+
+/// Readiness: 200 when every dependency checks out, 503 so traffic goes elsewhere otherwise.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every dependency checks out", body = Readiness),
+        (status = 503, description = "A dependency failed its check", body = Readiness)
+    )
+)]
+pub async fn ready(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Readiness>) {
+    let readiness = health::readiness(&state).await;
+    let status = match readiness.status {
+        ReadinessStatus::Ready => StatusCode::OK,
+        ReadinessStatus::NotReady => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(readiness))
+}
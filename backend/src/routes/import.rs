@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::State;
+
+use crate::error::AppError;
+use crate::models::import::ImportSummary;
+use crate::services::import;
+use crate::state::AppState;
+
+/// Imports the highlights of a `My Clippings.txt` as drafts: phrases without meanings,
+/// which are not embedded until a meaning is added.
+#[utoipa::path(
+    post,
+    path = "/import/kindle",
+    tag = "import",
+    request_body(content = String, content_type = "text/plain", description = "A Kindle `My Clippings.txt`"),
+    responses(
+        (status = 200, description = "What was imported", body = ImportSummary)
+    )
+)]
+pub async fn import_kindle(
+    State(state): State<Arc<AppState>>,
+    body: String,
+) -> Result<Json<ImportSummary>, AppError> {
+    Ok(Json(import::import_kindle(&state.pool, &body).await?))
+}
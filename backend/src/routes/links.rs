@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use uuid::Uuid;
+
+use crate::error::{AppError, ErrorBody};
+use crate::models::link::{CreateLinkRequest, GraphEdge, GraphQuery, PhraseGraph, PhraseLink};
+use crate::services::db;
+use crate::state::AppState;
+
+/// Deepest neighborhood the graph endpoint will expand.
+const MAX_GRAPH_DEPTH: i32 = 3;
+
+#[utoipa::path(
+    post,
+    path = "/phrases/{id}/links",
+    tag = "links",
+    params(("id" = Uuid, Path, description = "Phrase id")),
+    request_body = CreateLinkRequest,
+    responses(
+        (status = 200, description = "The new link, seen from the phrase", body = PhraseLink),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Phrase not found", body = ErrorBody)
+    )
+)]
+pub async fn create_link(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<CreateLinkRequest>,
+) -> Result<Json<PhraseLink>, AppError> {
+    let row = db::create_link(
+        &state.pool,
+        id,
+        req.target_id,
+        req.link_type,
+        req.note.as_deref(),
+    )
+    .await?;
+    Ok(Json(PhraseLink::from(row)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/phrases/{id}/links/{link_id}",
+    tag = "links",
+    params(("id" = Uuid, Path, description = "Phrase id"), ("link_id" = Uuid, Path, description = "Link id")),
+    responses(
+        (status = 200, description = "Done", body = serde_json::Value, example = json!({"ok": true})),
+        (status = 404, description = "Link not found", body = ErrorBody)
+    )
+)]
+pub async fn delete_link(
+    State(state): State<Arc<AppState>>,
+    Path((id, link_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    db::delete_link(&state.pool, id, link_id).await?;
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/phrases/{id}/graph",
+    tag = "links",
+    params(("id" = Uuid, Path, description = "Phrase id"), GraphQuery),
+    responses(
+        (status = 200, description = "Phrases within `depth` links", body = PhraseGraph),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Phrase not found", body = ErrorBody)
+    )
+)]
+pub async fn get_graph(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<GraphQuery>,
+) -> Result<Json<PhraseGraph>, AppError> {
+    if !(0..=MAX_GRAPH_DEPTH).contains(&query.depth) {
+        return Err(AppError::BadRequest(
+            format!("depth must be between 0 and {MAX_GRAPH_DEPTH}").into(),
+        ));
+    }
+
+    let (nodes, edges) = db::get_phrase_graph(&state.pool, id, query.depth).await?;
+    let edges = edges.into_iter().map(GraphEdge::from).collect();
+    Ok(Json(PhraseGraph { nodes, edges }))
+}
@@ -1,33 +1,144 @@
+pub mod admin;
+pub mod collections;
 pub mod export;
+pub mod health;
+pub mod import;
+pub mod links;
+pub mod openapi;
 pub mod phrases;
+pub mod saved_searches;
 pub mod search;
+pub mod tags;
 
 use std::sync::Arc;
 
 use axum::Router;
-use axum::routing::{get, post};
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::header;
+use axum::middleware;
+use axum::response::IntoResponse;
+use axum::routing::{MethodRouter, delete, get, post, put};
 
+use crate::error::AppError;
+use crate::services::metrics::METRICS;
+use crate::services::rate_limit;
 use crate::state::AppState;
 
-pub fn api_router(state: Arc<AppState>) -> Router {
-    Router::new()
-        .route("/health", get(health))
-        .route(
+/// Backups carry every embedding, roughly 40 KB of JSON per meaning, and an upload is
+/// held in memory while it is checked and restored; this allows some 6000 meanings.
+/// Larger backups are restored with `eemee-admin import backup`.
+const MAX_RESTORE_BYTES: usize = 256 * 1024 * 1024;
+/// Years of clippings stay well under this.
+const MAX_CLIPPINGS_BYTES: usize = 32 * 1024 * 1024;
+
+/// Every `/api` route with what serves it, in one place: [`api_router`] serves these, and
+/// the OpenAPI document is checked against them.
+pub fn api_routes(state: &Arc<AppState>) -> Vec<(&'static str, MethodRouter<Arc<AppState>>)> {
+    // On the handlers that call the embedding API
+    let limited = || middleware::from_fn_with_state(state.clone(), rate_limit::limit);
+
+    vec![
+        ("/health", get(health::health)),
+        ("/health/live", get(health::live)),
+        ("/health/ready", get(health::ready)),
+        ("/openapi.json", get(openapi::openapi)),
+        (
             "/phrases",
-            get(phrases::list_random_phrases).post(phrases::create_phrase),
-        )
-        .route(
+            get(phrases::list_random_phrases).merge(post(phrases::create_phrase).layer(limited())),
+        ),
+        (
             "/phrases/{id}",
             get(phrases::get_phrase)
-                .put(phrases::update_phrase)
+                .merge(put(phrases::update_phrase).layer(limited()))
                 .delete(phrases::delete_phrase),
-        )
-        .route("/search/semantic", post(search::semantic_search))
-        .route("/search/text", get(search::text_search))
-        .route("/export", get(export::export))
+        ),
+        ("/inbox", get(phrases::list_inbox)),
+        ("/phrases/{id}/similar", get(phrases::similar_phrases)),
+        ("/phrases/{id}/links", post(links::create_link)),
+        ("/phrases/{id}/links/{link_id}", delete(links::delete_link)),
+        ("/phrases/{id}/graph", get(links::get_graph)),
+        (
+            "/collections",
+            get(collections::list_collections).post(collections::create_collection),
+        ),
+        (
+            "/collections/{id}",
+            get(collections::get_collection)
+                .put(collections::update_collection)
+                .delete(collections::delete_collection),
+        ),
+        (
+            "/collections/{id}/phrases",
+            post(collections::add_phrase).put(collections::reorder_phrases),
+        ),
+        (
+            "/collections/{id}/phrases/{phrase_id}",
+            delete(collections::remove_phrase),
+        ),
+        (
+            "/search/semantic",
+            post(search::semantic_search).layer(limited()),
+        ),
+        ("/search/text", get(search::text_search)),
+        (
+            "/search/history",
+            get(search::list_history).delete(search::clear_history),
+        ),
+        (
+            "/saved-searches",
+            get(saved_searches::list_saved_searches).post(saved_searches::create_saved_search),
+        ),
+        (
+            "/saved-searches/{id}",
+            get(saved_searches::get_saved_search)
+                .put(saved_searches::update_saved_search)
+                .delete(saved_searches::delete_saved_search),
+        ),
+        (
+            "/saved-searches/{id}/run",
+            get(saved_searches::run_saved_search).layer(limited()),
+        ),
+        ("/tags/suggest", post(tags::suggest_tags).layer(limited())),
+        ("/export", get(export::export)),
+        (
+            "/import/kindle",
+            post(import::import_kindle).layer(DefaultBodyLimit::max(MAX_CLIPPINGS_BYTES)),
+        ),
+        (
+            "/admin/vector-index",
+            get(admin::vector_index_status)
+                .post(admin::build_vector_index)
+                .delete(admin::drop_vector_index),
+        ),
+        ("/admin/jobs", get(admin::list_jobs)),
+        ("/admin/jobs/{id}/retry", post(admin::retry_job)),
+        ("/admin/usage", get(admin::embedding_usage)),
+        ("/admin/backup", get(admin::create_backup)),
+        (
+            "/admin/restore",
+            post(admin::restore_backup).layer(DefaultBodyLimit::max(MAX_RESTORE_BYTES)),
+        ),
+    ]
+}
+
+pub fn api_router(state: Arc<AppState>) -> Router {
+    api_routes(&state)
+        .into_iter()
+        .fold(Router::new(), |router, (path, method_router)| {
+            router.route(path, method_router)
+        })
+        .with_state(state)
+}
+
+/// The Prometheus scrape endpoint, served at the root rather than behind the login when
+/// `expose_metrics` is set.
+pub fn metrics_router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
         .with_state(state)
 }
 
-async fn health() -> &'static str {
-    "ok"
+async fn metrics(State(state): State<Arc<AppState>>) -> Result<impl IntoResponse, AppError> {
+    let body = METRICS.render(&state.pool).await?;
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body))
 }
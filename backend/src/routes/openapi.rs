@@ -0,0 +1,141 @@
+//! OpenAPI 3 document for [`super::api_routes`], generated from the handler annotations
+//! and the request and response types, so clients can generate their types from it.
+
+use axum::Json;
+use utoipa::openapi::security::{
+    ApiKey, ApiKeyValue, Http, HttpAuthScheme, SecurityRequirement, SecurityScheme,
+};
+use utoipa::openapi::{ContentBuilder, OpenApi as Document, Ref, RefOr, Response, ResponseBuilder};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use crate::auth::middleware::is_public;
+use crate::error::ErrorBody;
+use crate::routes::{
+    admin, collections, export, health, import, links, phrases, saved_searches, search, tags,
+};
+
+/// Name of the session cookie set by the login flow.
+const SESSION_COOKIE: &str = "id";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "eemee API"),
+    servers((url = "/api")),
+    paths(
+        health::health,
+        health::live,
+        health::ready,
+        openapi,
+        phrases::list_random_phrases,
+        phrases::create_phrase,
+        phrases::get_phrase,
+        phrases::update_phrase,
+        phrases::delete_phrase,
+        phrases::list_inbox,
+        phrases::similar_phrases,
+        links::create_link,
+        links::delete_link,
+        links::get_graph,
+        collections::list_collections,
+        collections::create_collection,
+        collections::get_collection,
+        collections::update_collection,
+        collections::delete_collection,
+        collections::add_phrase,
+        collections::reorder_phrases,
+        collections::remove_phrase,
+        search::semantic_search,
+        search::text_search,
+        search::list_history,
+        search::clear_history,
+        saved_searches::list_saved_searches,
+        saved_searches::create_saved_search,
+        saved_searches::get_saved_search,
+        saved_searches::update_saved_search,
+        saved_searches::delete_saved_search,
+        saved_searches::run_saved_search,
+        tags::suggest_tags,
+        export::export,
+        import::import_kindle,
+        admin::vector_index_status,
+        admin::build_vector_index,
+        admin::drop_vector_index,
+        admin::list_jobs,
+        admin::retry_job,
+        admin::embedding_usage,
+        admin::create_backup,
+        admin::restore_backup,
+    ),
+    components(schemas(ErrorBody)),
+    modifiers(&CommonResponses)
+)]
+pub struct ApiDoc;
+
+/// Adds the errors any handler can return, and the session or API token the routes behind
+/// the login require, rather than repeating them on every handler.
+struct CommonResponses;
+
+impl Modify for CommonResponses {
+    fn modify(&self, openapi: &mut Document) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new(SESSION_COOKIE))),
+        );
+        components.add_security_scheme(
+            "token",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+        );
+
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            if is_public(path) {
+                continue;
+            }
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                // Either one will do
+                operation.security.get_or_insert_with(Vec::new).extend(
+                    ["session", "token"]
+                        .map(|scheme| SecurityRequirement::new(scheme, Vec::<String>::new())),
+                );
+                let responses = &mut operation.responses.responses;
+                responses
+                    .entry("401".to_string())
+                    .or_insert_with(|| error_response("Not logged in, or an unknown API token"));
+                responses
+                    .entry("500".to_string())
+                    .or_insert_with(|| error_response("Database or embedding service failure"));
+            }
+        }
+    }
+}
+
+fn error_response(description: &str) -> RefOr<Response> {
+    ResponseBuilder::new()
+        .description(description)
+        .content(
+            "application/json",
+            ContentBuilder::new()
+                .schema(Some(Ref::from_schema_name(ErrorBody::name())))
+                .build(),
+        )
+        .build()
+        .into()
+}
+
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "meta",
+    responses(
+        (status = 200, description = "This document", body = serde_json::Value)
+    )
+)]
+pub async fn openapi() -> Json<Document> {
+    Json(ApiDoc::openapi())
+}
@@ -2,18 +2,33 @@ use std::sync::Arc;
 
 use axum::Json;
 use axum::extract::{Path, Query, State};
+use utoipa::IntoParams;
 use uuid::Uuid;
 
-use crate::error::AppError;
-use crate::models::phrase::{CreatePhraseRequest, Phrase, UpdatePhraseRequest};
-use crate::services::db;
+use crate::error::{AppError, ErrorBody};
+use crate::models::link::PhraseLink;
+use crate::models::phrase::{
+    CreatePhraseRequest, InboxQuery, Phrase, PhraseDetail, PhraseFilter, ScoredPhrase,
+    SimilarQuery, UpdatePhraseRequest,
+};
+use crate::services::{db, trash};
 use crate::state::AppState;
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListPhrasesQuery {
     pub limit: Option<i64>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/phrases",
+    tag = "phrases",
+    params(ListPhrasesQuery),
+    responses(
+        (status = 200, description = "Random phrases", body = Vec<Phrase>)
+    )
+)]
 pub async fn list_random_phrases(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListPhrasesQuery>,
@@ -23,19 +38,42 @@ pub async fn list_random_phrases(
     Ok(Json(rows.into_iter().map(Phrase::from).collect()))
 }
 
+/// Drafts, newest first: phrases saved without a meaning yet.
+#[utoipa::path(
+    get,
+    path = "/inbox",
+    tag = "phrases",
+    params(InboxQuery, PhraseFilter),
+    responses(
+        (status = 200, description = "Drafts, newest first", body = Vec<Phrase>)
+    )
+)]
+pub async fn list_inbox(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<InboxQuery>,
+) -> Result<Json<Vec<Phrase>>, AppError> {
+    let rows = db::list_drafts(&state.pool, query.limit, &query.filter).await?;
+    Ok(Json(rows.into_iter().map(Phrase::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/phrases",
+    tag = "phrases",
+    request_body = CreatePhraseRequest,
+    responses(
+        (status = 200, description = "The saved phrase", body = Phrase),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 429, description = "Rate limited; see Retry-After", body = ErrorBody)
+    )
+)]
 pub async fn create_phrase(
     State(state): State<Arc<AppState>>,
     Json(req): Json<CreatePhraseRequest>,
 ) -> Result<Json<Phrase>, AppError> {
-    if req.meanings.is_empty() || req.meanings.iter().any(|m| m.trim().is_empty()) {
-        return Err(AppError::BadRequest(
-            "At least one non-empty meaning is required".to_string(),
-        ));
-    }
-
-    let mut embeddings = Vec::with_capacity(req.meanings.len());
-    for meaning in &req.meanings {
-        embeddings.push(state.embedding.embed(meaning).await?);
+    // No meanings saves a draft. Meanings are embedded in the background.
+    if req.meanings.iter().any(|m| m.trim().is_empty()) {
+        return Err(AppError::BadRequest("Meanings must not be empty".into()));
     }
 
     let row = db::create_phrase(
@@ -45,40 +83,83 @@ pub async fn create_phrase(
         req.source.as_deref(),
         &req.tags,
         req.memo.as_deref(),
-        &embeddings,
     )
     .await?;
     Ok(Json(Phrase::from(row)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/phrases/{id}",
+    tag = "phrases",
+    params(("id" = Uuid, Path, description = "Phrase id")),
+    responses(
+        (status = 200, description = "The phrase with its links", body = PhraseDetail),
+        (status = 404, description = "Phrase not found", body = ErrorBody)
+    )
+)]
 pub async fn get_phrase(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
-) -> Result<Json<Phrase>, AppError> {
+) -> Result<Json<PhraseDetail>, AppError> {
     let row = db::get_phrase(&state.pool, id).await?;
-    Ok(Json(Phrase::from(row)))
+    let links = db::get_phrase_links(&state.pool, id)
+        .await?
+        .into_iter()
+        .map(PhraseLink::from)
+        .collect();
+    Ok(Json(PhraseDetail {
+        phrase: Phrase::from(row),
+        links,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/phrases/{id}/similar",
+    tag = "phrases",
+    params(("id" = Uuid, Path, description = "Phrase id"), SimilarQuery, PhraseFilter),
+    responses(
+        (status = 200, description = "Most similar phrases first", body = Vec<ScoredPhrase>),
+        (status = 404, description = "Phrase not found", body = ErrorBody)
+    )
+)]
+pub async fn similar_phrases(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SimilarQuery>,
+) -> Result<Json<Vec<ScoredPhrase>>, AppError> {
+    // 404 for unknown phrases rather than an empty list
+    db::get_phrase(&state.pool, id).await?;
+    let rows = db::similar_phrases(&state.pool, id, query.limit, &query.filter).await?;
+    Ok(Json(rows.into_iter().map(ScoredPhrase::from).collect()))
 }
 
+#[utoipa::path(
+    put,
+    path = "/phrases/{id}",
+    tag = "phrases",
+    params(("id" = Uuid, Path, description = "Phrase id")),
+    request_body = UpdatePhraseRequest,
+    responses(
+        (status = 200, description = "The updated phrase", body = Phrase),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Phrase not found", body = ErrorBody),
+        (status = 429, description = "Rate limited; see Retry-After", body = ErrorBody)
+    )
+)]
 pub async fn update_phrase(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
     Json(req): Json<UpdatePhraseRequest>,
 ) -> Result<Json<Phrase>, AppError> {
-    let (meanings, embeddings) = match &req.meanings {
-        Some(meanings) => {
-            if meanings.is_empty() || meanings.iter().any(|m| m.trim().is_empty()) {
-                return Err(AppError::BadRequest(
-                    "At least one non-empty meaning is required".to_string(),
-                ));
-            }
-            let mut embs = Vec::with_capacity(meanings.len());
-            for meaning in meanings {
-                embs.push(state.embedding.embed(meaning).await?);
-            }
-            (Some(meanings.as_slice()), Some(embs))
-        }
-        None => (None, None),
-    };
+    if let Some(meanings) = &req.meanings
+        && (meanings.is_empty() || meanings.iter().any(|m| m.trim().is_empty()))
+    {
+        return Err(AppError::BadRequest(
+            "At least one non-empty meaning is required".into(),
+        ));
+    }
 
     let row = db::update_phrase(
         &state.pool,
@@ -87,17 +168,27 @@ pub async fn update_phrase(
         req.source.as_deref(),
         req.tags.as_deref(),
         req.memo.as_deref(),
-        meanings,
-        embeddings.as_deref(),
+        req.meanings.as_deref(),
     )
     .await?;
     Ok(Json(Phrase::from(row)))
 }
 
+/// Moves the phrase to the trash, from which `eemee-admin trash restore` can put it back.
+#[utoipa::path(
+    delete,
+    path = "/phrases/{id}",
+    tag = "phrases",
+    params(("id" = Uuid, Path, description = "Phrase id")),
+    responses(
+        (status = 200, description = "Done", body = serde_json::Value, example = json!({"ok": true})),
+        (status = 404, description = "Phrase not found", body = ErrorBody)
+    )
+)]
 pub async fn delete_phrase(
     State(state): State<Arc<AppState>>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    db::delete_phrase(&state.pool, id).await?;
+    trash::trash_phrase(&state.pool, id).await?;
     Ok(Json(serde_json::json!({ "ok": true })))
 }
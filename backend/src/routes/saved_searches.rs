@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use uuid::Uuid;
+
+use crate::error::{AppError, ErrorBody};
+use crate::models::phrase::Phrase;
+use crate::models::search::{
+    CreateSavedSearchRequest, RunSavedSearchQuery, SavedSearch, SearchMode, SearchResults,
+    UpdateSavedSearchRequest,
+};
+use crate::routes::search::run_text_search;
+use crate::services::metrics::METRICS;
+use crate::services::{db, query, usage};
+use crate::state::AppState;
+
+fn validate(name: &str, mode: SearchMode, query_text: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "Saved search name must not be empty".into(),
+        ));
+    }
+    match mode {
+        SearchMode::Semantic if query_text.trim().is_empty() => Err(AppError::BadRequest(
+            "Semantic search query must not be empty".into(),
+        )),
+        SearchMode::Semantic => Ok(()),
+        // Reject queries that would fail on every run
+        SearchMode::Text => query::parse(query_text).map(drop).map_err(AppError::from),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/saved-searches",
+    tag = "saved-searches",
+    responses(
+        (status = 200, description = "All saved searches", body = Vec<SavedSearch>)
+    )
+)]
+pub async fn list_saved_searches(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<SavedSearch>>, AppError> {
+    let rows = db::list_saved_searches(&state.pool).await?;
+    Ok(Json(rows.into_iter().map(SavedSearch::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/saved-searches",
+    tag = "saved-searches",
+    request_body = CreateSavedSearchRequest,
+    responses(
+        (status = 200, description = "The saved search", body = SavedSearch),
+        (status = 400, description = "Invalid request", body = ErrorBody)
+    )
+)]
+pub async fn create_saved_search(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateSavedSearchRequest>,
+) -> Result<Json<SavedSearch>, AppError> {
+    validate(&req.name, req.mode, &req.query)?;
+    let row =
+        db::create_saved_search(&state.pool, &req.name, req.mode, &req.query, &req.filters).await?;
+    Ok(Json(SavedSearch::from(row)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/saved-searches/{id}",
+    tag = "saved-searches",
+    params(("id" = Uuid, Path, description = "Saved search id")),
+    responses(
+        (status = 200, description = "The saved search", body = SavedSearch),
+        (status = 404, description = "Saved search not found", body = ErrorBody)
+    )
+)]
+pub async fn get_saved_search(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SavedSearch>, AppError> {
+    let row = db::get_saved_search(&state.pool, id).await?;
+    Ok(Json(SavedSearch::from(row)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/saved-searches/{id}",
+    tag = "saved-searches",
+    params(("id" = Uuid, Path, description = "Saved search id")),
+    request_body = UpdateSavedSearchRequest,
+    responses(
+        (status = 200, description = "The updated saved search", body = SavedSearch),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Saved search not found", body = ErrorBody)
+    )
+)]
+pub async fn update_saved_search(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateSavedSearchRequest>,
+) -> Result<Json<SavedSearch>, AppError> {
+    let current = SavedSearch::from(db::get_saved_search(&state.pool, id).await?);
+    validate(
+        req.name.as_deref().unwrap_or(&current.name),
+        req.mode.unwrap_or(current.mode),
+        req.query.as_deref().unwrap_or(&current.query),
+    )?;
+
+    let row = db::update_saved_search(
+        &state.pool,
+        id,
+        req.name.as_deref(),
+        req.mode,
+        req.query.as_deref(),
+        req.filters.as_ref(),
+    )
+    .await?;
+    Ok(Json(SavedSearch::from(row)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/saved-searches/{id}",
+    tag = "saved-searches",
+    params(("id" = Uuid, Path, description = "Saved search id")),
+    responses(
+        (status = 200, description = "Done", body = serde_json::Value, example = json!({"ok": true})),
+        (status = 404, description = "Saved search not found", body = ErrorBody)
+    )
+)]
+pub async fn delete_saved_search(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    db::delete_saved_search(&state.pool, id).await?;
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+/// Runs a saved search. Semantic searches embed their query on the first run and reuse
+/// the stored embedding afterwards.
+#[utoipa::path(
+    get,
+    path = "/saved-searches/{id}/run",
+    tag = "saved-searches",
+    params(("id" = Uuid, Path, description = "Saved search id"), RunSavedSearchQuery),
+    responses(
+        (status = 200, description = "Results shaped like those of the search mode", body = SearchResults),
+        (status = 402, description = "Embedding budget used up", body = ErrorBody),
+        (status = 404, description = "Saved search not found", body = ErrorBody),
+        (status = 429, description = "Rate limited; see Retry-After", body = ErrorBody)
+    )
+)]
+pub async fn run_saved_search(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<RunSavedSearchQuery>,
+) -> Result<Json<SearchResults>, AppError> {
+    let saved = SavedSearch::from(db::get_saved_search(&state.pool, id).await?);
+
+    let results = match saved.mode {
+        SearchMode::Semantic => {
+            let embedding = match db::get_saved_search_embedding(&state.pool, id).await? {
+                Some(embedding) => embedding,
+                None => {
+                    usage::check_budget(&state.pool, &state.limits).await?;
+                    let embedding = state.embedding.embed(&saved.query).await?.vector;
+                    db::set_saved_search_embedding(&state.pool, id, &embedding).await?;
+                    embedding
+                }
+            };
+            let rows =
+                db::semantic_search(&state.pool, &embedding, params.limit, &saved.filters).await?;
+            SearchResults::Semantic(rows.into_iter().map(Phrase::from).collect())
+        }
+        SearchMode::Text => SearchResults::Text(
+            run_text_search(&state, &saved.query, params.limit, &saved.filters).await?,
+        ),
+    };
+
+    METRICS.record_search(saved.mode);
+    if params.record {
+        db::record_search(
+            &state.pool,
+            saved.mode,
+            &saved.query,
+            &saved.filters,
+            results.len(),
+        )
+        .await?;
+    }
+    Ok(Json(results))
+}
@@ -1,28 +1,330 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use axum::Json;
 use axum::extract::{Query, State};
+use pgvector::Vector;
+use uuid::Uuid;
 
-use crate::error::AppError;
-use crate::models::phrase::{Phrase, SemanticSearchRequest, TextSearchQuery};
-use crate::services::db;
+use crate::error::{AppError, ErrorBody};
+use crate::models::phrase::{
+    AnnParams, CombineMode, NearestMeaningRow, Phrase, PhraseFilter, PhraseWithMeaningsRow,
+    SemanticSearchRequest, TextSearchHit, TextSearchQuery,
+};
+use crate::models::search::{HistoryQuery, SearchHistoryEntry, SearchMode};
+use crate::services::metrics::METRICS;
+use crate::services::{db, query as search_query, rerank, text, usage, vectors};
 use crate::state::AppState;
 
+fn validate_ann_params(params: &AnnParams) -> Result<(), AppError> {
+    if params.ef_search.is_some_and(|ef| !(1..=1000).contains(&ef)) {
+        return Err(AppError::BadRequest(
+            "ef_search must be between 1 and 1000".into(),
+        ));
+    }
+    if params.probes.is_some_and(|probes| probes < 1) {
+        return Err(AppError::BadRequest("probes must be at least 1".into()));
+    }
+    Ok(())
+}
+
+/// Candidates fetched per requested result when reranking for diversity.
+const MMR_CANDIDATE_FACTOR: i64 = 5;
+const MAX_MMR_CANDIDATES: i64 = 200;
+/// Upper bound on queries plus examples in one search.
+const MAX_QUERY_VECTORS: usize = 10;
+/// Upper bound on `limit`, which also sizes the candidate fetches.
+const MAX_SEMANTIC_LIMIT: i64 = 100;
+
+fn validate_query_inputs(req: &SemanticSearchRequest) -> Result<(), AppError> {
+    let texts = req.weighted_texts();
+    let weights: Vec<f32> = texts
+        .iter()
+        .map(|&(_, weight)| weight)
+        .chain(req.examples.iter().map(|e| e.weight))
+        .collect();
+
+    if weights.is_empty() {
+        return Err(AppError::BadRequest(
+            "A query, queries or examples are required".into(),
+        ));
+    }
+    if weights.len() > MAX_QUERY_VECTORS {
+        return Err(AppError::BadRequest(
+            format!("At most {MAX_QUERY_VECTORS} queries and examples are allowed").into(),
+        ));
+    }
+    if req.queries.iter().any(|q| q.text.trim().is_empty()) {
+        return Err(AppError::BadRequest("Query texts must not be empty".into()));
+    }
+    if weights.iter().any(|w| !w.is_finite() || *w == 0.0) {
+        return Err(AppError::BadRequest(
+            "Weights must be non-zero numbers".into(),
+        ));
+    }
+    if weights.iter().all(|w| *w < 0.0) {
+        return Err(AppError::BadRequest(
+            "At least one query or example needs a positive weight".into(),
+        ));
+    }
+    if req.combine == CombineMode::Sum && req.ann_params().is_some() {
+        return Err(AppError::BadRequest(
+            "ef_search and probes require combine = centroid".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Embeds the query texts and averages the meanings of the example phrases, keeping the
+/// weights.
+async fn query_vectors(
+    state: &AppState,
+    req: &SemanticSearchRequest,
+) -> Result<Vec<(Vec<f32>, f32)>, AppError> {
+    let texts = req.weighted_texts();
+    if !texts.is_empty() {
+        usage::check_budget(&state.pool, &state.limits).await?;
+    }
+    let mut weighted = Vec::new();
+    for (text, weight) in texts {
+        let embedding = state.embedding.embed(text).await?.vector;
+        weighted.push((embedding.to_vec(), weight));
+    }
+    for example in &req.examples {
+        let embeddings = db::get_meaning_embeddings(&state.pool, example.phrase_id).await?;
+        if embeddings.is_empty() {
+            // 404 for unknown phrases; otherwise a draft or not embedded yet
+            db::get_phrase(&state.pool, example.phrase_id).await?;
+            return Err(AppError::BadRequest(
+                format!(
+                    "Example phrase {} has no embedded meanings yet",
+                    example.phrase_id
+                )
+                .into(),
+            ));
+        }
+        let slices: Vec<&[f32]> = embeddings.iter().map(Vector::as_slice).collect();
+        weighted.push((vectors::mean_direction(&slices), example.weight));
+    }
+    Ok(weighted)
+}
+
+#[utoipa::path(
+    post,
+    path = "/search/semantic",
+    tag = "search",
+    request_body = SemanticSearchRequest,
+    responses(
+        (status = 200, description = "Closest phrases first", body = Vec<Phrase>),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 402, description = "Embedding budget used up", body = ErrorBody),
+        (status = 404, description = "Example phrase not found", body = ErrorBody),
+        (status = 429, description = "Rate limited; see Retry-After", body = ErrorBody)
+    )
+)]
 pub async fn semantic_search(
     State(state): State<Arc<AppState>>,
     Json(req): Json<SemanticSearchRequest>,
 ) -> Result<Json<Vec<Phrase>>, AppError> {
-    let query_embedding = state.embedding.embed(&req.query).await?;
-    let rows = db::semantic_search(&state.pool, &query_embedding, req.limit).await?;
+    validate_query_inputs(&req)?;
+    if !(1..=MAX_SEMANTIC_LIMIT).contains(&req.limit) {
+        return Err(AppError::BadRequest(
+            format!("limit must be between 1 and {MAX_SEMANTIC_LIMIT}").into(),
+        ));
+    }
+    let ann_params = req.ann_params();
+    if let Some(params) = &ann_params {
+        validate_ann_params(params)?;
+    }
+    if req.diversity.is_some_and(|d| !(0.0..=1.0).contains(&d)) {
+        return Err(AppError::BadRequest(
+            "diversity must be between 0 and 1".into(),
+        ));
+    }
+
+    // Reranking needs room to skip near-duplicates, but never fewer than requested.
+    // Examples are dropped from the results, so fetch that many more.
+    let examples: HashSet<Uuid> = req.examples.iter().map(|e| e.phrase_id).collect();
+    let fetch = match req.diversity {
+        Some(_) => (req.limit * MMR_CANDIDATE_FACTOR)
+            .min(MAX_MMR_CANDIDATES)
+            .max(req.limit),
+        None => req.limit,
+    } + examples.len() as i64;
+
+    let inputs = query_vectors(&state, &req).await?;
+    // A single query is searched as-is, like before weighted queries existed
+    let query_embedding = match inputs.as_slice() {
+        [(vector, _)] if examples.is_empty() => Vector::from(vector.clone()),
+        _ => Vector::from(vectors::weighted_centroid(&inputs)),
+    };
+
+    let mut rows = match (req.combine, ann_params) {
+        (CombineMode::Sum, _) => {
+            let weighted: Vec<(Vector, f64)> = inputs
+                .into_iter()
+                .map(|(vector, weight)| (Vector::from(vector), f64::from(weight)))
+                .collect();
+            db::semantic_search_weighted(&state.pool, &weighted, fetch, &req.filter).await?
+        }
+        (CombineMode::Centroid, Some(params)) => {
+            db::semantic_search_approximate(
+                &state.pool,
+                &query_embedding,
+                fetch,
+                &req.filter,
+                params,
+            )
+            .await?
+        }
+        (CombineMode::Centroid, None) => {
+            db::semantic_search(&state.pool, &query_embedding, fetch, &req.filter).await?
+        }
+    };
+    rows.retain(|row| !examples.contains(&row.id));
+
+    match req.diversity {
+        // With combine = sum the centroid stands in for the query when judging relevance
+        Some(diversity) => {
+            rows = diversify(&state, &query_embedding, rows, req.limit, diversity).await?;
+        }
+        None => rows.truncate(usize::try_from(req.limit).unwrap_or(0)),
+    }
+
     let phrases: Vec<Phrase> = rows.into_iter().map(Phrase::from).collect();
+    METRICS.record_search(SearchMode::Semantic);
+    if req.record {
+        db::record_search(
+            &state.pool,
+            SearchMode::Semantic,
+            &req.summary(),
+            &req.filter,
+            phrases.len(),
+        )
+        .await?;
+    }
     Ok(Json(phrases))
 }
 
+/// Reorders relevance-ranked candidates with MMR, comparing phrases by the meaning that
+/// is closest to the query, and keeps the first `limit`.
+async fn diversify(
+    state: &AppState,
+    query_embedding: &Vector,
+    candidates: Vec<PhraseWithMeaningsRow>,
+    limit: i64,
+    diversity: f64,
+) -> Result<Vec<PhraseWithMeaningsRow>, AppError> {
+    let ids: Vec<Uuid> = candidates.iter().map(|row| row.id).collect();
+    let nearest: HashMap<Uuid, NearestMeaningRow> =
+        db::nearest_meaning_vectors(&state.pool, query_embedding, &ids)
+            .await?
+            .into_iter()
+            .map(|row| (row.phrase_id, row))
+            .collect();
+
+    // Every candidate has at least one meaning, unless it was deleted meanwhile
+    let (rows, meanings): (Vec<PhraseWithMeaningsRow>, Vec<&NearestMeaningRow>) = candidates
+        .into_iter()
+        .filter_map(|row| nearest.get(&row.id).map(|meaning| (row, meaning)))
+        .unzip();
+    let relevance: Vec<f64> = meanings.iter().map(|m| m.similarity).collect();
+    let vectors: Vec<&[f32]> = meanings.iter().map(|m| m.embedding.as_slice()).collect();
+
+    let order = rerank::mmr(
+        &relevance,
+        &vectors,
+        usize::try_from(limit).unwrap_or(0),
+        diversity,
+    );
+    let mut rows: Vec<Option<PhraseWithMeaningsRow>> = rows.into_iter().map(Some).collect();
+    Ok(order.into_iter().filter_map(|i| rows[i].take()).collect())
+}
+
+/// Runs a text search and highlights each hit. Shared with saved searches.
+pub(crate) async fn run_text_search(
+    state: &AppState,
+    q: &str,
+    limit: i64,
+    filter: &PhraseFilter,
+) -> Result<Vec<TextSearchHit>, AppError> {
+    let parsed = search_query::parse(q)?;
+    let terms = parsed.positive_terms();
+    let rows = db::text_search(&state.pool, &parsed, limit, filter).await?;
+    let hits = rows
+        .into_iter()
+        .map(|row| {
+            let phrase = Phrase::from(row.phrase);
+            let highlights = text::highlight_phrase(&phrase, &terms);
+            TextSearchHit {
+                phrase,
+                score: row.score,
+                highlights,
+            }
+        })
+        .collect();
+    Ok(hits)
+}
+
+#[utoipa::path(
+    get,
+    path = "/search/text",
+    tag = "search",
+    params(TextSearchQuery, PhraseFilter),
+    responses(
+        (status = 200, description = "Best matches first", body = Vec<TextSearchHit>),
+        (status = 400, description = "Invalid request", body = ErrorBody)
+    )
+)]
 pub async fn text_search(
     State(state): State<Arc<AppState>>,
     Query(query): Query<TextSearchQuery>,
-) -> Result<Json<Vec<Phrase>>, AppError> {
-    let rows = db::text_search(&state.pool, &query.q, query.limit).await?;
-    let phrases: Vec<Phrase> = rows.into_iter().map(Phrase::from).collect();
-    Ok(Json(phrases))
+) -> Result<Json<Vec<TextSearchHit>>, AppError> {
+    let hits = run_text_search(&state, &query.q, query.limit, &query.filter).await?;
+    METRICS.record_search(SearchMode::Text);
+    if query.record {
+        db::record_search(
+            &state.pool,
+            SearchMode::Text,
+            &query.q,
+            &query.filter,
+            hits.len(),
+        )
+        .await?;
+    }
+    Ok(Json(hits))
+}
+
+#[utoipa::path(
+    get,
+    path = "/search/history",
+    tag = "search",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Recent searches, newest first", body = Vec<SearchHistoryEntry>)
+    )
+)]
+pub async fn list_history(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<SearchHistoryEntry>>, AppError> {
+    let rows = db::list_search_history(&state.pool, query.limit).await?;
+    Ok(Json(
+        rows.into_iter().map(SearchHistoryEntry::from).collect(),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/search/history",
+    tag = "search",
+    responses(
+        (status = 200, description = "Done", body = serde_json::Value, example = json!({"ok": true}))
+    )
+)]
+pub async fn clear_history(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    db::clear_search_history(&state.pool).await?;
+    Ok(Json(serde_json::json!({ "ok": true })))
 }
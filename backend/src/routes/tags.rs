@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::State;
+use uuid::Uuid;
+
+use crate::error::{AppError, ErrorBody};
+use crate::models::tag::{TagNeighbour, TagSuggestRequest, TagSuggestion};
+use crate::services::{db, tags, usage};
+use crate::state::AppState;
+
+/// Upper bound on neighbours consulted per meaning.
+const MAX_NEIGHBOURS: i64 = 100;
+/// Upper bound on draft meanings, each embedded on its own.
+const MAX_MEANINGS: usize = 10;
+
+#[utoipa::path(
+    post,
+    path = "/tags/suggest",
+    tag = "tags",
+    request_body = TagSuggestRequest,
+    responses(
+        (status = 200, description = "Suggested tags, best first", body = Vec<TagSuggestion>),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 402, description = "Embedding budget used up", body = ErrorBody),
+        (status = 404, description = "Phrase not found", body = ErrorBody),
+        (status = 429, description = "Rate limited; see Retry-After", body = ErrorBody)
+    )
+)]
+pub async fn suggest_tags(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<TagSuggestRequest>,
+) -> Result<Json<Vec<TagSuggestion>>, AppError> {
+    if !(1..=MAX_NEIGHBOURS).contains(&req.k) {
+        return Err(AppError::BadRequest(
+            format!("k must be between 1 and {MAX_NEIGHBOURS}").into(),
+        ));
+    }
+
+    let (embeddings, existing_tags) = match req.phrase_id {
+        Some(id) => {
+            let phrase = db::get_phrase(&state.pool, id).await?;
+            let embeddings = db::get_meaning_embeddings(&state.pool, id).await?;
+            (embeddings, phrase.tags)
+        }
+        None => {
+            if req.meanings.is_empty() || req.meanings.iter().any(|m| m.trim().is_empty()) {
+                return Err(AppError::BadRequest(
+                    "Either phrase_id or at least one non-empty meaning is required".into(),
+                ));
+            }
+            if req.meanings.len() > MAX_MEANINGS {
+                return Err(AppError::BadRequest(
+                    format!("At most {MAX_MEANINGS} meanings are allowed").into(),
+                ));
+            }
+            usage::check_budget(&state.pool, &state.limits).await?;
+            let mut embeddings = Vec::with_capacity(req.meanings.len());
+            for meaning in &req.meanings {
+                embeddings.push(state.embedding.embed(meaning).await?.vector);
+            }
+            (embeddings, Vec::new())
+        }
+    };
+
+    // A phrase near several meanings counts once, with its best similarity
+    let mut neighbours: HashMap<Uuid, TagNeighbour> = HashMap::new();
+    for embedding in &embeddings {
+        for neighbour in
+            db::nearest_tagged_phrases(&state.pool, embedding, req.k, req.phrase_id).await?
+        {
+            match neighbours.get_mut(&neighbour.id) {
+                Some(best) if neighbour.similarity > best.similarity => *best = neighbour,
+                Some(_) => {}
+                None => {
+                    neighbours.insert(neighbour.id, neighbour);
+                }
+            }
+        }
+    }
+
+    let neighbours: Vec<TagNeighbour> = neighbours.into_values().collect();
+    Ok(Json(tags::rank_tags(
+        &neighbours,
+        &existing_tags,
+        req.limit,
+    )))
+}
@@ -0,0 +1,440 @@
+//! Anki `.apkg` export: a zipped legacy (schema 11) collection holding one basic note per
+//! phrase, with the phrase on the front and meanings, source and memo on the back.
+
+use std::collections::HashSet;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+
+use chrono::Utc;
+use serde_json::{Value, json};
+use sha1::{Digest, Sha1};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::{ConnectOptions, Connection, Executor, SqliteConnection};
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+
+use crate::error::AppError;
+use crate::models::phrase::Phrase;
+
+pub const DECK_NAME: &str = "eemee";
+const MODEL_NAME: &str = "eemee Basic";
+/// Fixed ids, so every export maps onto the same note type and deck when re-imported.
+const MODEL_ID: i64 = 1_739_145_600_000;
+const DECK_ID: i64 = 1_739_145_600_001;
+const FIELDS: [&str; 4] = ["Phrase", "Meanings", "Source", "Memo"];
+
+const FRONT_TEMPLATE: &str = "<div class=phrase>{{Phrase}}</div>";
+const BACK_TEMPLATE: &str = "{{FrontSide}}<hr id=answer>{{Meanings}}\
+{{#Source}}<div class=source>{{Source}}</div>{{/Source}}\
+{{#Memo}}<div class=memo>{{Memo}}</div>{{/Memo}}";
+const CSS: &str = ".card { font-family: sans-serif; font-size: 20px; text-align: center; }
+.phrase { font-size: 28px; }
+.source, .memo { margin-top: 1em; font-size: 16px; color: #666; }
+ol { display: inline-block; text-align: left; }";
+
+const SCHEMA: &str = "
+CREATE TABLE col (
+    id integer PRIMARY KEY, crt integer NOT NULL, mod integer NOT NULL,
+    scm integer NOT NULL, ver integer NOT NULL, dty integer NOT NULL,
+    usn integer NOT NULL, ls integer NOT NULL, conf text NOT NULL,
+    models text NOT NULL, decks text NOT NULL, dconf text NOT NULL, tags text NOT NULL
+);
+CREATE TABLE notes (
+    id integer PRIMARY KEY, guid text NOT NULL, mid integer NOT NULL,
+    mod integer NOT NULL, usn integer NOT NULL, tags text NOT NULL,
+    flds text NOT NULL, sfld integer NOT NULL, csum integer NOT NULL,
+    flags integer NOT NULL, data text NOT NULL
+);
+CREATE TABLE cards (
+    id integer PRIMARY KEY, nid integer NOT NULL, did integer NOT NULL,
+    ord integer NOT NULL, mod integer NOT NULL, usn integer NOT NULL,
+    type integer NOT NULL, queue integer NOT NULL, due integer NOT NULL,
+    ivl integer NOT NULL, factor integer NOT NULL, reps integer NOT NULL,
+    lapses integer NOT NULL, left integer NOT NULL, odue integer NOT NULL,
+    odid integer NOT NULL, flags integer NOT NULL, data text NOT NULL
+);
+CREATE TABLE revlog (
+    id integer PRIMARY KEY, cid integer NOT NULL, usn integer NOT NULL,
+    ease integer NOT NULL, ivl integer NOT NULL, lastIvl integer NOT NULL,
+    factor integer NOT NULL, time integer NOT NULL, type integer NOT NULL
+);
+CREATE TABLE graves (usn integer NOT NULL, oid integer NOT NULL, type integer NOT NULL);
+CREATE INDEX ix_notes_usn ON notes (usn);
+CREATE INDEX ix_cards_usn ON cards (usn);
+CREATE INDEX ix_revlog_usn ON revlog (usn);
+CREATE INDEX ix_cards_nid ON cards (nid);
+CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+CREATE INDEX ix_revlog_cid ON revlog (cid);
+CREATE INDEX ix_notes_csum ON notes (csum);
+";
+
+/// Anki matches imported notes to existing ones by GUID, so deriving it from the phrase id
+/// makes a re-import update cards instead of duplicating them.
+pub fn note_guid(phrase_id: Uuid) -> String {
+    format!("eemee-{}", phrase_id.simple())
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' => out.push_str("<br>"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Field values in [`FIELDS`] order, as HTML.
+pub fn note_fields(phrase: &Phrase) -> [String; 4] {
+    let meanings = match phrase.meanings.as_slice() {
+        [meaning] => escape_html(meaning),
+        meanings => {
+            let items: String = meanings
+                .iter()
+                .map(|m| format!("<li>{}</li>", escape_html(m)))
+                .collect();
+            format!("<ol>{items}</ol>")
+        }
+    };
+    [
+        escape_html(&phrase.phrase),
+        meanings,
+        phrase
+            .source
+            .as_deref()
+            .map(escape_html)
+            .unwrap_or_default(),
+        phrase.memo.as_deref().map(escape_html).unwrap_or_default(),
+    ]
+}
+
+/// Anki tags are space-separated, so spaces inside a tag become underscores.
+pub fn note_tags(tags: &[String]) -> String {
+    if tags.is_empty() {
+        return String::new();
+    }
+    let tags: Vec<String> = tags.iter().map(|t| t.trim().replace(' ', "_")).collect();
+    format!(" {} ", tags.join(" "))
+}
+
+/// Anki's duplicate check: the first 8 hex digits of the SHA-1 of the sort field.
+fn field_checksum(text: &str) -> i64 {
+    let digest = Sha1::digest(text.as_bytes());
+    i64::from(u32::from_be_bytes([
+        digest[0], digest[1], digest[2], digest[3],
+    ]))
+}
+
+fn collection_json(now_ms: i64) -> (Value, Value, Value, Value) {
+    let now = now_ms / 1000;
+    let conf = json!({
+        "nextPos": 1, "estTimes": true, "activeDecks": [DECK_ID], "sortType": "noteFld",
+        "timeLim": 0, "sortBackwards": false, "addToCur": true, "curDeck": DECK_ID,
+        "newBury": true, "newSpread": 0, "dueCounts": true, "curModel": MODEL_ID.to_string(),
+        "collapseTime": 1200,
+    });
+    let fields: Vec<Value> = FIELDS
+        .iter()
+        .enumerate()
+        .map(|(ord, name)| {
+            json!({
+                "name": name, "ord": ord, "sticky": false, "rtl": false,
+                "font": "Arial", "size": 20, "media": [],
+            })
+        })
+        .collect();
+    let models = json!({
+        MODEL_ID.to_string(): {
+            "id": MODEL_ID, "name": MODEL_NAME, "type": 0, "mod": now, "usn": -1,
+            "sortf": 0, "did": DECK_ID, "flds": fields, "css": CSS,
+            "tmpls": [{
+                "name": "Card 1", "ord": 0, "qfmt": FRONT_TEMPLATE, "afmt": BACK_TEMPLATE,
+                "did": null, "bqfmt": "", "bafmt": "",
+            }],
+            "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+            "latexPost": "\\end{document}", "latexsvg": false,
+            "req": [[0, "any", [0]]], "tags": [], "vers": [],
+        }
+    });
+    let deck = |id: i64, name: &str| {
+        json!({
+            "id": id, "name": name, "desc": "", "mod": now, "usn": -1, "collapsed": false,
+            "browserCollapsed": false, "newToday": [0, 0], "revToday": [0, 0],
+            "lrnToday": [0, 0], "timeToday": [0, 0], "dyn": 0, "conf": 1,
+            "extendNew": 10, "extendRev": 50,
+        })
+    };
+    let decks = json!({
+        "1": deck(1, "Default"),
+        DECK_ID.to_string(): deck(DECK_ID, DECK_NAME),
+    });
+    let dconf = json!({
+        "1": {
+            "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60, "autoplay": true,
+            "timer": 0, "replayq": true, "dyn": false,
+            "new": {
+                "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500,
+                "order": 1, "perDay": 20, "bury": true, "separate": true,
+            },
+            "rev": {
+                "perDay": 200, "ease4": 1.3, "fuzz": 0.05, "maxIvl": 36500,
+                "ivlFct": 1, "minSpace": 1, "bury": true,
+            },
+            "lapse": {
+                "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0,
+            },
+        }
+    });
+    (conf, models, decks, dconf)
+}
+
+/// Deletes the scratch SQLite file however the export ends.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Writes notes into a scratch SQLite collection, then zips it into an `.apkg`.
+pub struct ApkgWriter {
+    conn: SqliteConnection,
+    file: TempFile,
+    used_ids: HashSet<i64>,
+    now_ms: i64,
+}
+
+impl ApkgWriter {
+    pub async fn create() -> Result<Self, AppError> {
+        let file = TempFile(std::env::temp_dir().join(format!("eemee-{}.anki2", Uuid::new_v4())));
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&file.0)
+            .create_if_missing(true)
+            // A single file, nothing left in a WAL when it is zipped
+            .journal_mode(SqliteJournalMode::Delete)
+            .connect()
+            .await?;
+
+        conn.execute(SCHEMA).await?;
+
+        let now_ms = Utc::now().timestamp_millis();
+        let (conf, models, decks, dconf) = collection_json(now_ms);
+        sqlx::query("INSERT INTO col VALUES (1, ?, ?, ?, 11, 0, 0, 0, ?, ?, ?, ?, '{}')")
+            .bind(now_ms / 1000)
+            .bind(now_ms)
+            .bind(now_ms)
+            .bind(conf.to_string())
+            .bind(models.to_string())
+            .bind(decks.to_string())
+            .bind(dconf.to_string())
+            .execute(&mut conn)
+            .await?;
+        sqlx::query("BEGIN").execute(&mut conn).await?;
+
+        Ok(ApkgWriter {
+            conn,
+            file,
+            used_ids: HashSet::new(),
+            now_ms,
+        })
+    }
+
+    /// Note ids are creation times in milliseconds and must be unique.
+    fn unique_id(&mut self, mut id: i64) -> i64 {
+        while !self.used_ids.insert(id) {
+            id += 1;
+        }
+        id
+    }
+
+    pub async fn add(&mut self, phrase: &Phrase) -> Result<(), AppError> {
+        let id = self.unique_id(phrase.created_at.timestamp_millis());
+        let fields = note_fields(phrase);
+        let modified = phrase.updated_at.timestamp();
+
+        sqlx::query("INSERT INTO notes VALUES (?, ?, ?, ?, -1, ?, ?, ?, ?, 0, '')")
+            .bind(id)
+            .bind(note_guid(phrase.id))
+            .bind(MODEL_ID)
+            .bind(modified)
+            .bind(note_tags(&phrase.tags))
+            .bind(fields.join("\x1f"))
+            .bind(&phrase.phrase)
+            .bind(field_checksum(&phrase.phrase))
+            .execute(&mut self.conn)
+            .await?;
+        // One new card per note, due in export order
+        let due = self.used_ids.len() as i64;
+        sqlx::query(
+            "INSERT INTO cards VALUES (?, ?, ?, 0, ?, -1, 0, 0, ?, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+        )
+        .bind(id)
+        .bind(id)
+        .bind(DECK_ID)
+        .bind(modified)
+        .bind(due)
+        .execute(&mut self.conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn finish(self) -> Result<Vec<u8>, AppError> {
+        let ApkgWriter {
+            mut conn,
+            file,
+            now_ms,
+            ..
+        } = self;
+        sqlx::query("UPDATE col SET mod = ?")
+            .bind(now_ms)
+            .execute(&mut conn)
+            .await?;
+        sqlx::query("COMMIT").execute(&mut conn).await?;
+        conn.close().await?;
+
+        let collection = tokio::fs::read(&file.0)
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        tokio::task::spawn_blocking(move || zip_package(&collection))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?
+    }
+}
+
+fn zip_package(collection: &[u8]) -> Result<Vec<u8>, AppError> {
+    let to_internal = |e: zip::result::ZipError| AppError::Internal(e.to_string());
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("collection.anki2", options)
+        .map_err(to_internal)?;
+    zip.write_all(collection)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    // No media files
+    zip.start_file("media", options).map_err(to_internal)?;
+    zip.write_all(b"{}")
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(zip.finish().map_err(to_internal)?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::phrase::PhraseStatus;
+    use std::io::Read;
+
+    fn phrase(text: &str, meanings: &[&str]) -> Phrase {
+        Phrase {
+            id: Uuid::new_v4(),
+            phrase: text.to_string(),
+            meanings: meanings.iter().map(|m| m.to_string()).collect(),
+            status: PhraseStatus::Ready,
+            source: Some("Novel <1>".to_string()),
+            tags: vec!["set phrase".to_string(), "idiom".to_string()],
+            memo: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn guid_is_stable_per_phrase() {
+        let id = Uuid::new_v4();
+        assert_eq!(note_guid(id), note_guid(id));
+        assert_ne!(note_guid(id), note_guid(Uuid::new_v4()));
+    }
+
+    #[test]
+    fn fields_are_escaped_html() {
+        let p = phrase("a & b", &["x < y"]);
+        let fields = note_fields(&p);
+        assert_eq!(fields[0], "a &amp; b");
+        assert_eq!(fields[1], "x &lt; y");
+        assert_eq!(fields[2], "Novel &lt;1&gt;");
+        assert_eq!(fields[3], "");
+
+        let p = phrase("p", &["one", "two"]);
+        assert_eq!(note_fields(&p)[1], "<ol><li>one</li><li>two</li></ol>");
+    }
+
+    #[test]
+    fn tags_are_space_separated() {
+        assert_eq!(note_tags(&[]), "");
+        let tags = vec!["set phrase".to_string(), "idiom".to_string()];
+        assert_eq!(note_tags(&tags), " set_phrase idiom ");
+    }
+
+    #[test]
+    fn checksum_matches_anki() {
+        // sha1("hello") = aaf4c61d...
+        assert_eq!(field_checksum("hello"), 0xaaf4c61d);
+    }
+
+    #[tokio::test]
+    async fn writes_a_readable_package() {
+        let phrases = [phrase("first", &["1"]), phrase("second", &["2", "3"])];
+        let mut writer = ApkgWriter::create().await.unwrap();
+        for p in &phrases {
+            writer.add(p).await.unwrap();
+        }
+        let package = writer.finish().await.unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(package)).unwrap();
+        let mut media = String::new();
+        archive
+            .by_name("media")
+            .unwrap()
+            .read_to_string(&mut media)
+            .unwrap();
+        assert_eq!(media, "{}");
+        let mut collection = Vec::new();
+        archive
+            .by_name("collection.anki2")
+            .unwrap()
+            .read_to_end(&mut collection)
+            .unwrap();
+
+        let file = TempFile(std::env::temp_dir().join(format!("eemee-test-{}", Uuid::new_v4())));
+        std::fs::write(&file.0, collection).unwrap();
+        let mut conn = SqliteConnectOptions::new()
+            .filename(&file.0)
+            .connect()
+            .await
+            .unwrap();
+
+        let notes: Vec<(i64, String, String, String, String)> =
+            sqlx::query_as("SELECT id, guid, flds, tags, sfld FROM notes ORDER BY id")
+                .fetch_all(&mut conn)
+                .await
+                .unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_ne!(notes[0].0, notes[1].0);
+        assert_eq!(notes[0].1, note_guid(phrases[0].id));
+        assert_eq!(
+            notes[1].2,
+            "second\x1f<ol><li>2</li><li>3</li></ol>\x1fNovel &lt;1&gt;\x1f"
+        );
+        assert_eq!(notes[1].3, " set_phrase idiom ");
+        assert_eq!(notes[1].4, "second");
+
+        let cards: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM cards WHERE did = ?")
+            .bind(DECK_ID)
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(cards, 2);
+
+        let models: String = sqlx::query_scalar("SELECT models FROM col")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        let models: Value = serde_json::from_str(&models).unwrap();
+        assert_eq!(models[MODEL_ID.to_string()]["flds"][1]["name"], "Meanings");
+        conn.close().await.unwrap();
+    }
+}
@@ -0,0 +1,709 @@
+//! Full-fidelity backups: the whole library with its embeddings, keyed by the original ids.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use futures::{Sink, SinkExt, StreamExt};
+use pgvector::Vector;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::backup::{
+    BACKUP_VERSION, Backup, BackupCollection, BackupCollectionPhrase, BackupLink, BackupMeaning,
+    BackupPhrase, BackupSavedSearch, RestoreMode, RestoreSummary,
+};
+use crate::models::link::LinkType;
+use crate::services::embedding::EMBEDDING_DIMENSIONS;
+
+type LinkRow = (Uuid, Uuid, Uuid, LinkType, Option<String>, DateTime<Utc>);
+
+/// Selects [`PhraseMeaningRow`]s, for the caller to filter and order.
+const PHRASE_MEANINGS: &str =
+    "SELECT p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at,
+            pm.id AS meaning_id, pm.meaning, pm.meaning_embedding, pm.embedding_model,
+            pm.created_at AS meaning_created_at
+     FROM phrases p
+     LEFT JOIN phrase_meanings pm ON pm.phrase_id = p.id";
+
+/// A phrase with one of its meanings, or none; the rows of a phrase are adjacent.
+#[derive(FromRow)]
+struct PhraseMeaningRow {
+    #[sqlx(flatten)]
+    phrase: BackupPhrase,
+    meaning_id: Option<Uuid>,
+    meaning: Option<String>,
+    meaning_embedding: Option<Vector>,
+    embedding_model: Option<String>,
+    meaning_created_at: Option<DateTime<Utc>>,
+}
+
+impl PhraseMeaningRow {
+    fn into_parts(self) -> (BackupPhrase, Option<BackupMeaning>) {
+        let meaning = match (self.meaning_id, self.meaning, self.meaning_created_at) {
+            (Some(id), Some(meaning), Some(created_at)) => Some(BackupMeaning {
+                id,
+                meaning,
+                embedding: self.meaning_embedding.map(|e| e.to_vec()),
+                embedding_model: self.embedding_model,
+                created_at,
+            }),
+            _ => None,
+        };
+        (self.phrase, meaning)
+    }
+}
+
+/// Encodes a [`Backup`] document piece by piece: the header, then each phrase, then the
+/// rest, which is small next to the phrases and their embeddings.
+pub struct BackupEncoder {
+    phrases: usize,
+}
+
+impl BackupEncoder {
+    pub fn header(created_at: DateTime<Utc>) -> Result<(Self, Vec<u8>), AppError> {
+        let header = format!(
+            "{{\"version\":{BACKUP_VERSION},\"created_at\":{},\"phrases\":[",
+            to_json(&created_at)?
+        );
+        Ok((Self { phrases: 0 }, header.into_bytes()))
+    }
+
+    pub fn phrase(&mut self, phrase: &BackupPhrase) -> Result<Vec<u8>, AppError> {
+        let mut bytes = if self.phrases == 0 {
+            Vec::new()
+        } else {
+            vec![b',']
+        };
+        bytes.extend(to_json(phrase)?.into_bytes());
+        self.phrases += 1;
+        Ok(bytes)
+    }
+
+    /// Closes the document; returns it with how many phrases were written.
+    pub fn footer(
+        self,
+        collections: &[BackupCollection],
+        links: &[BackupLink],
+        saved_searches: &[BackupSavedSearch],
+    ) -> Result<(Vec<u8>, usize), AppError> {
+        let footer = format!(
+            "],\"collections\":{},\"links\":{},\"saved_searches\":{}}}",
+            to_json(collections)?,
+            to_json(links)?,
+            to_json(saved_searches)?
+        );
+        Ok((footer.into_bytes(), self.phrases))
+    }
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string(value)
+        .map_err(|e| AppError::Internal(format!("Cannot encode backup: {e}")))
+}
+
+/// Writes the whole library to `out` as a [`Backup`] document, reading it from one
+/// snapshot so concurrent edits cannot tear the backup. Phrases come from a cursor, so
+/// the library is never held in memory. Returns how many phrases were written.
+pub async fn write_backup<S>(pool: &PgPool, out: &mut S) -> Result<usize, AppError>
+where
+    S: Sink<Vec<u8>> + Unpin,
+    S::Error: std::fmt::Display,
+{
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+
+    let (mut encoder, header) = BackupEncoder::header(Utc::now())?;
+    send(out, header).await?;
+
+    let query = format!("{PHRASE_MEANINGS} ORDER BY p.created_at, p.id, pm.created_at, pm.id");
+    let mut rows = sqlx::query_as::<_, PhraseMeaningRow>(&query).fetch(&mut *tx);
+    let mut current: Option<BackupPhrase> = None;
+    while let Some(row) = rows.next().await {
+        let (phrase, meaning) = row?.into_parts();
+        // The first row of the next phrase completes the one before
+        if current.as_ref().is_none_or(|p| p.id != phrase.id)
+            && let Some(done) = current.replace(phrase)
+        {
+            send(out, encoder.phrase(&done)?).await?;
+        }
+        if let Some(meaning) = meaning {
+            current.as_mut().expect("set above").meanings.push(meaning);
+        }
+    }
+    drop(rows);
+    if let Some(done) = current {
+        send(out, encoder.phrase(&done)?).await?;
+    }
+
+    let mut collections: Vec<BackupCollection> = sqlx::query_as(
+        "SELECT id, name, description, created_at, updated_at
+         FROM collections ORDER BY created_at, id",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let member_rows: Vec<(Uuid, Uuid, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT collection_id, phrase_id, added_at
+         FROM collection_phrases ORDER BY collection_id, position",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut members: HashMap<Uuid, Vec<BackupCollectionPhrase>> = HashMap::new();
+    for (collection_id, phrase_id, added_at) in member_rows {
+        members
+            .entry(collection_id)
+            .or_default()
+            .push(BackupCollectionPhrase {
+                phrase_id,
+                added_at,
+            });
+    }
+    for collection in &mut collections {
+        collection.phrases = members.remove(&collection.id).unwrap_or_default();
+    }
+
+    let links: Vec<BackupLink> = sqlx::query_as::<_, LinkRow>(
+        "SELECT id, from_phrase_id, to_phrase_id, link_type, note, created_at
+         FROM phrase_links ORDER BY created_at, id",
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(
+        |(id, from_phrase_id, to_phrase_id, link_type, note, created_at)| BackupLink {
+            id,
+            from_phrase_id,
+            to_phrase_id,
+            link_type,
+            note,
+            created_at,
+        },
+    )
+    .collect();
+
+    let saved_searches: Vec<BackupSavedSearch> = sqlx::query_as(
+        "SELECT id, name, mode, query, filters, created_at, updated_at
+         FROM saved_searches ORDER BY created_at, id",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let (footer, phrases) = encoder.footer(&collections, &links, &saved_searches)?;
+    send(out, footer).await?;
+    Ok(phrases)
+}
+
+/// One phrase as a backup holds it, locked until `tx` ends.
+pub(crate) async fn read_phrase(
+    tx: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+) -> Result<Option<BackupPhrase>, AppError> {
+    let query =
+        format!("{PHRASE_MEANINGS} WHERE p.id = $1 ORDER BY pm.created_at, pm.id FOR UPDATE OF p");
+    let rows = sqlx::query_as::<_, PhraseMeaningRow>(&query)
+        .bind(id)
+        .fetch_all(&mut **tx)
+        .await?;
+    let mut phrase = None;
+    for row in rows {
+        let (row_phrase, meaning) = row.into_parts();
+        let phrase = phrase.get_or_insert(row_phrase);
+        phrase.meanings.extend(meaning);
+    }
+    Ok(phrase)
+}
+
+async fn send<S>(out: &mut S, bytes: Vec<u8>) -> Result<(), AppError>
+where
+    S: Sink<Vec<u8>> + Unpin,
+    S::Error: std::fmt::Display,
+{
+    out.send(bytes)
+        .await
+        .map_err(|e| AppError::Internal(format!("Cannot write backup: {e}")))
+}
+
+#[derive(Deserialize)]
+struct BackupVersion {
+    version: u32,
+}
+
+/// Parses an uploaded archive. An archive from another version usually fails to parse as
+/// this one, so report the version mismatch rather than the first unexpected field.
+pub fn parse(bytes: &[u8]) -> Result<Backup, AppError> {
+    let unsupported = |version| {
+        AppError::BadRequest(
+            format!("Unsupported backup version {version}; expected {BACKUP_VERSION}").into(),
+        )
+    };
+    match serde_json::from_slice::<Backup>(bytes) {
+        Ok(backup) if backup.version == BACKUP_VERSION => Ok(backup),
+        Ok(backup) => Err(unsupported(backup.version)),
+        Err(e) => match serde_json::from_slice::<BackupVersion>(bytes) {
+            Ok(probe) if probe.version != BACKUP_VERSION => Err(unsupported(probe.version)),
+            _ => Err(AppError::BadRequest(format!("Invalid backup: {e}").into())),
+        },
+    }
+}
+
+/// Checks everything the database would otherwise reject halfway through a restore.
+/// Embeddings must come from `model`, since vectors from different models are not
+/// comparable.
+pub fn validate(backup: &Backup, model: &str) -> Result<(), AppError> {
+    let bad = |message: String| Err(AppError::BadRequest(message.into()));
+
+    let mut phrase_ids = HashSet::new();
+    let mut meaning_ids = HashSet::new();
+    for phrase in &backup.phrases {
+        if !phrase_ids.insert(phrase.id) {
+            return bad(format!("Duplicate phrase id {}", phrase.id));
+        }
+        for meaning in &phrase.meanings {
+            if !meaning_ids.insert(meaning.id) {
+                return bad(format!("Duplicate meaning id {}", meaning.id));
+            }
+            let (embedding, embedding_model) = match (&meaning.embedding, &meaning.embedding_model)
+            {
+                (Some(embedding), Some(embedding_model)) => (embedding, embedding_model),
+                (None, None) => continue,
+                _ => {
+                    return bad(format!(
+                        "Meaning {} needs both an embedding and its model, or neither",
+                        meaning.id
+                    ));
+                }
+            };
+            if embedding.len() != EMBEDDING_DIMENSIONS {
+                return bad(format!(
+                    "Meaning {} has {} dimensions; expected {EMBEDDING_DIMENSIONS}",
+                    meaning.id,
+                    embedding.len()
+                ));
+            }
+            if embedding_model != model {
+                return bad(format!(
+                    "Meaning {} was embedded with {embedding_model}, but this server uses {model}",
+                    meaning.id
+                ));
+            }
+        }
+    }
+
+    let mut collection_ids = HashSet::new();
+    for collection in &backup.collections {
+        if !collection_ids.insert(collection.id) {
+            return bad(format!("Duplicate collection id {}", collection.id));
+        }
+        let mut members = HashSet::new();
+        for member in &collection.phrases {
+            if !phrase_ids.contains(&member.phrase_id) {
+                return bad(format!(
+                    "Collection {} refers to unknown phrase {}",
+                    collection.id, member.phrase_id
+                ));
+            }
+            if !members.insert(member.phrase_id) {
+                return bad(format!(
+                    "Collection {} lists phrase {} twice",
+                    collection.id, member.phrase_id
+                ));
+            }
+        }
+    }
+
+    let mut link_ids = HashSet::new();
+    for link in &backup.links {
+        if !link_ids.insert(link.id) {
+            return bad(format!("Duplicate link id {}", link.id));
+        }
+        for end in [link.from_phrase_id, link.to_phrase_id] {
+            if !phrase_ids.contains(&end) {
+                return bad(format!("Link {} refers to unknown phrase {end}", link.id));
+            }
+        }
+        if link.from_phrase_id == link.to_phrase_id {
+            return bad(format!("Link {} links a phrase to itself", link.id));
+        }
+    }
+
+    let mut saved_search_ids = HashSet::new();
+    for search in &backup.saved_searches {
+        if !saved_search_ids.insert(search.id) {
+            return bad(format!("Duplicate saved search id {}", search.id));
+        }
+    }
+    Ok(())
+}
+
+/// Restores a validated backup in one transaction; on any error nothing changes.
+pub async fn restore(
+    pool: &PgPool,
+    backup: &Backup,
+    mode: RestoreMode,
+) -> Result<RestoreSummary, AppError> {
+    let mut tx = pool.begin().await?;
+
+    if mode == RestoreMode::Replace {
+        // Meanings, links and memberships go with their phrases and collections
+        for table in ["phrases", "collections", "saved_searches"] {
+            sqlx::query(&format!("DELETE FROM {table}"))
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    restore_phrases(&mut tx, &backup.phrases).await?;
+    restore_collections(&mut tx, &backup.collections).await?;
+    restore_links(&mut tx, &backup.links).await?;
+    restore_saved_searches(&mut tx, &backup.saved_searches).await?;
+
+    tx.commit().await?;
+
+    Ok(RestoreSummary {
+        mode,
+        phrases: backup.phrases.len(),
+        meanings: backup.phrases.iter().map(|p| p.meanings.len()).sum(),
+        collections: backup.collections.len(),
+        links: backup.links.len(),
+        saved_searches: backup.saved_searches.len(),
+    })
+}
+
+pub(crate) async fn restore_phrases(
+    tx: &mut Transaction<'_, Postgres>,
+    phrases: &[BackupPhrase],
+) -> Result<(), AppError> {
+    // A restored phrase gets exactly the meanings in the backup
+    let ids: Vec<Uuid> = phrases.iter().map(|p| p.id).collect();
+    sqlx::query("DELETE FROM phrase_meanings WHERE phrase_id = ANY($1)")
+        .bind(&ids)
+        .execute(&mut **tx)
+        .await?;
+
+    for phrase in phrases {
+        sqlx::query(
+            "INSERT INTO phrases (id, phrase, source, tags, memo, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (id) DO UPDATE
+             SET phrase = EXCLUDED.phrase, source = EXCLUDED.source, tags = EXCLUDED.tags,
+                 memo = EXCLUDED.memo, created_at = EXCLUDED.created_at,
+                 updated_at = EXCLUDED.updated_at",
+        )
+        .bind(phrase.id)
+        .bind(&phrase.phrase)
+        .bind(&phrase.source)
+        .bind(&phrase.tags)
+        .bind(&phrase.memo)
+        .bind(phrase.created_at)
+        .bind(phrase.updated_at)
+        .execute(&mut **tx)
+        .await?;
+
+        for meaning in &phrase.meanings {
+            let embedding = meaning.embedding.clone().map(Vector::from);
+            let pending = embedding.is_none();
+            sqlx::query(
+                "INSERT INTO phrase_meanings
+                     (id, phrase_id, meaning, meaning_embedding, embedding_model,
+                      embedding_status, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 ON CONFLICT (id) DO UPDATE
+                 SET phrase_id = EXCLUDED.phrase_id, meaning = EXCLUDED.meaning,
+                     meaning_embedding = EXCLUDED.meaning_embedding,
+                     embedding_model = EXCLUDED.embedding_model,
+                     embedding_status = EXCLUDED.embedding_status,
+                     created_at = EXCLUDED.created_at",
+            )
+            .bind(meaning.id)
+            .bind(phrase.id)
+            .bind(&meaning.meaning)
+            .bind(embedding)
+            .bind(&meaning.embedding_model)
+            .bind(if pending { "pending" } else { "ready" })
+            .bind(meaning.created_at)
+            .execute(&mut **tx)
+            .await?;
+
+            if pending {
+                sqlx::query(
+                    "INSERT INTO embedding_jobs (meaning_id) VALUES ($1)
+                     ON CONFLICT (meaning_id) DO UPDATE
+                     SET status = 'queued', attempts = 0, last_error = NULL,
+                         run_at = now(), updated_at = now()",
+                )
+                .bind(meaning.id)
+                .execute(&mut **tx)
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn restore_collections(
+    tx: &mut Transaction<'_, Postgres>,
+    collections: &[BackupCollection],
+) -> Result<(), AppError> {
+    // A restored collection gets exactly the members in the backup
+    let ids: Vec<Uuid> = collections.iter().map(|c| c.id).collect();
+    sqlx::query("DELETE FROM collection_phrases WHERE collection_id = ANY($1)")
+        .bind(&ids)
+        .execute(&mut **tx)
+        .await?;
+
+    for collection in collections {
+        sqlx::query(
+            "INSERT INTO collections (id, name, description, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (id) DO UPDATE
+             SET name = EXCLUDED.name, description = EXCLUDED.description,
+                 created_at = EXCLUDED.created_at, updated_at = EXCLUDED.updated_at",
+        )
+        .bind(collection.id)
+        .bind(&collection.name)
+        .bind(&collection.description)
+        .bind(collection.created_at)
+        .bind(collection.updated_at)
+        .execute(&mut **tx)
+        .await?;
+
+        for (position, member) in collection.phrases.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO collection_phrases (collection_id, phrase_id, position, added_at)
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind(collection.id)
+            .bind(member.phrase_id)
+            .bind(position as i32)
+            .bind(member.added_at)
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn restore_links(
+    tx: &mut Transaction<'_, Postgres>,
+    links: &[BackupLink],
+) -> Result<(), AppError> {
+    let ids: Vec<Uuid> = links.iter().map(|l| l.id).collect();
+    sqlx::query("DELETE FROM phrase_links WHERE id = ANY($1)")
+        .bind(&ids)
+        .execute(&mut **tx)
+        .await?;
+
+    for link in links {
+        // An identical link already in the library is kept as is
+        sqlx::query(
+            "INSERT INTO phrase_links
+                 (id, from_phrase_id, to_phrase_id, link_type, note, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (from_phrase_id, to_phrase_id, link_type) DO NOTHING",
+        )
+        .bind(link.id)
+        .bind(link.from_phrase_id)
+        .bind(link.to_phrase_id)
+        .bind(link.link_type)
+        .bind(&link.note)
+        .bind(link.created_at)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn restore_saved_searches(
+    tx: &mut Transaction<'_, Postgres>,
+    searches: &[BackupSavedSearch],
+) -> Result<(), AppError> {
+    for search in searches {
+        sqlx::query(
+            "INSERT INTO saved_searches (id, name, mode, query, filters, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (id) DO UPDATE
+             SET name = EXCLUDED.name, mode = EXCLUDED.mode, query = EXCLUDED.query,
+                 filters = EXCLUDED.filters, query_embedding = NULL,
+                 created_at = EXCLUDED.created_at, updated_at = EXCLUDED.updated_at",
+        )
+        .bind(search.id)
+        .bind(&search.name)
+        .bind(search.mode)
+        .bind(&search.query)
+        .bind(Json(&search.filters))
+        .bind(search.created_at)
+        .bind(search.updated_at)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "text-embedding-3-large";
+
+    fn phrase(meanings: usize) -> BackupPhrase {
+        BackupPhrase {
+            id: Uuid::new_v4(),
+            phrase: "phrase".to_string(),
+            source: None,
+            tags: vec![],
+            memo: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            meanings: (0..meanings)
+                .map(|_| BackupMeaning {
+                    id: Uuid::new_v4(),
+                    meaning: "meaning".to_string(),
+                    embedding: Some(vec![0.0; EMBEDDING_DIMENSIONS]),
+                    embedding_model: Some(MODEL.to_string()),
+                    created_at: Utc::now(),
+                })
+                .collect(),
+        }
+    }
+
+    fn backup(phrases: Vec<BackupPhrase>) -> Backup {
+        Backup {
+            version: BACKUP_VERSION,
+            created_at: Utc::now(),
+            phrases,
+            collections: vec![],
+            links: vec![],
+            saved_searches: vec![],
+        }
+    }
+
+    fn link(from: Uuid, to: Uuid) -> BackupLink {
+        BackupLink {
+            id: Uuid::new_v4(),
+            from_phrase_id: from,
+            to_phrase_id: to,
+            link_type: LinkType::Synonym,
+            note: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn rejected(backup: &Backup, model: &str) -> String {
+        match validate(backup, model) {
+            Err(AppError::BadRequest(input)) => input.message,
+            other => panic!("expected a bad request, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut original = backup(vec![phrase(2), phrase(1)]);
+        original.links = vec![link(original.phrases[0].id, original.phrases[1].id)];
+        let bytes = serde_json::to_vec(&original).unwrap();
+        let parsed = parse(&bytes).unwrap();
+        assert_eq!(parsed.phrases.len(), 2);
+        assert_eq!(parsed.phrases[0].meanings.len(), 2);
+        assert_eq!(parsed.links[0].link_type, LinkType::Synonym);
+        validate(&parsed, MODEL).unwrap();
+    }
+
+    #[test]
+    fn encoder_writes_a_parseable_backup() {
+        let phrases = [phrase(2), phrase(0), phrase(1)];
+        let links = [link(phrases[0].id, phrases[2].id)];
+        let (mut encoder, mut bytes) = BackupEncoder::header(Utc::now()).unwrap();
+        for p in &phrases {
+            bytes.extend(encoder.phrase(p).unwrap());
+        }
+        let (footer, count) = encoder.footer(&[], &links, &[]).unwrap();
+        bytes.extend(footer);
+        assert_eq!(count, 3);
+
+        let parsed = parse(&bytes).unwrap();
+        assert_eq!(parsed.phrases.len(), 3);
+        assert_eq!(parsed.phrases[0].meanings.len(), 2);
+        assert!(parsed.phrases[1].meanings.is_empty());
+        assert_eq!(parsed.links.len(), 1);
+        validate(&parsed, MODEL).unwrap();
+    }
+
+    #[test]
+    fn parse_reports_unsupported_versions() {
+        let future = br#"{"version": 99, "library": {}}"#;
+        match parse(future) {
+            Err(AppError::BadRequest(input)) => assert!(input.message.contains("version 99")),
+            other => panic!("unexpected {other:?}"),
+        }
+
+        let mut newer = backup(vec![]);
+        newer.version = BACKUP_VERSION + 1;
+        let bytes = serde_json::to_vec(&newer).unwrap();
+        assert!(matches!(parse(&bytes), Err(AppError::BadRequest(_))));
+
+        match parse(br#"{"phrases": []}"#) {
+            Err(AppError::BadRequest(input)) => {
+                assert!(input.message.starts_with("Invalid backup"))
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn validate_rejects_other_models_and_dimensions() {
+        let b = backup(vec![phrase(1)]);
+        assert!(rejected(&b, "other-model").contains("other-model"));
+
+        let mut b = backup(vec![phrase(1)]);
+        b.phrases[0].meanings[0].embedding.as_mut().unwrap().pop();
+        assert!(rejected(&b, MODEL).contains("dimensions"));
+
+        // Drafts have no meanings yet
+        assert!(validate(&backup(vec![phrase(0)]), MODEL).is_ok());
+
+        // Pending meanings have neither an embedding nor a model
+        let mut b = backup(vec![phrase(1)]);
+        b.phrases[0].meanings[0].embedding = None;
+        assert!(rejected(&b, MODEL).contains("neither"));
+        b.phrases[0].meanings[0].embedding_model = None;
+        assert!(validate(&b, MODEL).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_dangling_references() {
+        let mut b = backup(vec![phrase(1)]);
+        b.links = vec![link(b.phrases[0].id, Uuid::new_v4())];
+        assert!(rejected(&b, MODEL).contains("unknown phrase"));
+
+        let mut b = backup(vec![phrase(1)]);
+        b.links = vec![link(b.phrases[0].id, b.phrases[0].id)];
+        assert!(rejected(&b, MODEL).contains("itself"));
+
+        let mut b = backup(vec![phrase(1)]);
+        b.collections = vec![BackupCollection {
+            id: Uuid::new_v4(),
+            name: "c".to_string(),
+            description: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            phrases: vec![BackupCollectionPhrase {
+                phrase_id: Uuid::new_v4(),
+                added_at: Utc::now(),
+            }],
+        }];
+        assert!(rejected(&b, MODEL).contains("unknown phrase"));
+    }
+
+    #[test]
+    fn validate_rejects_duplicate_ids() {
+        let p = phrase(1);
+        let mut twin = phrase(1);
+        twin.id = p.id;
+        assert!(rejected(&backup(vec![p, twin]), MODEL).contains("Duplicate phrase"));
+    }
+}
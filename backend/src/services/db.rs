@@ -1,15 +1,92 @@
 use crate::error::AppError;
-use crate::models::phrase::PhraseWithMeaningsRow;
+use crate::models::collection::{Collection, CollectionPhraseRow};
+use crate::models::import::NewDraft;
+use crate::models::link::{GraphEdgeRow, GraphNode, LinkType, PhraseLinkRow};
+use crate::models::phrase::{
+    AnnParams, NearestMeaningRow, PhraseFilter, PhraseWithMeaningsRow, ScoredPhraseRow,
+};
+use crate::models::search::{SavedSearchRow, SearchHistoryRow, SearchMode};
+use crate::models::stats::{LibraryStats, ModelCount};
+use crate::models::tag::TagNeighbour;
+use crate::services::query::{Field, Query, Term};
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
 use pgvector::Vector;
-use sqlx::PgPool;
+use sqlx::migrate::Migrator;
+use sqlx::postgres::PgArguments;
+use sqlx::query::QueryAs;
+use sqlx::types::Json;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::LazyLock;
 use uuid::Uuid;
 
+/// The migrations in `migrations/`, embedded at build time.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Phrases with their meanings and status. Drafts have no meanings and get an
+/// empty array, and pending meanings have no embedding yet, so queries that rank by
+/// embedding must leave both out.
 const PHRASE_WITH_MEANINGS_QUERY: &str =
     "SELECT p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at,
-            array_agg(pm.meaning ORDER BY pm.created_at) as meanings
+            COALESCE(array_agg(pm.meaning ORDER BY pm.created_at)
+                     FILTER (WHERE pm.id IS NOT NULL), '{}') as meanings,
+            CASE WHEN COUNT(pm.id) = 0 THEN 'draft'
+                 WHEN bool_or(pm.embedding_status = 'failed') THEN 'failed'
+                 WHEN bool_or(pm.embedding_status = 'pending') THEN 'pending'
+                 ELSE 'ready' END AS status
      FROM phrases p
-     JOIN phrase_meanings pm ON pm.phrase_id = p.id";
+     LEFT JOIN phrase_meanings pm ON pm.phrase_id = p.id";
+
+/// Numbers `$n` placeholders in the order their values are bound, so that SQL assembled
+/// from several parts never needs hand-computed offsets.
+struct Placeholders(usize);
+
+impl Placeholders {
+    /// Placeholders following `$1..$bound`, which the query writes out itself.
+    fn after(bound: usize) -> Self {
+        Placeholders(bound)
+    }
+
+    fn next(&mut self) -> String {
+        self.0 += 1;
+        format!("${}", self.0)
+    }
+}
+
+/// SQL predicate restricting `p` to a [`PhraseFilter`]. Bind the values with
+/// [`bind_filter`] in the position of the placeholders taken from `params`.
+fn filter_predicate(params: &mut Placeholders) -> String {
+    let (collection, tags, source) = (params.next(), params.next(), params.next());
+    format!(
+        "({collection}::uuid IS NULL OR EXISTS (
+             SELECT 1 FROM collection_phrases cp
+             WHERE cp.collection_id = {collection} AND cp.phrase_id = p.id))
+         AND (cardinality({tags}::text[]) = 0 OR p.tags @> {tags}::text[])
+         AND ({source}::text IS NULL OR p.source ILIKE {})",
+        contains_pattern(&source)
+    )
+}
+
+/// LIKE pattern matching the text of the SQL expression `value` anywhere, with `%`, `_`
+/// and `\` in it escaped so they match literally.
+fn contains_pattern(value: &str) -> String {
+    format!(
+        "('%' || replace(replace(replace({value}, '\\', '\\\\'), '%', '\\%'), '_', '\\_') || '%')"
+    )
+}
 
+fn bind_filter<'q, O>(
+    query: QueryAs<'q, Postgres, O, PgArguments>,
+    filter: &PhraseFilter,
+) -> QueryAs<'q, Postgres, O, PgArguments> {
+    query
+        .bind(filter.collection_id)
+        .bind(filter.tags.clone())
+        .bind(filter.source.clone())
+}
+
+/// Saves a phrase with its meanings pending, queueing a job to embed each one.
+#[tracing::instrument(skip_all)]
 pub async fn create_phrase(
     pool: &PgPool,
     phrase: &str,
@@ -17,7 +94,6 @@ pub async fn create_phrase(
     source: Option<&str>,
     tags: &[String],
     memo: Option<&str>,
-    embeddings: &[Vector],
 ) -> Result<PhraseWithMeaningsRow, AppError> {
     let mut tx = pool.begin().await?;
 
@@ -33,23 +109,73 @@ pub async fn create_phrase(
     .fetch_one(&mut *tx)
     .await?;
 
-    for (meaning, embedding) in meanings.iter().zip(embeddings.iter()) {
+    insert_pending_meanings(&mut tx, row.id, meanings).await?;
+
+    tx.commit().await?;
+
+    get_phrase(pool, row.id).await
+}
+
+/// Inserts meanings without embeddings, each with a job for the embedding workers.
+async fn insert_pending_meanings(
+    tx: &mut Transaction<'_, Postgres>,
+    phrase_id: Uuid,
+    meanings: &[String],
+) -> Result<(), AppError> {
+    for meaning in meanings {
         sqlx::query(
-            "INSERT INTO phrase_meanings (phrase_id, meaning, meaning_embedding)
-             VALUES ($1, $2, $3)",
+            "WITH pending AS (
+                 INSERT INTO phrase_meanings (phrase_id, meaning, embedding_status)
+                 VALUES ($1, $2, 'pending')
+                 RETURNING id
+             )
+             INSERT INTO embedding_jobs (meaning_id) SELECT id FROM pending",
         )
-        .bind(row.id)
+        .bind(phrase_id)
         .bind(meaning)
-        .bind(embedding)
-        .execute(&mut *tx)
+        .execute(&mut **tx)
         .await?;
     }
+    Ok(())
+}
 
+/// Saves phrases without meanings in one transaction, returning their ids in order.
+#[tracing::instrument(skip_all)]
+pub async fn create_drafts(pool: &PgPool, drafts: &[NewDraft]) -> Result<Vec<Uuid>, AppError> {
+    let mut tx = pool.begin().await?;
+    let mut ids = Vec::with_capacity(drafts.len());
+    for draft in drafts {
+        let id = sqlx::query_scalar(
+            "INSERT INTO phrases (phrase, source, memo, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $4)
+             RETURNING id",
+        )
+        .bind(&draft.phrase)
+        .bind(&draft.source)
+        .bind(&draft.memo)
+        .bind(draft.created_at)
+        .fetch_one(&mut *tx)
+        .await?;
+        ids.push(id);
+    }
     tx.commit().await?;
+    Ok(ids)
+}
 
-    get_phrase(pool, row.id).await
+/// The phrases already saved from any of `sources`, as `(source, phrase)` pairs.
+#[tracing::instrument(skip_all)]
+pub async fn get_phrases_from_sources(
+    pool: &PgPool,
+    sources: &[String],
+) -> Result<Vec<(String, String)>, AppError> {
+    let rows = sqlx::query_as("SELECT source, phrase FROM phrases WHERE source = ANY($1)")
+        .bind(sources)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
 }
 
+#[tracing::instrument(skip_all, fields(%id))]
 pub async fn get_phrase(pool: &PgPool, id: Uuid) -> Result<PhraseWithMeaningsRow, AppError> {
     let query = format!(
         "{PHRASE_WITH_MEANINGS_QUERY}
@@ -65,7 +191,8 @@ pub async fn get_phrase(pool: &PgPool, id: Uuid) -> Result<PhraseWithMeaningsRow
     Ok(row)
 }
 
-#[allow(clippy::too_many_arguments)]
+/// Updates the given fields. New meanings replace the old ones and are queued for embedding.
+#[tracing::instrument(skip_all, fields(%id))]
 pub async fn update_phrase(
     pool: &PgPool,
     id: Uuid,
@@ -74,7 +201,6 @@ pub async fn update_phrase(
     tags: Option<&[String]>,
     memo: Option<&str>,
     meanings: Option<&[String]>,
-    embeddings: Option<&[Vector]>,
 ) -> Result<PhraseWithMeaningsRow, AppError> {
     let existing = get_phrase(pool, id).await?;
 
@@ -98,23 +224,12 @@ pub async fn update_phrase(
     .execute(&mut *tx)
     .await?;
 
-    if let (Some(meanings), Some(embeddings)) = (meanings, embeddings) {
+    if let Some(meanings) = meanings {
         sqlx::query("DELETE FROM phrase_meanings WHERE phrase_id = $1")
             .bind(id)
             .execute(&mut *tx)
             .await?;
-
-        for (meaning, embedding) in meanings.iter().zip(embeddings.iter()) {
-            sqlx::query(
-                "INSERT INTO phrase_meanings (phrase_id, meaning, meaning_embedding)
-                 VALUES ($1, $2, $3)",
-            )
-            .bind(id)
-            .bind(meaning)
-            .bind(embedding)
-            .execute(&mut *tx)
-            .await?;
-        }
+        insert_pending_meanings(&mut tx, id, meanings).await?;
     }
 
     tx.commit().await?;
@@ -122,62 +237,492 @@ pub async fn update_phrase(
     get_phrase(pool, id).await
 }
 
-pub async fn delete_phrase(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
-    let result = sqlx::query("DELETE FROM phrases WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await?;
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound);
-    }
-    Ok(())
-}
-
+#[tracing::instrument(skip_all)]
 pub async fn semantic_search(
     pool: &PgPool,
     query_embedding: &Vector,
     limit: i64,
+    filter: &PhraseFilter,
 ) -> Result<Vec<PhraseWithMeaningsRow>, AppError> {
     let query = format!(
         "{PHRASE_WITH_MEANINGS_QUERY}
+         WHERE {}
          GROUP BY p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at
+         HAVING COUNT(pm.meaning_embedding) > 0
          ORDER BY MIN(pm.meaning_embedding <=> $1)
-         LIMIT $2"
+         LIMIT $2",
+        filter_predicate(&mut Placeholders::after(2))
     );
 
-    let rows = sqlx::query_as::<_, PhraseWithMeaningsRow>(&query)
+    let query = sqlx::query_as::<_, PhraseWithMeaningsRow>(&query)
+        .bind(query_embedding)
+        .bind(limit);
+    let rows = bind_filter(query, filter).fetch_all(pool).await?;
+    Ok(rows)
+}
+
+/// Semantic search scoring each phrase by the weighted sum, over the query vectors, of its
+/// best cosine similarity to that vector. Phrases must be close to every positively
+/// weighted vector on its own to rank high, unlike a search with their centroid.
+#[tracing::instrument(skip_all)]
+pub async fn semantic_search_weighted(
+    pool: &PgPool,
+    vectors: &[(Vector, f64)],
+    limit: i64,
+    filter: &PhraseFilter,
+) -> Result<Vec<PhraseWithMeaningsRow>, AppError> {
+    // $1 is the limit, then the filter, then one (vector, weight) pair per query vector
+    let mut params = Placeholders::after(1);
+    let filter_sql = filter_predicate(&mut params);
+    let terms: Vec<String> = vectors
+        .iter()
+        .map(|_| {
+            let (vector, weight) = (params.next(), params.next());
+            format!("{weight}::float8 * (1 - MIN(pm.meaning_embedding <=> {vector}))")
+        })
+        .collect();
+    let query = format!(
+        "WITH scored AS (
+             SELECT pm.phrase_id, {} AS score
+             FROM phrase_meanings pm
+             WHERE pm.meaning_embedding IS NOT NULL
+             GROUP BY pm.phrase_id
+         )
+         {PHRASE_WITH_MEANINGS_QUERY}
+         JOIN scored sc ON sc.phrase_id = p.id
+         WHERE {filter_sql}
+         GROUP BY p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at, sc.score
+         ORDER BY sc.score DESC
+         LIMIT $1",
+        terms.join(" + "),
+    );
+
+    let query = sqlx::query_as::<_, PhraseWithMeaningsRow>(&query).bind(limit);
+    let mut query = bind_filter(query, filter);
+    for (vector, weight) in vectors {
+        query = query.bind(vector).bind(weight);
+    }
+    let rows = query.fetch_all(pool).await?;
+    Ok(rows)
+}
+
+/// Meanings taken from the vector index per requested phrase. Phrases with several
+/// meanings and post-index filtering both shrink the candidate set.
+const ANN_CANDIDATE_FACTOR: i64 = 4;
+
+/// Approximate variant of [`semantic_search`] over the half-precision embeddings, which
+/// can use the HNSW/IVFFlat index. Filters are applied to the index candidates, so a
+/// selective filter can return fewer than `limit` phrases.
+#[tracing::instrument(skip_all)]
+pub async fn semantic_search_approximate(
+    pool: &PgPool,
+    query_embedding: &Vector,
+    limit: i64,
+    filter: &PhraseFilter,
+    params: AnnParams,
+) -> Result<Vec<PhraseWithMeaningsRow>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // Transaction-local, so pooled connections keep the server defaults
+    if let Some(ef_search) = params.ef_search {
+        sqlx::query("SELECT set_config('hnsw.ef_search', $1, true)")
+            .bind(ef_search.to_string())
+            .execute(&mut *tx)
+            .await?;
+    }
+    if let Some(probes) = params.probes {
+        sqlx::query("SELECT set_config('ivfflat.probes', $1, true)")
+            .bind(probes.to_string())
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let query = format!(
+        "WITH nearest AS (
+             SELECT pm.phrase_id, pm.meaning_embedding_half <=> $1::halfvec(3072) AS distance
+             FROM phrase_meanings pm
+             WHERE pm.meaning_embedding_half IS NOT NULL
+             ORDER BY pm.meaning_embedding_half <=> $1::halfvec(3072)
+             LIMIT $3
+         ), ranked AS (
+             SELECT phrase_id, MIN(distance) AS distance FROM nearest GROUP BY phrase_id
+         )
+         {PHRASE_WITH_MEANINGS_QUERY}
+         JOIN ranked r ON r.phrase_id = p.id
+         WHERE {}
+         GROUP BY p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at, r.distance
+         ORDER BY r.distance
+         LIMIT $2",
+        filter_predicate(&mut Placeholders::after(3))
+    );
+
+    let query = sqlx::query_as::<_, PhraseWithMeaningsRow>(&query)
         .bind(query_embedding)
         .bind(limit)
-        .fetch_all(pool)
-        .await?;
+        .bind(limit.saturating_mul(ANN_CANDIDATE_FACTOR));
+    let rows = bind_filter(query, filter).fetch_all(&mut *tx).await?;
+
+    tx.commit().await?;
+    Ok(rows)
+}
+
+/// For each of the given phrases, the meaning embedding closest to the query and its cosine
+/// similarity. Used to rerank search candidates by the sense that matched.
+#[tracing::instrument(skip_all)]
+pub async fn nearest_meaning_vectors(
+    pool: &PgPool,
+    query_embedding: &Vector,
+    phrase_ids: &[Uuid],
+) -> Result<Vec<NearestMeaningRow>, AppError> {
+    let rows = sqlx::query_as::<_, NearestMeaningRow>(
+        "SELECT DISTINCT ON (pm.phrase_id)
+                pm.phrase_id, pm.meaning_embedding AS embedding,
+                1 - (pm.meaning_embedding <=> $1) AS similarity
+         FROM phrase_meanings pm
+         WHERE pm.phrase_id = ANY($2) AND pm.meaning_embedding IS NOT NULL
+         ORDER BY pm.phrase_id, pm.meaning_embedding <=> $1",
+    )
+    .bind(query_embedding)
+    .bind(phrase_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Phrases closest to an existing phrase, scored by the best cosine similarity between
+/// any of their meanings. Uses the stored vectors only, so no embedding call is needed.
+#[tracing::instrument(skip_all)]
+pub async fn similar_phrases(
+    pool: &PgPool,
+    phrase_id: Uuid,
+    limit: i64,
+    filter: &PhraseFilter,
+) -> Result<Vec<ScoredPhraseRow>, AppError> {
+    let query = format!(
+        "WITH scored AS (
+             SELECT pm.phrase_id, MIN(pm.meaning_embedding <=> src.meaning_embedding) AS distance
+             FROM phrase_meanings pm
+             CROSS JOIN phrase_meanings src
+             WHERE src.phrase_id = $1 AND pm.phrase_id <> $1
+               AND pm.meaning_embedding IS NOT NULL AND src.meaning_embedding IS NOT NULL
+             GROUP BY pm.phrase_id
+         )
+         SELECT p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at,
+                array_agg(pm.meaning ORDER BY pm.created_at) as meanings,
+                CASE WHEN bool_or(pm.embedding_status = 'failed') THEN 'failed'
+                     WHEN bool_or(pm.embedding_status = 'pending') THEN 'pending'
+                     ELSE 'ready' END AS status,
+                1 - sc.distance AS score
+         FROM scored sc
+         JOIN phrases p ON p.id = sc.phrase_id
+         JOIN phrase_meanings pm ON pm.phrase_id = p.id
+         WHERE {}
+         GROUP BY p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at, sc.distance
+         ORDER BY sc.distance
+         LIMIT $2",
+        filter_predicate(&mut Placeholders::after(2))
+    );
+
+    let query = sqlx::query_as::<_, ScoredPhraseRow>(&query)
+        .bind(phrase_id)
+        .bind(limit);
+    let rows = bind_filter(query, filter).fetch_all(pool).await?;
+    Ok(rows)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_meaning_embeddings(
+    pool: &PgPool,
+    phrase_id: Uuid,
+) -> Result<Vec<Vector>, AppError> {
+    let rows = sqlx::query_scalar::<_, Vector>(
+        "SELECT meaning_embedding FROM phrase_meanings
+         WHERE phrase_id = $1 AND meaning_embedding IS NOT NULL
+         ORDER BY created_at",
+    )
+    .bind(phrase_id)
+    .fetch_all(pool)
+    .await?;
     Ok(rows)
 }
 
+/// The `k` phrases nearest to an embedding with their tags, optionally leaving one out.
+#[tracing::instrument(skip_all)]
+pub async fn nearest_tagged_phrases(
+    pool: &PgPool,
+    embedding: &Vector,
+    k: i64,
+    exclude: Option<Uuid>,
+) -> Result<Vec<TagNeighbour>, AppError> {
+    let rows = sqlx::query_as::<_, TagNeighbour>(
+        "SELECT p.id, p.tags, 1 - MIN(pm.meaning_embedding <=> $1) AS similarity
+         FROM phrases p
+         JOIN phrase_meanings pm ON pm.phrase_id = p.id
+         WHERE p.id IS DISTINCT FROM $3 AND pm.meaning_embedding IS NOT NULL
+         GROUP BY p.id, p.tags
+         ORDER BY MIN(pm.meaning_embedding <=> $1)
+         LIMIT $2",
+    )
+    .bind(embedding)
+    .bind(k)
+    .bind(exclude)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+const PHRASE_FIELD: &str = "eemee_normalize(p.phrase)";
+const MEANING_FIELD: &str = "eemee_normalize(pm2.meaning)";
+const TAG_FIELD: &str = "eemee_normalize(t)";
+const SOURCE_FIELD: &str = "eemee_normalize(p.source)";
+const MEMO_FIELD: &str = "eemee_normalize(p.memo)";
+/// Every tag of a phrase in one normalized string, which has a trigram index.
+const TAGS_FIELD: &str = "eemee_normalize_tags(p.tags)";
+
+/// SQL for a normalized search term and the LIKE pattern matching it as a substring.
+struct TermSql {
+    term: String,
+    pattern: String,
+    fuzzy: bool,
+}
+
+impl TermSql {
+    fn new(param: &str, fuzzy: bool) -> Self {
+        let term = format!("eemee_normalize({param})");
+        let pattern = contains_pattern(&term);
+        TermSql {
+            term,
+            pattern,
+            fuzzy,
+        }
+    }
+
+    /// Whether a normalized field contains the term or, when fuzzy, is similar enough to it.
+    fn matches(&self, field: &str) -> String {
+        let TermSql { term, pattern, .. } = self;
+        if self.fuzzy {
+            format!("({field} LIKE {pattern} OR {term} <% {field})")
+        } else {
+            format!("({field} LIKE {pattern})")
+        }
+    }
+
+    /// Relevance of a normalized field: 1 for a substring match, otherwise trigram word
+    /// similarity when fuzzy (typo tolerance), otherwise NULL.
+    fn score(&self, field: &str) -> String {
+        let TermSql { term, pattern, .. } = self;
+        if self.fuzzy {
+            format!(
+                "CASE WHEN {field} LIKE {pattern} THEN 1.0::float8
+                      ELSE word_similarity({term}, {field})::float8 END"
+            )
+        } else {
+            format!("CASE WHEN {field} LIKE {pattern} THEN 1.0::float8 END")
+        }
+    }
+
+    fn meaning_matches(&self) -> String {
+        format!(
+            "EXISTS (SELECT 1 FROM phrase_meanings pm2 WHERE pm2.phrase_id = p.id AND {})",
+            self.matches(MEANING_FIELD)
+        )
+    }
+
+    fn meaning_score(&self) -> String {
+        format!(
+            "(SELECT MAX({}) FROM phrase_meanings pm2 WHERE pm2.phrase_id = p.id)",
+            self.score(MEANING_FIELD)
+        )
+    }
+}
+
+/// Match predicate and score for one term. Unscoped terms search every field, and a match
+/// in the phrase outranks one in meanings, tags, source and memo, in that order.
+fn compile_term(term: &Term, param: &str) -> (String, String) {
+    let sql = TermSql::new(param, !term.exact);
+    match term.field {
+        Some(Field::Phrase) => (sql.matches(PHRASE_FIELD), sql.score(PHRASE_FIELD)),
+        Some(Field::Meaning) => (sql.meaning_matches(), sql.meaning_score()),
+        Some(Field::Tag) => (
+            format!(
+                "EXISTS (SELECT 1 FROM unnest(p.tags) AS t WHERE {TAG_FIELD} = {})",
+                sql.term
+            ),
+            "1.0::float8".to_string(),
+        ),
+        Some(Field::Source) => (sql.matches(SOURCE_FIELD), sql.score(SOURCE_FIELD)),
+        Some(Field::Memo) => (sql.matches(MEMO_FIELD), sql.score(MEMO_FIELD)),
+        None => (
+            format!(
+                "({} OR {} OR {} OR EXISTS (SELECT 1 FROM unnest(p.tags) AS t WHERE {}) OR {})",
+                sql.matches(PHRASE_FIELD),
+                sql.matches(SOURCE_FIELD),
+                sql.matches(MEMO_FIELD),
+                sql.matches(TAG_FIELD),
+                sql.meaning_matches(),
+            ),
+            format!(
+                "GREATEST({}, {} * 0.9, (SELECT MAX({}) FROM unnest(p.tags) AS t) * 0.8,
+                          {} * 0.7, {} * 0.6)",
+                sql.score(PHRASE_FIELD),
+                sql.meaning_score(),
+                sql.score(TAG_FIELD),
+                sql.score(SOURCE_FIELD),
+                sql.score(MEMO_FIELD),
+            ),
+        ),
+    }
+}
+
+/// Compiles a parsed search query into a match predicate over `p` and a score expression,
+/// which is `None` when every term is excluded. Term values are appended to `binds`, in
+/// the order of the placeholders taken from `params`.
+fn compile_query(
+    query: &Query,
+    params: &mut Placeholders,
+    binds: &mut Vec<String>,
+) -> (String, Option<String>) {
+    match query {
+        Query::Term(term) => {
+            binds.push(term.value.clone());
+            let (matches, score) = compile_term(term, &params.next());
+            (
+                format!("COALESCE({matches}, false)"),
+                Some(format!("COALESCE({score}, 0)")),
+            )
+        }
+        Query::Not(inner) => {
+            let (matches, _) = compile_query(inner, params, binds);
+            (format!("NOT {matches}"), None)
+        }
+        Query::And(children) | Query::Or(children) => {
+            if children.is_empty() {
+                return ("TRUE".to_string(), None);
+            }
+            let (predicates, scores): (Vec<String>, Vec<Option<String>>) = children
+                .iter()
+                .map(|child| compile_query(child, params, binds))
+                .unzip();
+            let scores: Vec<String> = scores.into_iter().flatten().collect();
+            let (operator, score) = if matches!(query, Query::And(_)) {
+                // Mean relevance of the terms that must match
+                let score = (!scores.is_empty())
+                    .then(|| format!("(({}) / {})", scores.join(" + "), scores.len()));
+                (" AND ", score)
+            } else {
+                let score =
+                    (!scores.is_empty()).then(|| format!("GREATEST({})", scores.join(", ")));
+                (" OR ", score)
+            };
+            (format!("({})", predicates.join(operator)), score)
+        }
+    }
+}
+
+/// A `candidates` CTE with the ids of phrases matching any of `terms`, as a UNION of a
+/// lookup in `phrases` and one in `phrase_meanings`. Each arm is a disjunction of
+/// conditions on single trigram-indexed expressions, which the planner can answer with a
+/// bitmap OR of index scans; an `EXISTS` over meanings inside the phrase arm would force a
+/// scan of every phrase. The arms may over-match (a tag term matches the joined tags), as
+/// the full predicate is applied to the candidates afterwards.
+fn compile_candidates(
+    terms: &[&Term],
+    params: &mut Placeholders,
+    binds: &mut Vec<String>,
+) -> String {
+    let (mut phrase_arms, mut meaning_arms) = (Vec::new(), Vec::new());
+    for term in terms {
+        binds.push(term.value.clone());
+        let sql = TermSql::new(&params.next(), !term.exact);
+        match term.field {
+            Some(Field::Phrase) => phrase_arms.push(sql.matches(PHRASE_FIELD)),
+            Some(Field::Meaning) => meaning_arms.push(sql.matches(MEANING_FIELD)),
+            Some(Field::Tag) => phrase_arms.push(sql.matches(TAGS_FIELD)),
+            Some(Field::Source) => phrase_arms.push(sql.matches(SOURCE_FIELD)),
+            Some(Field::Memo) => phrase_arms.push(sql.matches(MEMO_FIELD)),
+            None => {
+                for field in [PHRASE_FIELD, SOURCE_FIELD, MEMO_FIELD, TAGS_FIELD] {
+                    phrase_arms.push(sql.matches(field));
+                }
+                meaning_arms.push(sql.matches(MEANING_FIELD));
+            }
+        }
+    }
+
+    let mut arms = Vec::new();
+    if !phrase_arms.is_empty() {
+        arms.push(format!(
+            "SELECT p.id FROM phrases p WHERE {}",
+            phrase_arms.join(" OR ")
+        ));
+    }
+    if !meaning_arms.is_empty() {
+        arms.push(format!(
+            "SELECT pm2.phrase_id FROM phrase_meanings pm2 WHERE {}",
+            meaning_arms.join(" OR ")
+        ));
+    }
+    format!("WITH candidates AS ({}) ", arms.join(" UNION "))
+}
+
+/// Text search with the query language parsed by [`crate::services::query`]. Both the
+/// query and the fields are folded with `eemee_normalize`, so width, kana and case variants
+/// match each other. Results are ordered by relevance, then by most recently updated.
+///
+/// Unless the query can match without any positive term (`-storm`), the search starts from
+/// [`compile_candidates`], so `EXPLAIN` should show bitmap scans on the `*_trgm` indexes
+/// rather than a sequential scan of `phrases` once the library outgrows a few pages.
+#[tracing::instrument(skip_all)]
 pub async fn text_search(
     pool: &PgPool,
-    query: &str,
+    query: &Query,
     limit: i64,
-) -> Result<Vec<PhraseWithMeaningsRow>, AppError> {
-    let pattern = format!("%{query}%");
+    filter: &PhraseFilter,
+) -> Result<Vec<ScoredPhraseRow>, AppError> {
+    // $1 is the limit, then the filter, then the term values in `binds`
+    let mut params = Placeholders::after(1);
+    let filter_sql = filter_predicate(&mut params);
+    let mut binds = Vec::new();
+    let (predicate, score) = compile_query(query, &mut params, &mut binds);
+    let score = score.unwrap_or_else(|| "0::float8".to_string());
+    let (candidates, candidates_join) = if query.requires_match() {
+        (
+            compile_candidates(&query.positive_terms(), &mut params, &mut binds),
+            "JOIN candidates c ON c.id = p.id",
+        )
+    } else {
+        (String::new(), "")
+    };
+
     let query_str = format!(
-        "{PHRASE_WITH_MEANINGS_QUERY}
-         WHERE p.phrase ILIKE $1
-            OR p.source ILIKE $1
-            OR EXISTS (SELECT 1 FROM unnest(p.tags) AS t WHERE t ILIKE $1)
-            OR EXISTS (SELECT 1 FROM phrase_meanings pm2 WHERE pm2.phrase_id = p.id AND pm2.meaning ILIKE $1)
+        "{candidates}SELECT p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at,
+                COALESCE(array_agg(pm.meaning ORDER BY pm.created_at)
+                         FILTER (WHERE pm.id IS NOT NULL), '{{}}') as meanings,
+                CASE WHEN COUNT(pm.id) = 0 THEN 'draft'
+                     WHEN bool_or(pm.embedding_status = 'failed') THEN 'failed'
+                     WHEN bool_or(pm.embedding_status = 'pending') THEN 'pending'
+                     ELSE 'ready' END AS status,
+                {score} AS score
+         FROM phrases p
+         {candidates_join}
+         LEFT JOIN phrase_meanings pm ON pm.phrase_id = p.id
+         WHERE {predicate}
+           AND {filter_sql}
          GROUP BY p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at
-         ORDER BY p.updated_at DESC
-         LIMIT $2"
+         ORDER BY score DESC, p.updated_at DESC
+         LIMIT $1"
     );
 
-    let rows = sqlx::query_as::<_, PhraseWithMeaningsRow>(&query_str)
-        .bind(&pattern)
-        .bind(limit)
-        .fetch_all(pool)
-        .await?;
+    let query = sqlx::query_as::<_, ScoredPhraseRow>(&query_str).bind(limit);
+    let mut query = bind_filter(query, filter);
+    for value in binds {
+        query = query.bind(value);
+    }
+    let rows = query.fetch_all(pool).await?;
     Ok(rows)
 }
 
+#[tracing::instrument(skip_all)]
 pub async fn get_random_phrases(
     pool: &PgPool,
     limit: i64,
@@ -196,15 +741,641 @@ pub async fn get_random_phrases(
     Ok(rows)
 }
 
-pub async fn get_all_phrases(pool: &PgPool) -> Result<Vec<PhraseWithMeaningsRow>, AppError> {
+/// Phrases without meanings, newest first.
+#[tracing::instrument(skip_all)]
+pub async fn list_drafts(
+    pool: &PgPool,
+    limit: i64,
+    filter: &PhraseFilter,
+) -> Result<Vec<PhraseWithMeaningsRow>, AppError> {
     let query = format!(
         "{PHRASE_WITH_MEANINGS_QUERY}
+         WHERE pm.id IS NULL AND {}
          GROUP BY p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at
-         ORDER BY p.created_at DESC"
+         ORDER BY p.created_at DESC, p.id
+         LIMIT $1",
+        filter_predicate(&mut Placeholders::after(1))
     );
 
-    let rows = sqlx::query_as::<_, PhraseWithMeaningsRow>(&query)
+    let query = sqlx::query_as::<_, PhraseWithMeaningsRow>(&query).bind(limit);
+    let rows = bind_filter(query, filter).fetch_all(pool).await?;
+    Ok(rows)
+}
+
+/// Export query; the filter binds come first, then the created-at range.
+static EXPORT_QUERY: LazyLock<String> = LazyLock::new(|| {
+    let mut params = Placeholders::after(0);
+    let filter_sql = filter_predicate(&mut params);
+    let (from, to) = (params.next(), params.next());
+    format!(
+        "{PHRASE_WITH_MEANINGS_QUERY}
+         WHERE {filter_sql}
+           AND ({from}::timestamptz IS NULL OR p.created_at >= {from})
+           AND ({to}::timestamptz IS NULL OR p.created_at < {to})
+         GROUP BY p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at
+         ORDER BY p.created_at DESC"
+    )
+});
+
+/// Streams every matching phrase, newest first, without collecting the result set.
+/// The stream holds a pooled connection until it is dropped.
+pub fn stream_phrases<'a>(
+    pool: &'a PgPool,
+    filter: &PhraseFilter,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> BoxStream<'a, Result<PhraseWithMeaningsRow, AppError>> {
+    let query = sqlx::query_as::<_, PhraseWithMeaningsRow>(&EXPORT_QUERY);
+    bind_filter(query, filter)
+        .bind(from)
+        .bind(to)
+        .fetch(pool)
+        .map_err(AppError::from)
+        .boxed()
+}
+
+const COLLECTION_QUERY: &str = "SELECT c.id, c.name, c.description, c.created_at, c.updated_at,
+            (SELECT COUNT(*) FROM collection_phrases cp WHERE cp.collection_id = c.id) AS phrase_count
+     FROM collections c";
+
+#[tracing::instrument(skip_all)]
+pub async fn list_collections(pool: &PgPool) -> Result<Vec<Collection>, AppError> {
+    let query = format!("{COLLECTION_QUERY} ORDER BY c.updated_at DESC");
+    let rows = sqlx::query_as::<_, Collection>(&query)
         .fetch_all(pool)
         .await?;
     Ok(rows)
 }
+
+#[tracing::instrument(skip_all)]
+pub async fn create_collection(
+    pool: &PgPool,
+    name: &str,
+    description: Option<&str>,
+) -> Result<Collection, AppError> {
+    let row = sqlx::query_as::<_, Collection>(
+        "INSERT INTO collections (name, description)
+         VALUES ($1, $2)
+         RETURNING id, name, description, created_at, updated_at, 0::bigint AS phrase_count",
+    )
+    .bind(name)
+    .bind(description)
+    .fetch_one(pool)
+    .await?;
+    Ok(row)
+}
+
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn get_collection(pool: &PgPool, id: Uuid) -> Result<Collection, AppError> {
+    let query = format!("{COLLECTION_QUERY} WHERE c.id = $1");
+    let row = sqlx::query_as::<_, Collection>(&query)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(row)
+}
+
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn update_collection(
+    pool: &PgPool,
+    id: Uuid,
+    name: Option<&str>,
+    description: Option<&str>,
+) -> Result<Collection, AppError> {
+    let result = sqlx::query(
+        "UPDATE collections
+         SET name = COALESCE($1, name), description = COALESCE($2, description), updated_at = now()
+         WHERE id = $3",
+    )
+    .bind(name)
+    .bind(description)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    get_collection(pool, id).await
+}
+
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn delete_collection(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM collections WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// Phrases of a collection in order. Positions are renumbered densely from zero,
+/// so gaps left by cascaded phrase deletions never show up in the API.
+#[tracing::instrument(skip_all)]
+pub async fn get_collection_phrases(
+    pool: &PgPool,
+    collection_id: Uuid,
+) -> Result<Vec<CollectionPhraseRow>, AppError> {
+    let rows = sqlx::query_as::<_, CollectionPhraseRow>(
+        "SELECT (ROW_NUMBER() OVER (ORDER BY cp.position) - 1)::int AS position,
+                p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at,
+                COALESCE(array_agg(pm.meaning ORDER BY pm.created_at)
+                         FILTER (WHERE pm.id IS NOT NULL), '{}') as meanings,
+                CASE WHEN COUNT(pm.id) = 0 THEN 'draft'
+                     WHEN bool_or(pm.embedding_status = 'failed') THEN 'failed'
+                     WHEN bool_or(pm.embedding_status = 'pending') THEN 'pending'
+                     ELSE 'ready' END AS status
+         FROM collection_phrases cp
+         JOIN phrases p ON p.id = cp.phrase_id
+         LEFT JOIN phrase_meanings pm ON pm.phrase_id = p.id
+         WHERE cp.collection_id = $1
+         GROUP BY cp.position, p.id, p.phrase, p.source, p.tags, p.memo, p.created_at, p.updated_at
+         ORDER BY cp.position",
+    )
+    .bind(collection_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Locks the collection row for the rest of the transaction and returns its member ids in order.
+async fn lock_collection_members(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    collection_id: Uuid,
+) -> Result<Vec<Uuid>, AppError> {
+    sqlx::query("SELECT id FROM collections WHERE id = $1 FOR UPDATE")
+        .bind(collection_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let members = sqlx::query_scalar::<_, Uuid>(
+        "SELECT phrase_id FROM collection_phrases WHERE collection_id = $1 ORDER BY position",
+    )
+    .bind(collection_id)
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(members)
+}
+
+/// Rewrites member positions to match `phrase_ids` and bumps the collection's `updated_at`.
+async fn write_collection_order(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    collection_id: Uuid,
+    phrase_ids: &[Uuid],
+) -> Result<(), AppError> {
+    for (position, phrase_id) in phrase_ids.iter().enumerate() {
+        sqlx::query(
+            "UPDATE collection_phrases SET position = $1
+             WHERE collection_id = $2 AND phrase_id = $3",
+        )
+        .bind(position as i32)
+        .bind(collection_id)
+        .bind(phrase_id)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    sqlx::query("UPDATE collections SET updated_at = now() WHERE id = $1")
+        .bind(collection_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn add_phrase_to_collection(
+    pool: &PgPool,
+    collection_id: Uuid,
+    phrase_id: Uuid,
+    position: Option<i32>,
+) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let mut members = lock_collection_members(&mut tx, collection_id).await?;
+
+    if members.contains(&phrase_id) {
+        return Err(AppError::BadRequest(
+            "Phrase is already in the collection".into(),
+        ));
+    }
+
+    let phrase_exists: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM phrases WHERE id = $1)")
+            .bind(phrase_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    if !phrase_exists {
+        return Err(AppError::NotFound);
+    }
+
+    let index = position
+        .map(|p| (p.max(0) as usize).min(members.len()))
+        .unwrap_or(members.len());
+
+    sqlx::query(
+        "INSERT INTO collection_phrases (collection_id, phrase_id, position)
+         VALUES ($1, $2, $3)",
+    )
+    .bind(collection_id)
+    .bind(phrase_id)
+    .bind(index as i32)
+    .execute(&mut *tx)
+    .await?;
+
+    members.insert(index, phrase_id);
+    write_collection_order(&mut tx, collection_id, &members).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn remove_phrase_from_collection(
+    pool: &PgPool,
+    collection_id: Uuid,
+    phrase_id: Uuid,
+) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let mut members = lock_collection_members(&mut tx, collection_id).await?;
+
+    let result =
+        sqlx::query("DELETE FROM collection_phrases WHERE collection_id = $1 AND phrase_id = $2")
+            .bind(collection_id)
+            .bind(phrase_id)
+            .execute(&mut *tx)
+            .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    members.retain(|id| *id != phrase_id);
+    write_collection_order(&mut tx, collection_id, &members).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Reorders a collection. `phrase_ids` must list every member exactly once.
+#[tracing::instrument(skip_all)]
+pub async fn reorder_collection(
+    pool: &PgPool,
+    collection_id: Uuid,
+    phrase_ids: &[Uuid],
+) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let members = lock_collection_members(&mut tx, collection_id).await?;
+
+    let mut expected = members.clone();
+    expected.sort();
+    let mut given = phrase_ids.to_vec();
+    given.sort();
+    if expected != given {
+        return Err(AppError::BadRequest(
+            "phrase_ids must list every phrase in the collection exactly once".into(),
+        ));
+    }
+
+    write_collection_order(&mut tx, collection_id, phrase_ids).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+const PHRASE_LINK_QUERY: &str = "SELECT l.id, l.link_type, l.note, l.created_at,
+            l.from_phrase_id = $1 AS outgoing,
+            other.id AS other_id, other.phrase AS other_phrase
+     FROM phrase_links l
+     JOIN phrases other
+       ON other.id = CASE WHEN l.from_phrase_id = $1 THEN l.to_phrase_id ELSE l.from_phrase_id END
+     WHERE (l.from_phrase_id = $1 OR l.to_phrase_id = $1)";
+
+/// Links touching a phrase in either direction, oldest first.
+#[tracing::instrument(skip_all)]
+pub async fn get_phrase_links(
+    pool: &PgPool,
+    phrase_id: Uuid,
+) -> Result<Vec<PhraseLinkRow>, AppError> {
+    let query = format!("{PHRASE_LINK_QUERY} ORDER BY l.created_at");
+    let rows = sqlx::query_as::<_, PhraseLinkRow>(&query)
+        .bind(phrase_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn create_link(
+    pool: &PgPool,
+    from_phrase_id: Uuid,
+    to_phrase_id: Uuid,
+    link_type: LinkType,
+    note: Option<&str>,
+) -> Result<PhraseLinkRow, AppError> {
+    if from_phrase_id == to_phrase_id {
+        return Err(AppError::BadRequest(
+            "A phrase cannot be linked to itself".into(),
+        ));
+    }
+
+    let existing: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM phrases WHERE id IN ($1, $2)")
+        .bind(from_phrase_id)
+        .bind(to_phrase_id)
+        .fetch_one(pool)
+        .await?;
+    if existing < 2 {
+        return Err(AppError::NotFound);
+    }
+
+    let link_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO phrase_links (from_phrase_id, to_phrase_id, link_type, note)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (from_phrase_id, to_phrase_id, link_type) DO NOTHING
+         RETURNING id",
+    )
+    .bind(from_phrase_id)
+    .bind(to_phrase_id)
+    .bind(link_type)
+    .bind(note)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Link already exists".into()))?;
+
+    let query = format!("{PHRASE_LINK_QUERY} AND l.id = $2");
+    let row = sqlx::query_as::<_, PhraseLinkRow>(&query)
+        .bind(from_phrase_id)
+        .bind(link_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(row)
+}
+
+/// Deletes a link, which must touch `phrase_id` on either end.
+#[tracing::instrument(skip_all)]
+pub async fn delete_link(pool: &PgPool, phrase_id: Uuid, link_id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query(
+        "DELETE FROM phrase_links
+         WHERE id = $1 AND (from_phrase_id = $2 OR to_phrase_id = $2)",
+    )
+    .bind(link_id)
+    .bind(phrase_id)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// Phrases reachable from `phrase_id` within `depth` hops (links followed in both
+/// directions), and every link between them.
+#[tracing::instrument(skip_all)]
+pub async fn get_phrase_graph(
+    pool: &PgPool,
+    phrase_id: Uuid,
+    depth: i32,
+) -> Result<(Vec<GraphNode>, Vec<GraphEdgeRow>), AppError> {
+    let nodes = sqlx::query_as::<_, GraphNode>(
+        "WITH RECURSIVE reachable(id, depth) AS (
+             SELECT $1::uuid, 0
+             UNION
+             SELECT CASE WHEN l.from_phrase_id = r.id THEN l.to_phrase_id ELSE l.from_phrase_id END,
+                    r.depth + 1
+             FROM reachable r
+             JOIN phrase_links l ON l.from_phrase_id = r.id OR l.to_phrase_id = r.id
+             WHERE r.depth < $2
+         )
+         SELECT p.id, p.phrase, MIN(r.depth) AS depth
+         FROM reachable r
+         JOIN phrases p ON p.id = r.id
+         GROUP BY p.id, p.phrase
+         ORDER BY depth, p.phrase",
+    )
+    .bind(phrase_id)
+    .bind(depth)
+    .fetch_all(pool)
+    .await?;
+
+    if nodes.is_empty() {
+        return Err(AppError::NotFound);
+    }
+
+    let ids: Vec<Uuid> = nodes.iter().map(|n| n.id).collect();
+    let edges = sqlx::query_as::<_, GraphEdgeRow>(
+        "SELECT id, from_phrase_id, to_phrase_id, link_type, note
+         FROM phrase_links
+         WHERE from_phrase_id = ANY($1) AND to_phrase_id = ANY($1)
+         ORDER BY created_at",
+    )
+    .bind(&ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok((nodes, edges))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn record_search(
+    pool: &PgPool,
+    mode: SearchMode,
+    query: &str,
+    filter: &PhraseFilter,
+    result_count: usize,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO search_history (mode, query, filters, result_count) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(mode)
+    .bind(query)
+    .bind(Json(filter))
+    .bind(i32::try_from(result_count).unwrap_or(i32::MAX))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn list_search_history(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<SearchHistoryRow>, AppError> {
+    let rows = sqlx::query_as::<_, SearchHistoryRow>(
+        "SELECT id, mode, query, filters, result_count, created_at
+         FROM search_history
+         ORDER BY created_at DESC
+         LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn clear_search_history(pool: &PgPool) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM search_history")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+const SAVED_SEARCH_QUERY: &str = "SELECT id, name, mode, query, filters,
+            query_embedding IS NOT NULL AS has_embedding, created_at, updated_at
+     FROM saved_searches";
+
+#[tracing::instrument(skip_all)]
+pub async fn list_saved_searches(pool: &PgPool) -> Result<Vec<SavedSearchRow>, AppError> {
+    let query = format!("{SAVED_SEARCH_QUERY} ORDER BY name, created_at");
+    let rows = sqlx::query_as::<_, SavedSearchRow>(&query)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn create_saved_search(
+    pool: &PgPool,
+    name: &str,
+    mode: SearchMode,
+    query: &str,
+    filter: &PhraseFilter,
+) -> Result<SavedSearchRow, AppError> {
+    let id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO saved_searches (name, mode, query, filters) VALUES ($1, $2, $3, $4)
+         RETURNING id",
+    )
+    .bind(name)
+    .bind(mode)
+    .bind(query)
+    .bind(Json(filter))
+    .fetch_one(pool)
+    .await?;
+
+    get_saved_search(pool, id).await
+}
+
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn get_saved_search(pool: &PgPool, id: Uuid) -> Result<SavedSearchRow, AppError> {
+    let query = format!("{SAVED_SEARCH_QUERY} WHERE id = $1");
+    let row = sqlx::query_as::<_, SavedSearchRow>(&query)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(row)
+}
+
+/// Updates the given fields. A cached query embedding is dropped when the mode or the
+/// query text changes.
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn update_saved_search(
+    pool: &PgPool,
+    id: Uuid,
+    name: Option<&str>,
+    mode: Option<SearchMode>,
+    query: Option<&str>,
+    filter: Option<&PhraseFilter>,
+) -> Result<SavedSearchRow, AppError> {
+    let result = sqlx::query(
+        "UPDATE saved_searches
+         SET name = COALESCE($1, name),
+             mode = COALESCE($2, mode),
+             query = COALESCE($3, query),
+             filters = COALESCE($4, filters),
+             query_embedding = CASE
+                 WHEN COALESCE($2, mode) <> mode OR COALESCE($3, query) <> query THEN NULL
+                 ELSE query_embedding
+             END,
+             updated_at = now()
+         WHERE id = $5",
+    )
+    .bind(name)
+    .bind(mode.map(SearchMode::as_str))
+    .bind(query)
+    .bind(filter.map(Json))
+    .bind(id)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    get_saved_search(pool, id).await
+}
+
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn delete_saved_search(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM saved_searches WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn get_saved_search_embedding(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<Vector>, AppError> {
+    let embedding = sqlx::query_scalar::<_, Option<Vector>>(
+        "SELECT query_embedding FROM saved_searches WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    Ok(embedding)
+}
+
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn set_saved_search_embedding(
+    pool: &PgPool,
+    id: Uuid,
+    embedding: &Vector,
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE saved_searches SET query_embedding = $1 WHERE id = $2")
+        .bind(embedding)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn library_stats(pool: &PgPool) -> Result<LibraryStats, AppError> {
+    let mut stats = sqlx::query_as::<_, LibraryStats>(
+        "SELECT
+             (SELECT COUNT(*) FROM phrases) AS phrases,
+             (SELECT COUNT(*) FROM phrases p
+              WHERE NOT EXISTS (SELECT 1 FROM phrase_meanings pm WHERE pm.phrase_id = p.id))
+                 AS drafts,
+             (SELECT COUNT(*) FROM phrase_meanings) AS meanings,
+             (SELECT COUNT(*) FROM phrase_meanings WHERE embedding_status = 'pending')
+                 AS pending_meanings,
+             (SELECT COUNT(*) FROM phrase_meanings WHERE embedding_status = 'failed')
+                 AS failed_meanings,
+             (SELECT COUNT(*) FROM collections) AS collections,
+             (SELECT COUNT(*) FROM phrase_links) AS links,
+             (SELECT COUNT(*) FROM saved_searches) AS saved_searches,
+             (SELECT COUNT(*) FROM search_history) AS search_history,
+             (SELECT COUNT(*) FROM embedding_jobs WHERE status = 'queued') AS queued_jobs,
+             (SELECT COUNT(*) FROM embedding_jobs WHERE status = 'dead') AS dead_jobs",
+    )
+    .fetch_one(pool)
+    .await?;
+    stats.embedding_models = sqlx::query_as::<_, ModelCount>(
+        "SELECT embedding_model AS model, COUNT(*) AS meanings
+         FROM phrase_meanings
+         WHERE embedding_status = 'ready'
+         GROUP BY embedding_model
+         ORDER BY meanings DESC, model",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(stats)
+}
@@ -1,15 +1,41 @@
 use std::future::Future;
 use std::pin::Pin;
+use std::time::Duration;
 
 use crate::error::AppError;
 use pgvector::Vector;
 use serde::{Deserialize, Serialize};
 
+/// Model used by [`EmbeddingService`].
+pub const EMBEDDING_MODEL: &str = "text-embedding-3-large";
+/// Length of every stored embedding; fixed by the `vector(3072)` columns.
+pub const EMBEDDING_DIMENSIONS: usize = 3072;
+
+/// An embedding with what it cost.
+#[derive(Debug, Clone)]
+pub struct Embedding {
+    pub vector: Vector,
+    /// Input tokens billed for it.
+    pub tokens: i64,
+}
+
 pub trait Embedder: Send + Sync {
+    /// Name of the model, stored next to each embedding.
+    fn model(&self) -> &str;
+
+    /// Who serves the model, for metrics.
+    fn provider(&self) -> &str;
+
     fn embed<'a>(
         &'a self,
         text: &'a str,
-    ) -> Pin<Box<dyn Future<Output = Result<Vector, AppError>> + Send + 'a>>;
+    ) -> Pin<Box<dyn Future<Output = Result<Embedding, AppError>> + Send + 'a>>;
+
+    /// Checks that the provider can be reached with our credentials, without a billed
+    /// call. Providers with nothing to check report success.
+    fn check(&self) -> Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + '_>> {
+        Box::pin(async { Ok(()) })
+    }
 }
 
 #[derive(Clone)]
@@ -27,6 +53,7 @@ struct EmbeddingRequest {
 #[derive(Deserialize)]
 struct EmbeddingResponse {
     data: Vec<EmbeddingData>,
+    usage: EmbeddingUsage,
 }
 
 #[derive(Deserialize)]
@@ -34,17 +61,25 @@ struct EmbeddingData {
     embedding: Vec<f32>,
 }
 
+#[derive(Deserialize)]
+struct EmbeddingUsage {
+    total_tokens: i64,
+}
+
 impl EmbeddingService {
-    pub fn new(api_key: String) -> Self {
-        Self {
-            client: reqwest::Client::new(),
-            api_key,
-        }
+    /// `timeout` bounds each API call, so a stalled request cannot hold a worker forever.
+    pub fn new(api_key: String, timeout: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to build HTTP client");
+        Self { client, api_key }
     }
 
-    async fn embed_impl(&self, text: &str) -> Result<Vector, AppError> {
+    #[tracing::instrument(name = "embed", skip_all, fields(model = EMBEDDING_MODEL, chars = text.len()))]
+    async fn embed_impl(&self, text: &str) -> Result<Embedding, AppError> {
         let request = EmbeddingRequest {
-            model: "text-embedding-3-large".to_string(),
+            model: EMBEDDING_MODEL.to_string(),
             input: text.to_string(),
         };
 
@@ -79,15 +114,51 @@ impl EmbeddingService {
             .next()
             .ok_or_else(|| AppError::Embedding("No embedding returned".to_string()))?;
 
-        Ok(Vector::from(embedding.embedding))
+        Ok(Embedding {
+            vector: Vector::from(embedding.embedding),
+            tokens: result.usage.total_tokens,
+        })
+    }
+
+    /// Looks up the model, which is free and fails on a revoked key like an embedding would.
+    async fn check_impl(&self) -> Result<(), AppError> {
+        let response = self
+            .client
+            .get(format!(
+                "https://api.openai.com/v1/models/{EMBEDDING_MODEL}"
+            ))
+            .bearer_auth(&self.api_key)
+            .send()
+            .await
+            .map_err(|e| AppError::Embedding(e.to_string()))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(AppError::Embedding(format!(
+                "OpenAI API error {}",
+                response.status()
+            )))
+        }
     }
 }
 
 impl Embedder for EmbeddingService {
+    fn model(&self) -> &str {
+        EMBEDDING_MODEL
+    }
+
+    fn provider(&self) -> &str {
+        "openai"
+    }
+
     fn embed<'a>(
         &'a self,
         text: &'a str,
-    ) -> Pin<Box<dyn Future<Output = Result<Vector, AppError>> + Send + 'a>> {
+    ) -> Pin<Box<dyn Future<Output = Result<Embedding, AppError>> + Send + 'a>> {
         Box::pin(self.embed_impl(text))
     }
+
+    fn check(&self) -> Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + '_>> {
+        Box::pin(self.check_impl())
+    }
 }
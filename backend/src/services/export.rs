@@ -0,0 +1,196 @@
+use crate::error::AppError;
+use crate::models::phrase::Phrase;
+
+const CSV_HEADER: [&str; 8] = [
+    "id",
+    "phrase",
+    "meanings",
+    "source",
+    "tags",
+    "memo",
+    "created_at",
+    "updated_at",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Ndjson,
+    Csv,
+}
+
+impl ExportFormat {
+    /// Unknown formats fall back to JSON.
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "csv" => ExportFormat::Csv,
+            "ndjson" => ExportFormat::Ndjson,
+            _ => ExportFormat::Json,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson; charset=utf-8",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    pub fn content_disposition(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "attachment; filename=\"eemee-export.json\"",
+            ExportFormat::Ndjson => "attachment; filename=\"eemee-export.ndjson\"",
+            ExportFormat::Csv => "attachment; filename=\"eemee-export.csv\"",
+        }
+    }
+}
+
+/// Serializes phrases one at a time, so an export never holds more than one row.
+/// Call [`Encoder::header`], then [`Encoder::row`] per phrase, then [`Encoder::footer`].
+pub struct Encoder {
+    format: ExportFormat,
+    rows: usize,
+}
+
+impl Encoder {
+    pub fn new(format: ExportFormat) -> Self {
+        Encoder { format, rows: 0 }
+    }
+
+    pub fn header(&mut self) -> Result<Vec<u8>, AppError> {
+        match self.format {
+            ExportFormat::Json => Ok(b"[".to_vec()),
+            ExportFormat::Ndjson => Ok(Vec::new()),
+            ExportFormat::Csv => self.csv_record(CSV_HEADER),
+        }
+    }
+
+    pub fn row(&mut self, phrase: &Phrase) -> Result<Vec<u8>, AppError> {
+        self.rows += 1;
+        match self.format {
+            ExportFormat::Json => {
+                let mut out = if self.rows == 1 {
+                    b"\n".to_vec()
+                } else {
+                    b",\n".to_vec()
+                };
+                serde_json::to_writer_pretty(&mut out, phrase)
+                    .map_err(|e| AppError::Internal(e.to_string()))?;
+                Ok(out)
+            }
+            ExportFormat::Ndjson => {
+                let mut out =
+                    serde_json::to_vec(phrase).map_err(|e| AppError::Internal(e.to_string()))?;
+                out.push(b'\n');
+                Ok(out)
+            }
+            ExportFormat::Csv => self.csv_record([
+                &phrase.id.to_string(),
+                &phrase.phrase,
+                &phrase.meanings.join(" | "),
+                phrase.source.as_deref().unwrap_or(""),
+                &phrase.tags.join(", "),
+                phrase.memo.as_deref().unwrap_or(""),
+                &phrase.created_at.to_rfc3339(),
+                &phrase.updated_at.to_rfc3339(),
+            ]),
+        }
+    }
+
+    pub fn footer(&mut self) -> Vec<u8> {
+        match self.format {
+            ExportFormat::Json if self.rows > 0 => b"\n]".to_vec(),
+            ExportFormat::Json => b"]".to_vec(),
+            ExportFormat::Ndjson | ExportFormat::Csv => Vec::new(),
+        }
+    }
+
+    fn csv_record<I, T>(&self, record: I) -> Result<Vec<u8>, AppError>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        // Records are independent, so each gets its own writer
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer
+            .write_record(record)
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        writer
+            .into_inner()
+            .map_err(|e| AppError::Internal(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::phrase::PhraseStatus;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn phrase(text: &str) -> Phrase {
+        Phrase {
+            id: Uuid::new_v4(),
+            phrase: text.to_string(),
+            meanings: vec!["one".to_string(), "two, \"quoted\"".to_string()],
+            status: PhraseStatus::Ready,
+            source: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+            memo: Some("line\nbreak".to_string()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn encode(format: ExportFormat, phrases: &[Phrase]) -> String {
+        let mut encoder = Encoder::new(format);
+        let mut out = encoder.header().unwrap();
+        for p in phrases {
+            out.extend(encoder.row(p).unwrap());
+        }
+        out.extend(encoder.footer());
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(ExportFormat::parse("csv"), ExportFormat::Csv);
+        assert_eq!(ExportFormat::parse("ndjson"), ExportFormat::Ndjson);
+        assert_eq!(ExportFormat::parse("json"), ExportFormat::Json);
+        assert_eq!(ExportFormat::parse("xml"), ExportFormat::Json);
+    }
+
+    #[test]
+    fn json_is_a_valid_array() {
+        for n in 0..3 {
+            let phrases: Vec<Phrase> = (0..n).map(|i| phrase(&format!("p{i}"))).collect();
+            let out = encode(ExportFormat::Json, &phrases);
+            let parsed: Vec<serde_json::Value> = serde_json::from_str(&out).unwrap();
+            assert_eq!(parsed.len(), n);
+        }
+        assert_eq!(encode(ExportFormat::Json, &[]), "[]");
+    }
+
+    #[test]
+    fn ndjson_has_one_object_per_line() {
+        let out = encode(ExportFormat::Ndjson, &[phrase("p0"), phrase("p1")]);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let second: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(second["phrase"], "p1");
+        assert_eq!(second["memo"], "line\nbreak");
+    }
+
+    #[test]
+    fn csv_quotes_across_chunks() {
+        let out = encode(ExportFormat::Csv, &[phrase("p0"), phrase("p1")]);
+        let mut reader = csv::Reader::from_reader(out.as_bytes());
+        assert_eq!(reader.headers().unwrap(), CSV_HEADER.as_slice());
+        let records: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(&records[1][1], "p1");
+        assert_eq!(&records[1][2], "one | two, \"quoted\"");
+        assert_eq!(&records[1][5], "line\nbreak");
+    }
+}
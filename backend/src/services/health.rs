@@ -0,0 +1,114 @@
+//! Dependency checks behind `GET /api/health/ready`. Each check runs under a timeout, so a
+//! hung dependency fails readiness rather than hanging the probe. The endpoint is public,
+//! so failures are logged in full and reported with a short, generic message.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use sqlx::PgPool;
+
+use crate::models::health::{Check, Checks, Readiness};
+use crate::services::db::MIGRATOR;
+use crate::services::embedding::Embedder;
+use crate::state::AppState;
+
+pub async fn readiness(state: &AppState) -> Readiness {
+    let timeout = state.health.timeout;
+    let (database, migrations, embedding) = tokio::join!(
+        timed(timeout, check_database(&state.pool)),
+        timed(timeout, check_migrations(&state.pool)),
+        async {
+            if state.health.probe_embedder {
+                timed(timeout, check_embedder(state.embedding.as_ref())).await
+            } else {
+                Check::skipped()
+            }
+        },
+    );
+    Readiness::new(Checks {
+        database,
+        migrations,
+        embedding,
+    })
+}
+
+async fn timed(timeout: Duration, check: impl Future<Output = Result<(), String>>) -> Check {
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, check)
+        .await
+        .unwrap_or_else(|_| Err(format!("Timed out after {}ms", timeout.as_millis())));
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(()) => Check::ok(latency_ms),
+        Err(e) => Check::failed(latency_ms, e),
+    }
+}
+
+async fn check_database(pool: &PgPool) -> Result<(), String> {
+    sqlx::query("SELECT 1")
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| {
+            tracing::warn!("Readiness: database check failed: {e}");
+            "Query failed".to_string()
+        })
+}
+
+async fn check_migrations(pool: &PgPool) -> Result<(), String> {
+    let applied: Vec<i64> =
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| {
+                tracing::warn!("Readiness: migration check failed: {e}");
+                "Cannot read applied migrations".to_string()
+            })?;
+    let expected = MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| m.version);
+    let pending = pending_migrations(expected, &applied);
+    if pending.is_empty() {
+        Ok(())
+    } else {
+        let versions: Vec<String> = pending.iter().map(i64::to_string).collect();
+        Err(format!(
+            "{} migration(s) pending: {}",
+            pending.len(),
+            versions.join(", ")
+        ))
+    }
+}
+
+/// Versions this build expects that the database hasn't applied, in order.
+pub fn pending_migrations(expected: impl IntoIterator<Item = i64>, applied: &[i64]) -> Vec<i64> {
+    expected
+        .into_iter()
+        .filter(|v| !applied.contains(v))
+        .collect()
+}
+
+/// The provider's own no-cost check; catches an unreachable API or a revoked key.
+async fn check_embedder(embedder: &dyn Embedder) -> Result<(), String> {
+    embedder.check().await.map_err(|e| {
+        tracing::warn!("Readiness: embedding check failed: {e}");
+        "Embedding request failed".to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_migrations_are_those_not_applied() {
+        assert_eq!(pending_migrations([1, 2, 3], &[1, 3]), vec![2]);
+        assert!(pending_migrations([1, 2], &[1, 2, 5]).is_empty());
+    }
+
+    #[test]
+    fn migrator_embeds_this_crates_migrations() {
+        assert!(MIGRATOR.iter().count() > 0);
+    }
+}
@@ -0,0 +1,35 @@
+use std::collections::HashSet;
+
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::import::ImportSummary;
+use crate::services::{db, kindle};
+
+/// Imports the highlights of a `My Clippings.txt` as drafts, skipping those already saved
+/// from the same book.
+pub async fn import_kindle(pool: &PgPool, clippings: &str) -> Result<ImportSummary, AppError> {
+    let parsed = kindle::parse(clippings);
+    let total = parsed.clippings.len() + parsed.malformed;
+    let highlights = kindle::latest_highlights(parsed.clippings);
+
+    let mut sources: Vec<String> = highlights.iter().map(|c| c.source()).collect();
+    sources.sort();
+    sources.dedup();
+    let mut seen: HashSet<(String, String)> = db::get_phrases_from_sources(pool, &sources)
+        .await?
+        .into_iter()
+        .map(|(source, phrase)| (source, kindle::phrase_key(&phrase)))
+        .collect();
+
+    let considered = highlights.len();
+    let (drafts, duplicates) = kindle::drafts(highlights, &mut seen);
+    let phrase_ids = db::create_drafts(pool, &drafts).await?;
+
+    Ok(ImportSummary {
+        imported: phrase_ids.len(),
+        duplicates,
+        skipped: total - considered,
+        phrase_ids,
+    })
+}
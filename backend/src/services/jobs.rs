@@ -0,0 +1,248 @@
+//! Background embedding of meanings. Saving a phrase queues one job per meaning in
+//! `embedding_jobs`; workers claim a job with `FOR UPDATE SKIP LOCKED`, so several can run
+//! side by side. Claiming leases the job by moving its `run_at` past the lease, and the
+//! claim is committed before the embedding API is called, so no connection is held while
+//! waiting on it. A worker that dies mid-job leaves the job to be picked up again once
+//! the lease runs out.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::job::{EmbeddingJob, JobQuery};
+use crate::services::embedding::{EMBEDDING_DIMENSIONS, Embedder};
+use crate::services::metrics::METRICS;
+
+/// Attempts before a job is marked dead and its meaning `failed`.
+pub const MAX_ATTEMPTS: i32 = 5;
+const RETRY_BASE: Duration = Duration::from_secs(30);
+/// How long an idle worker waits before looking for new jobs.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a claimed job is left to its worker; well beyond any embedding call.
+const LEASE: Duration = Duration::from_secs(600);
+
+#[derive(Debug, FromRow)]
+struct ClaimedJob {
+    id: Uuid,
+    meaning_id: Uuid,
+    attempts: i32,
+    meaning: String,
+    /// The job's `run_at` once leased. The result is only written while it is unchanged,
+    /// as the job may have been queued afresh, e.g. by an edit, in the meantime.
+    leased_until: DateTime<Utc>,
+}
+
+/// Delay before retrying a job that has failed `attempts` times: 30 s, doubling each time.
+pub fn retry_delay(attempts: i32) -> Duration {
+    RETRY_BASE * 2u32.pow(attempts.clamp(1, 16) as u32 - 1)
+}
+
+/// Runs the next due job, if any. Returns whether there was one, failed or not.
+pub async fn run_next(pool: &PgPool, embedder: &dyn Embedder) -> Result<bool, AppError> {
+    let job = sqlx::query_as::<_, ClaimedJob>(
+        "UPDATE embedding_jobs j
+         SET run_at = now() + make_interval(secs => $1), updated_at = now()
+         FROM phrase_meanings pm
+         WHERE pm.id = j.meaning_id
+           AND j.id = (SELECT id FROM embedding_jobs
+                       WHERE status = 'queued' AND run_at <= now()
+                       ORDER BY run_at
+                       LIMIT 1
+                       FOR UPDATE SKIP LOCKED)
+         RETURNING j.id, j.meaning_id, j.attempts, pm.meaning, j.run_at AS leased_until",
+    )
+    .bind(LEASE.as_secs_f64())
+    .fetch_optional(pool)
+    .await?;
+    let Some(job) = job else {
+        return Ok(false);
+    };
+
+    let embedded = embedder.embed(&job.meaning).await.and_then(|embedding| {
+        let embedding = embedding.vector;
+        if embedding.as_slice().len() == EMBEDDING_DIMENSIONS {
+            Ok(embedding)
+        } else {
+            Err(AppError::Embedding(format!(
+                "Expected {EMBEDDING_DIMENSIONS} dimensions, got {}",
+                embedding.as_slice().len()
+            )))
+        }
+    });
+
+    let mut tx = pool.begin().await?;
+    match embedded {
+        Ok(embedding) => {
+            let finished = sqlx::query("DELETE FROM embedding_jobs WHERE id = $1 AND run_at = $2")
+                .bind(job.id)
+                .bind(job.leased_until)
+                .execute(&mut *tx)
+                .await?;
+            if finished.rows_affected() == 1 {
+                sqlx::query(
+                    "UPDATE phrase_meanings
+                     SET meaning_embedding = $2, embedding_model = $3, embedding_status = 'ready'
+                     WHERE id = $1",
+                )
+                .bind(job.meaning_id)
+                .bind(embedding)
+                .bind(embedder.model())
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        Err(e) => {
+            let attempts = job.attempts + 1;
+            let dead = attempts >= MAX_ATTEMPTS;
+            METRICS.record_job_failure(embedder.provider(), dead);
+            tracing::warn!(
+                "Embedding job {} failed (attempt {attempts}/{MAX_ATTEMPTS}): {e}",
+                job.id
+            );
+            let recorded = sqlx::query(
+                "UPDATE embedding_jobs
+                 SET attempts = $2, last_error = $3, updated_at = now(),
+                     status = CASE WHEN $4 THEN 'dead' ELSE 'queued' END,
+                     run_at = now() + make_interval(secs => $5)
+                 WHERE id = $1 AND run_at = $6",
+            )
+            .bind(job.id)
+            .bind(attempts)
+            .bind(e.to_string())
+            .bind(dead)
+            .bind(retry_delay(attempts).as_secs_f64())
+            .bind(job.leased_until)
+            .execute(&mut *tx)
+            .await?;
+            if dead && recorded.rows_affected() == 1 {
+                sqlx::query("UPDATE phrase_meanings SET embedding_status = 'failed' WHERE id = $1")
+                    .bind(job.meaning_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+    }
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Runs due jobs until none are left, returning how many ran.
+pub async fn drain(pool: &PgPool, embedder: &dyn Embedder) -> Result<usize, AppError> {
+    let mut ran = 0;
+    while run_next(pool, embedder).await? {
+        ran += 1;
+    }
+    Ok(ran)
+}
+
+/// Starts `count` workers that poll for jobs until `shutdown` is cancelled. A worker
+/// finishes the job it is running first; the handles complete once each has stopped.
+pub fn spawn_workers(
+    pool: PgPool,
+    embedder: Arc<dyn Embedder>,
+    count: usize,
+    shutdown: CancellationToken,
+) -> Vec<JoinHandle<()>> {
+    (0..count)
+        .map(|_| {
+            let pool = pool.clone();
+            let embedder = embedder.clone();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                while !shutdown.is_cancelled() {
+                    match run_next(&pool, embedder.as_ref()).await {
+                        Ok(true) => continue,
+                        Ok(false) => {}
+                        Err(e) => tracing::error!("Embedding worker error: {e}"),
+                    }
+                    tokio::select! {
+                        _ = shutdown.cancelled() => {}
+                        _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+/// Jobs with their meanings, dead ones first as they need attention.
+pub async fn list_jobs(pool: &PgPool, query: &JobQuery) -> Result<Vec<EmbeddingJob>, AppError> {
+    let jobs = sqlx::query_as::<_, EmbeddingJob>(
+        "SELECT j.id, j.meaning_id, pm.phrase_id, pm.meaning, j.status, j.attempts,
+                j.last_error, j.run_at, j.created_at, j.updated_at
+         FROM embedding_jobs j
+         JOIN phrase_meanings pm ON pm.id = j.meaning_id
+         WHERE $1::text IS NULL OR j.status = $1
+         ORDER BY j.status = 'dead' DESC, j.run_at
+         LIMIT $2",
+    )
+    .bind(query.status)
+    .bind(query.limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(jobs)
+}
+
+/// Queues a job again with a fresh set of attempts, e.g. once a dead job's cause is fixed.
+pub async fn retry_job(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let meaning_id: Uuid = sqlx::query_scalar(
+        "UPDATE embedding_jobs
+         SET status = 'queued', attempts = 0, run_at = now(), updated_at = now()
+         WHERE id = $1
+         RETURNING meaning_id",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    sqlx::query("UPDATE phrase_meanings SET embedding_status = 'pending' WHERE id = $1")
+        .bind(meaning_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Queues meanings to be embedded again: those not embedded by `model`, including failed
+/// ones, or every meaning with `all`. Their embeddings are cleared, so search leaves them
+/// out until the workers catch up. Returns how many were queued.
+pub async fn requeue_meanings(pool: &PgPool, model: &str, all: bool) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        "WITH stale AS (
+             UPDATE phrase_meanings
+             SET meaning_embedding = NULL, embedding_model = NULL, embedding_status = 'pending'
+             WHERE $2 OR embedding_model IS DISTINCT FROM $1
+             RETURNING id
+         )
+         INSERT INTO embedding_jobs (meaning_id) SELECT id FROM stale
+         ON CONFLICT (meaning_id) DO UPDATE
+         SET status = 'queued', attempts = 0, last_error = NULL,
+             run_at = now(), updated_at = now()",
+    )
+    .bind(model)
+    .bind(all)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(4), Duration::from_secs(240));
+        assert_eq!(retry_delay(0), Duration::from_secs(30));
+    }
+}
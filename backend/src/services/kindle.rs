@@ -0,0 +1,459 @@
+//! Parser for Kindle's `My Clippings.txt`, in the English and Japanese locales.
+//!
+//! Each clipping is a title line, a metadata line, a blank line and the text, followed by
+//! a `==========` separator:
+//!
+//! ```text
+//! Book Title (Author)
+//! - Your Highlight on page 12 | Location 170-172 | Added on Sunday, March 3, 2019 10:21:33 PM
+//!
+//! highlighted text
+//! ==========
+//! 本のタイトル (著者)
+//! - 12ページ|位置No. 170-172のハイライト |作成日: 2019年3月3日日曜日 22:21:33
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{NaiveDateTime, Utc};
+
+use crate::models::import::NewDraft;
+use crate::services::text::normalize;
+
+const SEPARATOR: &str = "==========";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClippingKind {
+    Highlight,
+    Note,
+    Bookmark,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clipping {
+    pub title: String,
+    pub author: Option<String>,
+    pub kind: ClippingKind,
+    pub page: Option<String>,
+    /// Location range as written, e.g. `170-172`.
+    pub location: Option<String>,
+    /// Local time of the device; the file carries no time zone.
+    pub added_at: Option<NaiveDateTime>,
+    pub text: String,
+}
+
+impl Clipping {
+    /// `Title (Author)`, the way Kindle names the book.
+    pub fn source(&self) -> String {
+        match &self.author {
+            Some(author) => format!("{} ({author})", self.title),
+            None => self.title.clone(),
+        }
+    }
+
+    /// Where in the book the clipping is, e.g. `page 12, location 170-172`.
+    pub fn position(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            self.page.as_ref().map(|p| format!("page {p}")),
+            self.location.as_ref().map(|l| format!("location {l}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    fn location_start(&self) -> Option<&str> {
+        self.location
+            .as_deref()
+            .map(|l| l.split('-').next().unwrap_or(l))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedClippings {
+    pub clippings: Vec<Clipping>,
+    /// Entries that could not be parsed.
+    pub malformed: usize,
+}
+
+pub fn parse(input: &str) -> ParsedClippings {
+    let mut parsed = ParsedClippings::default();
+    for entry in input.split(SEPARATOR) {
+        if entry
+            .trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}')
+            .is_empty()
+        {
+            continue;
+        }
+        match parse_entry(entry) {
+            Some(clipping) => parsed.clippings.push(clipping),
+            None => parsed.malformed += 1,
+        }
+    }
+    parsed
+}
+
+fn parse_entry(entry: &str) -> Option<Clipping> {
+    let mut lines = entry
+        .lines()
+        .map(|line| line.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}'))
+        .skip_while(|line| line.is_empty());
+    let (title, author) = split_title(lines.next()?);
+    let meta = lines.next()?.strip_prefix('-')?;
+    let text = lines
+        .skip_while(|line| line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string();
+
+    let mut kind = None;
+    let mut page = None;
+    let mut location = None;
+    let mut added_at = None;
+    for segment in meta.split('|').map(str::trim) {
+        kind = kind.or_else(|| parse_kind(segment));
+        page = page.or_else(|| parse_page(segment));
+        location = location.or_else(|| parse_location(segment));
+        added_at = added_at.or_else(|| parse_added(segment));
+    }
+
+    Some(Clipping {
+        title,
+        author,
+        kind: kind?,
+        page,
+        location,
+        added_at,
+        text,
+    })
+}
+
+/// Splits `Title (Author)` on the last parenthesised group; titles may contain parentheses.
+fn split_title(line: &str) -> (String, Option<String>) {
+    let line = line.trim();
+    for (open, close) in [('(', ')'), ('（', '）')] {
+        if let Some(rest) = line.strip_suffix(close)
+            && let Some(start) = rest.rfind(open)
+        {
+            let author = rest[start + open.len_utf8()..].trim();
+            let title = rest[..start].trim();
+            if !title.is_empty() && !author.is_empty() {
+                return (title.to_string(), Some(author.to_string()));
+            }
+        }
+    }
+    (line.to_string(), None)
+}
+
+fn parse_kind(segment: &str) -> Option<ClippingKind> {
+    let lower = segment.to_lowercase();
+    if lower.contains("highlight") || segment.contains("ハイライト") {
+        Some(ClippingKind::Highlight)
+    } else if lower.contains("note") || segment.contains("メモ") {
+        Some(ClippingKind::Note)
+    } else if lower.contains("bookmark") || segment.contains("ブックマーク") {
+        Some(ClippingKind::Bookmark)
+    } else {
+        None
+    }
+}
+
+/// The token right after `marker`, made of chars accepted by `keep`.
+fn token_after<'a>(segment: &'a str, marker: &str, keep: fn(char) -> bool) -> Option<&'a str> {
+    let start = segment.find(marker)? + marker.len();
+    let rest = segment[start..].trim_start();
+    let end = rest.find(|c: char| !keep(c)).unwrap_or(rest.len());
+    (end > 0).then(|| &rest[..end])
+}
+
+fn is_range_char(c: char) -> bool {
+    c.is_ascii_digit() || c == '-'
+}
+
+fn parse_page(segment: &str) -> Option<String> {
+    let lower = segment.to_lowercase();
+    if let Some(page) = token_after(&lower, "page", |c| c.is_ascii_alphanumeric() || c == '-') {
+        return Some(page.to_string());
+    }
+    // 12ページ
+    let end = segment.find("ページ")?;
+    let digits = segment[..end]
+        .trim_end()
+        .rsplit(|c: char| !is_range_char(c))
+        .next()?;
+    (!digits.is_empty()).then(|| digits.to_string())
+}
+
+fn parse_location(segment: &str) -> Option<String> {
+    let lower = segment.to_lowercase();
+    ["location", "loc."]
+        .iter()
+        .find_map(|marker| token_after(&lower, marker, is_range_char))
+        .or_else(|| token_after(segment, "位置No.", is_range_char))
+        .map(str::to_string)
+}
+
+const ENGLISH_FORMATS: [&str; 4] = [
+    "%B %d, %Y %I:%M:%S %p",
+    "%d %B %Y %H:%M:%S",
+    "%B %d, %Y, %I:%M:%S %p",
+    "%B %d, %Y %H:%M:%S",
+];
+
+fn parse_added(segment: &str) -> Option<NaiveDateTime> {
+    if let Some(rest) = segment.strip_prefix("作成日") {
+        // 2019年3月3日 日曜日 22:21:33, with or without a space before the weekday
+        let rest = rest.trim_start_matches([':', '：', ' ']);
+        let date_end = rest.find('日')? + '日'.len_utf8();
+        let time = rest.rsplit(' ').next()?;
+        let value = format!("{} {time}", &rest[..date_end]);
+        return NaiveDateTime::parse_from_str(&value, "%Y年%m月%d日 %H:%M:%S").ok();
+    }
+
+    let rest = segment.strip_prefix("Added on")?.trim();
+    // Drop the weekday: "Sunday, March 3, 2019 ..." or "Sunday 3 March 2019 ..."
+    let rest = rest
+        .split_once(", ")
+        .filter(|(weekday, _)| !weekday.contains(' '))
+        .map_or_else(|| rest.split_once(' ').map_or(rest, |(_, r)| r), |(_, r)| r)
+        .trim();
+    ENGLISH_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(rest, format).ok())
+}
+
+/// Highlights only, without empty ones. When a highlight is edited Kindle appends the new
+/// version and keeps the old one, so for each book and starting location only the last
+/// highlight is kept.
+pub fn latest_highlights(clippings: Vec<Clipping>) -> Vec<Clipping> {
+    let mut kept: Vec<Clipping> = Vec::new();
+    // Index in `kept` of the highlight at each (title, author, starting location)
+    let mut positions: HashMap<(String, Option<String>, String), usize> = HashMap::new();
+    for clipping in clippings {
+        if clipping.kind != ClippingKind::Highlight || clipping.text.is_empty() {
+            continue;
+        }
+        let Some(start) = clipping.location_start() else {
+            kept.push(clipping);
+            continue;
+        };
+        let key = (
+            clipping.title.clone(),
+            clipping.author.clone(),
+            start.to_string(),
+        );
+        match positions.get(&key) {
+            Some(&i) => kept[i] = clipping,
+            None => {
+                positions.insert(key, kept.len());
+                kept.push(clipping);
+            }
+        }
+    }
+    kept
+}
+
+/// Compares phrases the way a reader would: ignoring case, width and spacing.
+pub fn phrase_key(text: &str) -> String {
+    normalize(text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Drafts for the highlights not in `seen`, which holds `(source, phrase_key)`
+/// pairs and is extended as drafts are made, so repeats within the file are dropped too.
+/// Returns the drafts and the number of duplicates.
+pub fn drafts(
+    highlights: Vec<Clipping>,
+    seen: &mut HashSet<(String, String)>,
+) -> (Vec<NewDraft>, usize) {
+    let mut drafts = Vec::new();
+    let mut duplicates = 0;
+    for clipping in highlights {
+        let source = clipping.source();
+        if !seen.insert((source.clone(), phrase_key(&clipping.text))) {
+            duplicates += 1;
+            continue;
+        }
+        drafts.push(NewDraft {
+            memo: clipping.position().map(|p| format!("Kindle: {p}")),
+            created_at: clipping
+                .added_at
+                .map(|t| t.and_utc())
+                .unwrap_or_else(Utc::now),
+            source: Some(source),
+            phrase: clipping.text,
+        });
+    }
+    (drafts, duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    const ENGLISH: &str = "\u{feff}The Remains of the Day (Kazuo Ishiguro)
+- Your Highlight on page 12 | Location 170-172 | Added on Sunday, March 3, 2019 10:21:33 PM
+
+a sense of dignity
+==========
+Moby Dick (Classic Edition) (Herman Melville)
+- Your Note on Location 88 | Added on Monday, 4 March 2019 09:05:00
+
+my own note
+==========
+Moby Dick (Classic Edition) (Herman Melville)
+- Your Bookmark on Location 90 | Added on Monday, 4 March 2019 09:06:00
+
+
+==========
+";
+
+    const JAPANESE: &str = "吾輩は猫である (夏目 漱石)\r
+- 12ページ|位置No. 170-172のハイライト |作成日: 2019年3月3日日曜日 22:21:33\r
+\r
+吾輩は猫である。名前はまだ無い。\r
+==========\r
+こころ (夏目漱石)\r
+- 位置No. 55のハイライト |作成日: 2020年1月15日 水曜日 8:01:02\r
+\r
+先生と私\r
+==========\r
+";
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(y, m, d).and_then(|date| date.and_hms_opt(h, min, s))
+    }
+
+    #[test]
+    fn parses_english_clippings() {
+        let parsed = parse(ENGLISH);
+        assert_eq!(parsed.malformed, 0);
+        assert_eq!(parsed.clippings.len(), 3);
+
+        let first = &parsed.clippings[0];
+        assert_eq!(first.title, "The Remains of the Day");
+        assert_eq!(first.author.as_deref(), Some("Kazuo Ishiguro"));
+        assert_eq!(first.kind, ClippingKind::Highlight);
+        assert_eq!(first.page.as_deref(), Some("12"));
+        assert_eq!(first.location.as_deref(), Some("170-172"));
+        assert_eq!(first.added_at, at(2019, 3, 3, 22, 21, 33));
+        assert_eq!(first.text, "a sense of dignity");
+        assert_eq!(
+            first.position().as_deref(),
+            Some("page 12, location 170-172")
+        );
+
+        let note = &parsed.clippings[1];
+        assert_eq!(note.title, "Moby Dick (Classic Edition)");
+        assert_eq!(
+            note.source(),
+            "Moby Dick (Classic Edition) (Herman Melville)"
+        );
+        assert_eq!(note.kind, ClippingKind::Note);
+        assert_eq!(note.added_at, at(2019, 3, 4, 9, 5, 0));
+
+        let bookmark = &parsed.clippings[2];
+        assert_eq!(bookmark.kind, ClippingKind::Bookmark);
+        assert_eq!(bookmark.text, "");
+    }
+
+    #[test]
+    fn parses_japanese_clippings() {
+        let parsed = parse(JAPANESE);
+        assert_eq!(parsed.malformed, 0);
+        let first = &parsed.clippings[0];
+        assert_eq!(first.title, "吾輩は猫である");
+        assert_eq!(first.author.as_deref(), Some("夏目 漱石"));
+        assert_eq!(first.kind, ClippingKind::Highlight);
+        assert_eq!(first.page.as_deref(), Some("12"));
+        assert_eq!(first.location.as_deref(), Some("170-172"));
+        assert_eq!(first.added_at, at(2019, 3, 3, 22, 21, 33));
+        assert_eq!(first.text, "吾輩は猫である。名前はまだ無い。");
+
+        let second = &parsed.clippings[1];
+        assert_eq!(second.page, None);
+        assert_eq!(second.location.as_deref(), Some("55"));
+        assert_eq!(second.added_at, at(2020, 1, 15, 8, 1, 2));
+    }
+
+    #[test]
+    fn older_formats_and_malformed_entries() {
+        let input = "Untitled
+- Highlight Loc. 1024-26  | Added on Tuesday, January 5, 2010, 07:08:09 AM
+
+old style
+==========
+garbage without metadata
+==========";
+        let parsed = parse(input);
+        assert_eq!(parsed.malformed, 1);
+        let clipping = &parsed.clippings[0];
+        assert_eq!(clipping.author, None);
+        assert_eq!(clipping.source(), "Untitled");
+        assert_eq!(clipping.location.as_deref(), Some("1024-26"));
+        assert_eq!(clipping.added_at, at(2010, 1, 5, 7, 8, 9));
+    }
+
+    #[test]
+    fn keeps_the_latest_version_of_an_edited_highlight() {
+        let input = "Book (A)
+- Your Highlight on Location 10-11 | Added on Sunday, March 3, 2019 10:00:00 PM
+
+short
+==========
+Book (A)
+- Your Highlight on Location 10-14 | Added on Sunday, March 3, 2019 10:01:00 PM
+
+short and extended
+==========
+Book (A)
+- Your Note on Location 14 | Added on Sunday, March 3, 2019 10:02:00 PM
+
+a note
+==========
+Other (B)
+- Your Highlight on Location 10-11 | Added on Sunday, March 3, 2019 10:03:00 PM
+
+other book
+==========";
+        let highlights = latest_highlights(parse(input).clippings);
+        let texts: Vec<&str> = highlights.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["short and extended", "other book"]);
+    }
+
+    #[test]
+    fn drafts_skip_known_and_repeated_highlights() {
+        let input = "Book (A)
+- Your Highlight on page 3 | Location 10-11 | Added on Sunday, March 3, 2019 10:00:00 PM
+
+Known phrase
+==========
+Book (A)
+- Your Highlight on Location 20 | Added on Sunday, March 3, 2019 10:01:00 PM
+
+new  phrase
+==========
+Book (A)
+- Your Highlight on Location 30 | Added on Sunday, March 3, 2019 10:02:00 PM
+
+New phrase
+==========";
+        let mut seen = HashSet::from([("Book (A)".to_string(), phrase_key("known phrase"))]);
+        let (drafts, duplicates) = drafts(latest_highlights(parse(input).clippings), &mut seen);
+        assert_eq!(duplicates, 2);
+        assert_eq!(drafts.len(), 1);
+        let draft = &drafts[0];
+        assert_eq!(draft.phrase, "new  phrase");
+        assert_eq!(draft.source.as_deref(), Some("Book (A)"));
+        assert_eq!(draft.memo.as_deref(), Some("Kindle: location 20"));
+        assert_eq!(
+            draft.created_at.naive_utc(),
+            at(2019, 3, 3, 22, 1, 0).unwrap()
+        );
+    }
+}
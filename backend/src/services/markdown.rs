@@ -0,0 +1,289 @@
+//! Markdown vault export: phrases as Markdown files with YAML front matter, zipped under
+//! one folder so the archive can be unpacked straight into an Obsidian vault.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write as _;
+use std::io::{Cursor, Write};
+
+use chrono::{DateTime, Utc};
+use zip::write::SimpleFileOptions;
+
+use crate::error::AppError;
+use crate::models::phrase::{MarkdownGroup, Phrase};
+
+const FOLDER: &str = "eemee";
+const NO_SOURCE: &str = "No source";
+/// Longest file stem, in chars; phrases can be whole sentences.
+const MAX_STEM_CHARS: usize = 80;
+
+/// A YAML scalar. JSON strings are valid double-quoted YAML, escapes included.
+fn yaml_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+/// Obsidian tags cannot contain whitespace.
+fn vault_tag(tag: &str) -> String {
+    tag.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+fn push_tags<'a>(out: &mut String, tags: impl IntoIterator<Item = &'a String>) {
+    let tags: Vec<String> = tags.into_iter().map(|t| vault_tag(t)).collect();
+    if tags.is_empty() {
+        out.push_str("tags: []\n");
+        return;
+    }
+    out.push_str("tags:\n");
+    for tag in tags {
+        let _ = writeln!(out, "  - {}", yaml_string(&tag));
+    }
+}
+
+fn push_date(out: &mut String, key: &str, date: DateTime<Utc>) {
+    let _ = writeln!(out, "{key}: {}", date.to_rfc3339());
+}
+
+/// Meanings as a list, then the memo as free text.
+fn push_body(out: &mut String, phrase: &Phrase) {
+    for meaning in &phrase.meanings {
+        let _ = writeln!(out, "- {}", meaning.replace('\n', " "));
+    }
+    if let Some(memo) = phrase.memo.as_deref().filter(|m| !m.trim().is_empty()) {
+        let _ = write!(out, "\n{}\n", memo.trim_end());
+    }
+}
+
+/// One note for a single phrase.
+pub fn phrase_note(phrase: &Phrase) -> String {
+    let mut out = String::from("---\n");
+    let _ = writeln!(out, "id: {}", phrase.id);
+    let _ = writeln!(out, "aliases:\n  - {}", yaml_string(&phrase.phrase));
+    push_tags(&mut out, &phrase.tags);
+    if let Some(source) = &phrase.source {
+        let _ = writeln!(out, "source: {}", yaml_string(source));
+    }
+    push_date(&mut out, "created", phrase.created_at);
+    push_date(&mut out, "updated", phrase.updated_at);
+    out.push_str("---\n\n");
+    let _ = writeln!(out, "# {}\n", phrase.phrase);
+    push_body(&mut out, phrase);
+    out
+}
+
+/// One note for every phrase from a source, oldest first, each under its own heading.
+pub fn source_note(source: Option<&str>, phrases: &[Phrase]) -> String {
+    let tags: BTreeSet<&String> = phrases.iter().flat_map(|p| &p.tags).collect();
+    let mut out = String::from("---\n");
+    if let Some(source) = source {
+        let _ = writeln!(out, "source: {}", yaml_string(source));
+    }
+    out.push_str("ids:\n");
+    for phrase in phrases {
+        let _ = writeln!(out, "  - {}", phrase.id);
+    }
+    push_tags(&mut out, tags);
+    if let Some(created) = phrases.iter().map(|p| p.created_at).min() {
+        push_date(&mut out, "created", created);
+    }
+    if let Some(updated) = phrases.iter().map(|p| p.updated_at).max() {
+        push_date(&mut out, "updated", updated);
+    }
+    out.push_str("---\n");
+    let _ = writeln!(out, "\n# {}", source.unwrap_or(NO_SOURCE));
+    for phrase in phrases {
+        let _ = writeln!(out, "\n## {}\n", phrase.phrase);
+        push_body(&mut out, phrase);
+    }
+    out
+}
+
+/// A file stem safe on every platform and in Obsidian links, falling back to `fallback`.
+fn file_stem(title: &str, fallback: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']' => ' ',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .take(MAX_STEM_CHARS)
+        .collect();
+    let stem = cleaned
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_start_matches('.')
+        .to_string();
+    if stem.is_empty() {
+        fallback.to_string()
+    } else {
+        stem
+    }
+}
+
+/// Builds the zip. Per-phrase notes are written as they arrive; per-source notes need
+/// every phrase of a source, so they are written by [`VaultWriter::finish`].
+pub struct VaultWriter {
+    zip: zip::ZipWriter<Cursor<Vec<u8>>>,
+    group: MarkdownGroup,
+    /// Lowercased, as most file systems ignore case.
+    names: HashSet<String>,
+    sources: BTreeMap<Option<String>, Vec<Phrase>>,
+}
+
+impl VaultWriter {
+    pub fn new(group: MarkdownGroup) -> Self {
+        VaultWriter {
+            zip: zip::ZipWriter::new(Cursor::new(Vec::new())),
+            group,
+            names: HashSet::new(),
+            sources: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, phrase: Phrase) -> Result<(), AppError> {
+        match self.group {
+            MarkdownGroup::Phrase => {
+                let stem = file_stem(&phrase.phrase, &phrase.id.to_string());
+                self.write_file(&stem, &phrase_note(&phrase))
+            }
+            MarkdownGroup::Source => {
+                self.sources
+                    .entry(phrase.source.clone())
+                    .or_default()
+                    .push(phrase);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> Result<Vec<u8>, AppError> {
+        for (source, mut phrases) in std::mem::take(&mut self.sources) {
+            phrases.sort_by_key(|p| p.created_at);
+            let stem = file_stem(source.as_deref().unwrap_or(NO_SOURCE), NO_SOURCE);
+            self.write_file(&stem, &source_note(source.as_deref(), &phrases))?;
+        }
+        let cursor = self
+            .zip
+            .finish()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        Ok(cursor.into_inner())
+    }
+
+    fn write_file(&mut self, stem: &str, contents: &str) -> Result<(), AppError> {
+        let mut name = format!("{stem}.md");
+        let mut n = 2;
+        while !self.names.insert(name.to_lowercase()) {
+            name = format!("{stem} ({n}).md");
+            n += 1;
+        }
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        self.zip
+            .start_file(format!("{FOLDER}/{name}"), options)
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        self.zip
+            .write_all(contents.as_bytes())
+            .map_err(|e| AppError::Internal(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::phrase::PhraseStatus;
+    use std::io::Read;
+    use uuid::Uuid;
+
+    fn phrase(text: &str, source: Option<&str>, created: &str) -> Phrase {
+        let created_at = DateTime::parse_from_rfc3339(created).unwrap().to_utc();
+        Phrase {
+            id: Uuid::new_v4(),
+            phrase: text.to_string(),
+            meanings: vec!["first meaning".to_string(), "second meaning".to_string()],
+            status: PhraseStatus::Ready,
+            source: source.map(str::to_string),
+            tags: vec!["set phrase".to_string()],
+            memo: Some("Seen in chapter 2.\n".to_string()),
+            created_at,
+            updated_at: created_at,
+        }
+    }
+
+    fn unzip(bytes: Vec<u8>) -> BTreeMap<String, String> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        (0..archive.len())
+            .map(|i| {
+                let mut file = archive.by_index(i).unwrap();
+                let mut contents = String::new();
+                file.read_to_string(&mut contents).unwrap();
+                (file.name().to_string(), contents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn phrase_note_layout() {
+        let p = phrase("break the \"ice\"", Some("Novel"), "2025-01-02T03:04:05Z");
+        let note = phrase_note(&p);
+        let expected = format!(
+            "---\nid: {}\naliases:\n  - \"break the \\\"ice\\\"\"\ntags:\n  - \"set_phrase\"\n\
+             source: \"Novel\"\ncreated: 2025-01-02T03:04:05+00:00\n\
+             updated: 2025-01-02T03:04:05+00:00\n---\n\n# break the \"ice\"\n\n\
+             - first meaning\n- second meaning\n\nSeen in chapter 2.\n",
+            p.id
+        );
+        assert_eq!(note, expected);
+    }
+
+    #[test]
+    fn file_stems_are_safe() {
+        assert_eq!(file_stem("a/b: c?", "x"), "a b c");
+        assert_eq!(file_stem("...hidden", "x"), "hidden");
+        assert_eq!(file_stem("///", "fallback"), "fallback");
+        assert_eq!(
+            file_stem(&"あ".repeat(200), "x").chars().count(),
+            MAX_STEM_CHARS
+        );
+    }
+
+    #[test]
+    fn one_file_per_phrase_with_unique_names() {
+        let mut writer = VaultWriter::new(MarkdownGroup::Phrase);
+        writer
+            .add(phrase("Rain", None, "2025-01-01T00:00:00Z"))
+            .unwrap();
+        writer
+            .add(phrase("rain", None, "2025-01-02T00:00:00Z"))
+            .unwrap();
+        let files = unzip(writer.finish().unwrap());
+        let names: Vec<&str> = files.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["eemee/Rain.md", "eemee/rain (2).md"]);
+    }
+
+    #[test]
+    fn one_file_per_source() {
+        let mut writer = VaultWriter::new(MarkdownGroup::Source);
+        writer
+            .add(phrase("later", Some("Novel"), "2025-02-01T00:00:00Z"))
+            .unwrap();
+        writer
+            .add(phrase("earlier", Some("Novel"), "2025-01-01T00:00:00Z"))
+            .unwrap();
+        writer
+            .add(phrase("orphan", None, "2025-01-01T00:00:00Z"))
+            .unwrap();
+        let files = unzip(writer.finish().unwrap());
+
+        let names: Vec<&str> = files.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["eemee/No source.md", "eemee/Novel.md"]);
+
+        let novel = &files["eemee/Novel.md"];
+        assert!(novel.starts_with("---\nsource: \"Novel\"\nids:\n"));
+        assert!(novel.contains("created: 2025-01-01T00:00:00+00:00\n"));
+        assert!(novel.contains("updated: 2025-02-01T00:00:00+00:00\n"));
+        let earlier = novel.find("## earlier").unwrap();
+        let later = novel.find("## later").unwrap();
+        assert!(earlier < later);
+        assert!(!files["eemee/No source.md"].contains("source:"));
+    }
+}
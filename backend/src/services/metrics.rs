@@ -0,0 +1,252 @@
+//! Prometheus metrics, served in the text format by `GET /metrics`.
+//!
+//! Counters and histograms are updated where things happen; the gauges are sampled from
+//! the pool and the database on each scrape.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, LazyLock};
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::search::SearchMode;
+use crate::services::embedding::{Embedder, Embedding};
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    embedding_duration: HistogramVec,
+    embedding_errors: IntCounterVec,
+    job_failures: IntCounterVec,
+    searches: IntCounterVec,
+    db_connections: IntGaugeVec,
+    phrases: IntGauge,
+    meanings: IntGaugeVec,
+}
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Label for requests that matched no route, so unknown paths cannot blow up cardinality.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("eemee".to_string()), None).expect("valid registry prefix");
+        let metrics = Metrics {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests by route and status"),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            http_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "HTTP request latency by route",
+                ),
+                &["method", "route"],
+            )
+            .unwrap(),
+            embedding_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "embedding_request_duration_seconds",
+                    "Latency of embedding API calls",
+                ),
+                &["provider"],
+            )
+            .unwrap(),
+            embedding_errors: IntCounterVec::new(
+                Opts::new("embedding_errors_total", "Failed embedding API calls"),
+                &["provider"],
+            )
+            .unwrap(),
+            job_failures: IntCounterVec::new(
+                Opts::new(
+                    "embedding_job_failures_total",
+                    "Failed embedding jobs, by whether they will be retried or are now dead",
+                ),
+                &["provider", "outcome"],
+            )
+            .unwrap(),
+            searches: IntCounterVec::new(
+                Opts::new("searches_total", "Searches run, by mode"),
+                &["mode"],
+            )
+            .unwrap(),
+            db_connections: IntGaugeVec::new(
+                Opts::new("db_pool_connections", "Database pool connections by state"),
+                &["state"],
+            )
+            .unwrap(),
+            phrases: IntGauge::new("phrases", "Phrases in the library").unwrap(),
+            meanings: IntGaugeVec::new(
+                Opts::new("meanings", "Meanings in the library, by embedding status"),
+                &["status"],
+            )
+            .unwrap(),
+            registry,
+        };
+
+        let collectors: [Box<dyn prometheus::core::Collector>; 9] = [
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_duration.clone()),
+            Box::new(metrics.embedding_duration.clone()),
+            Box::new(metrics.embedding_errors.clone()),
+            Box::new(metrics.job_failures.clone()),
+            Box::new(metrics.searches.clone()),
+            Box::new(metrics.db_connections.clone()),
+            Box::new(metrics.phrases.clone()),
+            Box::new(metrics.meanings.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).unwrap();
+        }
+        metrics
+    }
+
+    pub fn record_search(&self, mode: SearchMode) {
+        self.searches.with_label_values(&[mode.as_str()]).inc();
+    }
+
+    /// A failed embedding job; `dead` when it has no attempts left.
+    pub fn record_job_failure(&self, provider: &str, dead: bool) {
+        let outcome = if dead { "dead" } else { "retry" };
+        self.job_failures
+            .with_label_values(&[provider, outcome])
+            .inc();
+    }
+
+    /// Samples the gauges, then renders every metric.
+    pub async fn render(&self, pool: &PgPool) -> Result<String, AppError> {
+        let max = pool.options().get_max_connections();
+        let idle = pool.num_idle() as u32;
+        let size = pool.size();
+        for (state, value) in [("max", max), ("open", size), ("idle", idle)] {
+            self.db_connections
+                .with_label_values(&[state])
+                .set(value.into());
+        }
+        self.db_connections
+            .with_label_values(&["in_use"])
+            .set(size.saturating_sub(idle).into());
+
+        let phrases: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM phrases")
+            .fetch_one(pool)
+            .await?;
+        self.phrases.set(phrases);
+        let meanings: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT embedding_status, COUNT(*) FROM phrase_meanings GROUP BY embedding_status",
+        )
+        .fetch_all(pool)
+        .await?;
+        self.meanings.reset();
+        for status in ["pending", "ready", "failed"] {
+            let count = meanings
+                .iter()
+                .find(|(s, _)| s == status)
+                .map_or(0, |(_, n)| *n);
+            self.meanings.with_label_values(&[status]).set(count);
+        }
+
+        Ok(self.encode())
+    }
+
+    fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding cannot fail");
+        String::from_utf8(buffer).expect("text encoding is UTF-8")
+    }
+}
+
+/// Counts requests and their latency by matched route, e.g. `/api/phrases/{id}`.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE.to_string(), |p| p.as_str().to_string());
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    METRICS
+        .http_duration
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    METRICS
+        .http_requests
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+    response
+}
+
+/// Wraps an [`Embedder`] to record the latency and errors of its calls.
+pub struct InstrumentedEmbedder(pub Arc<dyn Embedder>);
+
+impl Embedder for InstrumentedEmbedder {
+    fn model(&self) -> &str {
+        self.0.model()
+    }
+
+    fn provider(&self) -> &str {
+        self.0.provider()
+    }
+
+    fn embed<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<Embedding, AppError>> + Send + 'a>> {
+        Box::pin(async move {
+            let provider = self.provider();
+            let started = Instant::now();
+            let result = self.0.embed(text).await;
+            METRICS
+                .embedding_duration
+                .with_label_values(&[provider])
+                .observe(started.elapsed().as_secs_f64());
+            if result.is_err() {
+                METRICS
+                    .embedding_errors
+                    .with_label_values(&[provider])
+                    .inc();
+            }
+            result
+        })
+    }
+
+    fn check(&self) -> Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + '_>> {
+        self.0.check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_prefixed_metrics() {
+        let metrics = Metrics::new();
+        metrics.record_search(SearchMode::Text);
+        metrics.record_search(SearchMode::Text);
+        metrics.record_job_failure("openai", true);
+
+        let text = metrics.encode();
+        assert!(text.contains("# TYPE eemee_searches_total counter"));
+        assert!(text.contains("eemee_searches_total{mode=\"text\"} 2"));
+        assert!(text.contains(
+            "eemee_embedding_job_failures_total{outcome=\"dead\",provider=\"openai\"} 1"
+        ));
+    }
+}
@@ -1,2 +1,22 @@
+pub mod anki;
+pub mod backup;
 pub mod db;
 pub mod embedding;
+pub mod export;
+pub mod health;
+pub mod import;
+pub mod jobs;
+pub mod kindle;
+pub mod markdown;
+pub mod metrics;
+pub mod query;
+pub mod rate_limit;
+pub mod rerank;
+pub mod tags;
+pub mod text;
+pub mod tokens;
+pub mod trash;
+pub mod usage;
+pub mod users;
+pub mod vector_index;
+pub mod vectors;
@@ -0,0 +1,600 @@
+//! Parser for the text search query language.
+//!
+//! ```text
+//! rain -storm tag:novel source:murakami "exact phrase" (memo:train OR meaning:journey)
+//! ```
+//!
+//! Terms separated by spaces must all match; `OR` between terms matches either side and binds
+//! looser than the implicit AND. `-` excludes a term or group. A bare word matches any field
+//! and tolerates typos, a quoted phrase must occur as written. `phrase:`, `meaning:`, `tag:`,
+//! `source:` and `memo:` restrict a term to one field; `tag:` matches whole tags only.
+
+use crate::error::{AppError, BadInput};
+
+/// Upper bound on terms per query, which keeps the generated SQL small.
+const MAX_TERMS: usize = 16;
+/// Upper bound on parenthesis nesting.
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Phrase,
+    Meaning,
+    Tag,
+    Source,
+    Memo,
+}
+
+impl Field {
+    /// Name used as the query prefix and as the highlight field.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Field::Phrase => "phrase",
+            Field::Meaning => "meaning",
+            Field::Tag => "tag",
+            Field::Source => "source",
+            Field::Memo => "memo",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "phrase" => Some(Field::Phrase),
+            "meaning" => Some(Field::Meaning),
+            "tag" => Some(Field::Tag),
+            "source" => Some(Field::Source),
+            "memo" => Some(Field::Memo),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    /// `None` searches every field.
+    pub field: Option<Field>,
+    pub value: String,
+    /// Quoted: substring match only, no typo tolerance.
+    pub exact: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Term(Term),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+impl Query {
+    /// Terms that contribute to a match, i.e. not under a `-`. Used for highlighting.
+    pub fn positive_terms(&self) -> Vec<&Term> {
+        let mut terms = Vec::new();
+        self.collect_positive(&mut terms);
+        terms
+    }
+
+    /// Whether every match matches at least one positive term, so that the phrases matching
+    /// some positive term are a superset of the results. False for `-storm` alone, or for an
+    /// empty query, which match phrases with no positive term at all.
+    pub fn requires_match(&self) -> bool {
+        match self {
+            Query::Term(_) => true,
+            Query::Not(_) => false,
+            Query::And(children) => children.iter().any(Query::requires_match),
+            Query::Or(children) => {
+                !children.is_empty() && children.iter().all(Query::requires_match)
+            }
+        }
+    }
+
+    fn collect_positive<'a>(&'a self, terms: &mut Vec<&'a Term>) {
+        match self {
+            Query::Term(term) => terms.push(term),
+            Query::Not(_) => {}
+            Query::And(children) | Query::Or(children) => {
+                for child in children {
+                    child.collect_positive(terms);
+                }
+            }
+        }
+    }
+}
+
+/// Parse failure with the char range of the offending token, so the UI can underline it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>, start: usize, end: usize) -> Self {
+        QueryError {
+            message: message.into(),
+            start,
+            end,
+        }
+    }
+}
+
+impl From<QueryError> for AppError {
+    fn from(err: QueryError) -> Self {
+        AppError::BadRequest(BadInput::at(err.message, err.start, err.end))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Term(Term),
+    Minus,
+    Or,
+    LParen,
+    RParen,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+fn is_word_end(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '"')
+}
+
+/// Reads a quoted string starting at the opening quote; returns the contents and the index
+/// just past the closing quote.
+fn read_quoted(chars: &[char], open: usize) -> Result<(String, usize), QueryError> {
+    match chars[open + 1..].iter().position(|&c| c == '"') {
+        Some(len) => {
+            let close = open + 1 + len;
+            Ok((chars[open + 1..close].iter().collect(), close + 1))
+        }
+        None => Err(QueryError::new("Unterminated quote", open, chars.len())),
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        let kind = match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            '"' => {
+                let (value, next) = read_quoted(&chars, i)?;
+                i = next;
+                TokenKind::Term(Term {
+                    field: None,
+                    value,
+                    exact: true,
+                })
+            }
+            '-' => {
+                if chars
+                    .get(i + 1)
+                    .is_none_or(|&c| c.is_whitespace() || c == ')')
+                {
+                    return Err(QueryError::new("Nothing to exclude after '-'", i, i + 1));
+                }
+                i += 1;
+                TokenKind::Minus
+            }
+            _ => {
+                while i < chars.len() && !is_word_end(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                if word == "OR" {
+                    TokenKind::Or
+                } else {
+                    let (term, next) = read_term(&chars, start, i, word)?;
+                    i = next;
+                    TokenKind::Term(term)
+                }
+            }
+        };
+        tokens.push(Token {
+            kind,
+            start,
+            end: i,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Turns the word `chars[start..end]` into a term, resolving a `field:` prefix. A prefixed
+/// term may continue with a quoted value, so the index where lexing resumes is returned.
+fn read_term(
+    chars: &[char],
+    start: usize,
+    end: usize,
+    word: String,
+) -> Result<(Term, usize), QueryError> {
+    let Some((prefix, rest)) = word.split_once(':').filter(|(prefix, _)| {
+        !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_alphabetic())
+    }) else {
+        let term = Term {
+            field: None,
+            value: word,
+            exact: false,
+        };
+        return Ok((term, end));
+    };
+
+    let prefix_end = start + prefix.chars().count();
+    let field = Field::parse(&prefix.to_ascii_lowercase()).ok_or_else(|| {
+        QueryError::new(
+            format!("Unknown field '{prefix}'; expected phrase, meaning, tag, source or memo"),
+            start,
+            prefix_end,
+        )
+    })?;
+
+    if !rest.is_empty() {
+        let term = Term {
+            field: Some(field),
+            value: rest.to_string(),
+            exact: false,
+        };
+        return Ok((term, end));
+    }
+    if chars.get(end) == Some(&'"') {
+        let (value, next) = read_quoted(chars, end)?;
+        let term = Term {
+            field: Some(field),
+            value,
+            exact: true,
+        };
+        return Ok((term, next));
+    }
+    Err(QueryError::new(
+        format!("Missing value after '{prefix}:'"),
+        start,
+        end,
+    ))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    terms: usize,
+    /// Char length of the input, where errors about a missing token point.
+    len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self, depth: usize) -> Result<Query, QueryError> {
+        let mut branches = vec![self.parse_and(depth)?];
+        while let Some(token) = self.peek() {
+            if token.kind != TokenKind::Or {
+                break;
+            }
+            self.pos += 1;
+            branches.push(self.parse_and(depth)?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.remove(0)
+        } else {
+            Query::Or(branches)
+        })
+    }
+
+    fn parse_and(&mut self, depth: usize) -> Result<Query, QueryError> {
+        let mut terms = Vec::new();
+        while let Some(token) = self.peek() {
+            match token.kind {
+                TokenKind::Or if terms.is_empty() => {
+                    return Err(QueryError::new(
+                        "OR must be between two terms",
+                        token.start,
+                        token.end,
+                    ));
+                }
+                TokenKind::Or | TokenKind::RParen => break,
+                _ => terms.push(self.parse_unary(depth)?),
+            }
+        }
+        match terms.len() {
+            0 => Err(QueryError::new(
+                "Expected a term",
+                self.previous_end(),
+                self.previous_end(),
+            )),
+            1 => Ok(terms.remove(0)),
+            _ => Ok(Query::And(terms)),
+        }
+    }
+
+    fn previous_end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or(self.len, |token| token.end)
+    }
+
+    fn parse_unary(&mut self, depth: usize) -> Result<Query, QueryError> {
+        if self
+            .peek()
+            .is_some_and(|token| token.kind == TokenKind::Minus)
+        {
+            self.pos += 1;
+            return Ok(Query::Not(Box::new(self.parse_primary(depth)?)));
+        }
+        self.parse_primary(depth)
+    }
+
+    fn parse_primary(&mut self, depth: usize) -> Result<Query, QueryError> {
+        let Some(token) = self.next() else {
+            return Err(QueryError::new("Expected a term", self.len, self.len));
+        };
+        match token.kind {
+            TokenKind::Term(term) => {
+                self.terms += 1;
+                if self.terms > MAX_TERMS {
+                    return Err(QueryError::new(
+                        format!("Too many terms (at most {MAX_TERMS})"),
+                        token.start,
+                        token.end,
+                    ));
+                }
+                Ok(Query::Term(term))
+            }
+            TokenKind::LParen => {
+                if depth >= MAX_DEPTH {
+                    return Err(QueryError::new(
+                        "Parentheses nested too deeply",
+                        token.start,
+                        token.end,
+                    ));
+                }
+                if self
+                    .peek()
+                    .is_some_and(|next| next.kind == TokenKind::RParen)
+                {
+                    return Err(QueryError::new("Empty group", token.start, token.end + 1));
+                }
+                let inner = self.parse_or(depth + 1)?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => Ok(inner),
+                    _ => Err(QueryError::new(
+                        "Unclosed parenthesis",
+                        token.start,
+                        token.end,
+                    )),
+                }
+            }
+            TokenKind::Minus => Err(QueryError::new("Unexpected '-'", token.start, token.end)),
+            TokenKind::Or => Err(QueryError::new(
+                "OR must be between two terms",
+                token.start,
+                token.end,
+            )),
+            TokenKind::RParen => Err(QueryError::new("Unexpected ')'", token.start, token.end)),
+        }
+    }
+}
+
+/// Parses a search query. A blank query parses to an empty [`Query::And`], which matches
+/// everything. Error positions are char (Unicode scalar value) offsets into `input`.
+pub fn parse(input: &str) -> Result<Query, QueryError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Ok(Query::And(Vec::new()));
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        terms: 0,
+        len: input.chars().count(),
+    };
+    let query = parser.parse_or(0)?;
+    if let Some(token) = parser.peek() {
+        let message = match token.kind {
+            TokenKind::RParen => "Unexpected ')'",
+            _ => "Unexpected token",
+        };
+        return Err(QueryError::new(message, token.start, token.end));
+    }
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorPosition;
+
+    fn word(value: &str) -> Query {
+        Query::Term(Term {
+            field: None,
+            value: value.to_string(),
+            exact: false,
+        })
+    }
+
+    fn scoped(field: Field, value: &str, exact: bool) -> Query {
+        Query::Term(Term {
+            field: Some(field),
+            value: value.to_string(),
+            exact,
+        })
+    }
+
+    fn error_span(input: &str) -> (usize, usize) {
+        let err = parse(input).unwrap_err();
+        (err.start, err.end)
+    }
+
+    #[test]
+    fn blank_query_matches_everything() {
+        assert_eq!(parse("   ").unwrap(), Query::And(vec![]));
+    }
+
+    #[test]
+    fn implicit_and_of_words() {
+        assert_eq!(
+            parse("rain  window").unwrap(),
+            Query::And(vec![word("rain"), word("window")])
+        );
+        assert_eq!(parse("rain").unwrap(), word("rain"));
+    }
+
+    #[test]
+    fn field_prefixes_and_quotes() {
+        let query = parse(r#"tag:novel source:"Haruki Murakami" "exact phrase""#).unwrap();
+        assert_eq!(
+            query,
+            Query::And(vec![
+                scoped(Field::Tag, "novel", false),
+                scoped(Field::Source, "Haruki Murakami", true),
+                Query::Term(Term {
+                    field: None,
+                    value: "exact phrase".to_string(),
+                    exact: true,
+                }),
+            ])
+        );
+        assert_eq!(
+            parse("MEMO:train").unwrap(),
+            scoped(Field::Memo, "train", false)
+        );
+    }
+
+    #[test]
+    fn or_binds_looser_than_and() {
+        assert_eq!(
+            parse("a b OR c").unwrap(),
+            Query::Or(vec![Query::And(vec![word("a"), word("b")]), word("c")])
+        );
+        assert_eq!(
+            parse("a (b OR c)").unwrap(),
+            Query::And(vec![word("a"), Query::Or(vec![word("b"), word("c")])])
+        );
+    }
+
+    #[test]
+    fn minus_excludes_terms_and_groups() {
+        assert_eq!(
+            parse("rain -storm").unwrap(),
+            Query::And(vec![word("rain"), Query::Not(Box::new(word("storm")))])
+        );
+        assert_eq!(
+            parse("-(a OR tag:b)").unwrap(),
+            Query::Not(Box::new(Query::Or(vec![
+                word("a"),
+                scoped(Field::Tag, "b", false)
+            ])))
+        );
+        // Only a leading minus excludes
+        assert_eq!(parse("well-known").unwrap(), word("well-known"));
+    }
+
+    #[test]
+    fn words_with_non_field_colons_are_plain() {
+        assert_eq!(parse("10:30").unwrap(), word("10:30"));
+        assert_eq!(parse(":)").unwrap_err().message, "Unexpected ')'");
+    }
+
+    #[test]
+    fn lowercase_or_is_a_word() {
+        assert_eq!(
+            parse("this or that").unwrap(),
+            Query::And(vec![word("this"), word("or"), word("that")])
+        );
+    }
+
+    #[test]
+    fn positive_terms_skip_exclusions() {
+        let query = parse("rain -storm (tag:novel OR memo:x)").unwrap();
+        let values: Vec<&str> = query
+            .positive_terms()
+            .iter()
+            .map(|t| t.value.as_str())
+            .collect();
+        assert_eq!(values, vec!["rain", "novel", "x"]);
+    }
+
+    #[test]
+    fn requires_match_unless_exclusions_can_match_alone() {
+        assert!(parse("rain").unwrap().requires_match());
+        assert!(parse("rain -storm").unwrap().requires_match());
+        assert!(parse("a (b OR c)").unwrap().requires_match());
+        assert!(!parse("-storm").unwrap().requires_match());
+        assert!(!parse("rain OR -storm").unwrap().requires_match());
+        assert!(!parse("").unwrap().requires_match());
+    }
+
+    #[test]
+    fn errors_point_at_offending_token() {
+        assert_eq!(error_span(r#"rain "unclosed"#), (5, 14));
+        assert_eq!(error_span("rain colour:red"), (5, 11));
+        assert_eq!(error_span("rain tag: x"), (5, 9));
+        assert_eq!(error_span("OR rain"), (0, 2));
+        assert_eq!(error_span("rain OR"), (7, 7));
+        assert_eq!(error_span("rain -"), (5, 6));
+        assert_eq!(error_span("(rain"), (0, 1));
+        assert_eq!(error_span("rain)"), (4, 5));
+        assert_eq!(error_span("a ()"), (2, 4));
+    }
+
+    #[test]
+    fn error_positions_count_chars() {
+        // Each kana is one position even though it is three UTF-8 bytes
+        assert_eq!(error_span("かふか foo:x"), (4, 7));
+        // Only ASCII prefixes are treated as fields
+        assert_eq!(parse("色:赤").unwrap(), word("色:赤"));
+    }
+
+    #[test]
+    fn limits_terms_and_nesting() {
+        let many = vec!["w"; MAX_TERMS + 1].join(" ");
+        assert_eq!(error_span(&many), (2 * MAX_TERMS, 2 * MAX_TERMS + 1));
+
+        let deep = format!(
+            "{}a{}",
+            "(".repeat(MAX_DEPTH + 1),
+            ")".repeat(MAX_DEPTH + 1)
+        );
+        assert_eq!(error_span(&deep), (MAX_DEPTH, MAX_DEPTH + 1));
+    }
+
+    #[test]
+    fn query_error_becomes_positioned_bad_request() {
+        let err = AppError::from(parse("tag:").unwrap_err());
+        let AppError::BadRequest(input) = err else {
+            panic!("expected a bad request, got {err:?}");
+        };
+        assert_eq!(input.position, Some(ErrorPosition { start: 0, end: 4 }));
+    }
+}
@@ -0,0 +1,199 @@
+//! Rate limits on the routes that spend embedding credits: a token bucket per user and
+//! one shared by everyone, so neither a stuck client nor a script can run up the bill.
+//! Buckets live in memory, so the limits are per process and reset on restart.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Allows `per_minute` requests a minute on average, and bursts of as many.
+#[derive(Debug, Clone, Copy)]
+struct Rate {
+    per_minute: u32,
+}
+
+impl Rate {
+    /// `None` for 0, which means no limit.
+    fn new(per_minute: u32) -> Option<Self> {
+        (per_minute > 0).then_some(Rate { per_minute })
+    }
+
+    fn capacity(self) -> f64 {
+        f64::from(self.per_minute)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(rate: Rate, now: Instant) -> Self {
+        Bucket {
+            tokens: rate.capacity(),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, rate: Rate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate.capacity() / 60.0).min(rate.capacity());
+        self.updated = now;
+    }
+
+    /// How long until a request fits; zero if one does now.
+    fn wait(&self, rate: Rate) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) * 60.0 / rate.capacity())
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    global: Option<Bucket>,
+    users: HashMap<String, Bucket>,
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_user: Option<Rate>,
+    global: Option<Rate>,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// Limits in requests per minute; 0 turns a limit off.
+    pub fn new(per_user_per_minute: u32, global_per_minute: u32) -> Self {
+        RateLimiter {
+            per_user: Rate::new(per_user_per_minute),
+            global: Rate::new(global_per_minute),
+            buckets: Mutex::new(Buckets::default()),
+        }
+    }
+
+    /// Takes a request from `user`'s bucket and the global one, or from neither if either
+    /// is empty, returning how long to wait before retrying.
+    pub fn check(&self, user: Option<&str>) -> Result<(), Duration> {
+        self.check_at(user, Instant::now())
+    }
+
+    fn check_at(&self, user: Option<&str>, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let Buckets { global, users } = &mut *buckets;
+
+        let mut limited: Vec<(&mut Bucket, Rate)> = Vec::with_capacity(2);
+        if let Some(rate) = self.global {
+            limited.push((global.get_or_insert_with(|| Bucket::full(rate, now)), rate));
+        }
+        if let (Some(rate), Some(user)) = (self.per_user, user) {
+            let bucket = users
+                .entry(user.to_string())
+                .or_insert_with(|| Bucket::full(rate, now));
+            limited.push((bucket, rate));
+        }
+
+        let mut wait = Duration::ZERO;
+        for (bucket, rate) in &mut limited {
+            bucket.refill(*rate, now);
+            wait = wait.max(bucket.wait(*rate));
+        }
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        for (bucket, _) in limited {
+            bucket.tokens -= 1.0;
+        }
+        Ok(())
+    }
+}
+
+/// Middleware for the routes that embed text, inside the auth check so the request
+/// names the user.
+pub async fn limit(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let email = request
+        .extensions()
+        .get::<AuthenticatedUser>()
+        .map(|user| user.email.as_str());
+    state
+        .rate_limiter
+        .check(email)
+        .map_err(|retry_after| AppError::RateLimited { retry_after })?;
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_a_burst_then_refills_over_time() {
+        let limiter = RateLimiter::new(3, 0);
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(Some("a@example.com"), start), Ok(()));
+        }
+        let wait = limiter.check_at(Some("a@example.com"), start).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(20));
+
+        let later = start + Duration::from_secs(20);
+        assert_eq!(limiter.check_at(Some("a@example.com"), later), Ok(()));
+        assert!(limiter.check_at(Some("a@example.com"), later).is_err());
+    }
+
+    #[test]
+    fn users_have_separate_buckets() {
+        let limiter = RateLimiter::new(1, 0);
+        let now = Instant::now();
+        assert!(limiter.check_at(Some("a@example.com"), now).is_ok());
+        assert!(limiter.check_at(Some("a@example.com"), now).is_err());
+        assert!(limiter.check_at(Some("b@example.com"), now).is_ok());
+    }
+
+    #[test]
+    fn global_limit_applies_across_users() {
+        let limiter = RateLimiter::new(10, 2);
+        let now = Instant::now();
+        assert!(limiter.check_at(Some("a@example.com"), now).is_ok());
+        assert!(limiter.check_at(Some("b@example.com"), now).is_ok());
+        assert_eq!(
+            limiter.check_at(Some("c@example.com"), now),
+            Err(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn refused_requests_take_nothing() {
+        let limiter = RateLimiter::new(1, 2);
+        let now = Instant::now();
+        assert!(limiter.check_at(Some("a@example.com"), now).is_ok());
+        // Refused by the user's bucket, so the global one keeps its token for b
+        assert!(limiter.check_at(Some("a@example.com"), now).is_err());
+        assert!(limiter.check_at(Some("b@example.com"), now).is_ok());
+    }
+
+    #[test]
+    fn zero_means_unlimited() {
+        let limiter = RateLimiter::new(0, 0);
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert!(limiter.check_at(Some("a@example.com"), now).is_ok());
+        }
+    }
+}
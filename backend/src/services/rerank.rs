@@ -0,0 +1,114 @@
+//! Reranking of semantic search results for diversity, so one sense of a query does not
+//! crowd out the others.
+
+/// Cosine similarity of two vectors; 0 when either has no magnitude.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    let (mut dot, mut norm_a, mut norm_b) = (0.0_f64, 0.0_f64, 0.0_f64);
+    for (&x, &y) in a.iter().zip(b) {
+        let (x, y) = (f64::from(x), f64::from(y));
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a <= f64::EPSILON || norm_b <= f64::EPSILON {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Maximal marginal relevance.
+///
+/// Greedily picks up to `k` candidates, each maximising
+/// `(1 - diversity) * relevance - diversity * (max similarity to the candidates picked so far)`,
+/// so near-duplicates of an earlier pick sink below results covering other senses. With
+/// `diversity` 0 this is plain relevance order, with 1 only novelty counts after the first
+/// pick. `relevance[i]` is candidate `i`'s similarity to the query; unusable (NaN)
+/// relevance counts as 0. Returns candidate indices in pick order; ties keep input order.
+pub fn mmr(relevance: &[f64], vectors: &[&[f32]], k: usize, diversity: f64) -> Vec<usize> {
+    let n = relevance.len().min(vectors.len());
+    let relevance: Vec<f64> = relevance[..n]
+        .iter()
+        .map(|&r| if r.is_finite() { r } else { 0.0 })
+        .collect();
+
+    let mut picked = Vec::with_capacity(k.min(n));
+    let mut remaining = vec![true; n];
+    // Highest similarity of each candidate to any picked one
+    let mut redundancy = vec![f64::NEG_INFINITY; n];
+
+    while picked.len() < k.min(n) {
+        let mut best: Option<(usize, f64)> = None;
+        for i in (0..n).filter(|&i| remaining[i]) {
+            let penalty = if picked.is_empty() {
+                0.0
+            } else {
+                redundancy[i]
+            };
+            let score = (1.0 - diversity) * relevance[i] - diversity * penalty;
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((i, score));
+            }
+        }
+        let Some((choice, _)) = best else { break };
+
+        remaining[choice] = false;
+        picked.push(choice);
+        for i in (0..n).filter(|&i| remaining[i]) {
+            redundancy[i] = redundancy[i].max(cosine_similarity(vectors[i], vectors[choice]));
+        }
+    }
+
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cosine_similarity_basics() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-9);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-9);
+        assert!((cosine_similarity(&[1.0, 0.0], &[-1.0, 0.0]) + 1.0).abs() < 1e-9);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    /// Two near-copies of one sense (0, 1) and a less relevant second sense (2).
+    fn near_copies() -> (Vec<f64>, Vec<Vec<f32>>) {
+        let relevance = vec![0.95, 0.94, 0.80];
+        let vectors = vec![vec![1.0, 0.01], vec![1.0, 0.02], vec![0.0, 1.0]];
+        (relevance, vectors)
+    }
+
+    #[test]
+    fn zero_diversity_keeps_relevance_order() {
+        let (relevance, vectors) = near_copies();
+        let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+        assert_eq!(mmr(&relevance, &refs, 3, 0.0), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn diversity_promotes_other_senses() {
+        let (relevance, vectors) = near_copies();
+        let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+        assert_eq!(mmr(&relevance, &refs, 2, 0.5), vec![0, 2]);
+        assert_eq!(mmr(&relevance, &refs, 3, 0.5), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn k_larger_than_candidates() {
+        let (relevance, vectors) = near_copies();
+        let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+        assert_eq!(mmr(&relevance, &refs, 10, 0.3).len(), 3);
+        assert!(mmr(&[], &[], 5, 0.3).is_empty());
+    }
+
+    #[test]
+    fn nan_relevance_counts_as_zero() {
+        let vectors = [vec![1.0_f32, 0.0], vec![0.0, 1.0]];
+        let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+        assert_eq!(mmr(&[f64::NAN, 0.5], &refs, 2, 0.5), vec![1, 0]);
+        // All unusable: input order
+        assert_eq!(mmr(&[f64::NAN, f64::NAN], &refs, 2, 0.5), vec![0, 1]);
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use crate::models::tag::{TagNeighbour, TagSuggestion};
+
+/// Ranks the tags of nearby phrases.
+///
+/// Each neighbour votes for its tags with its similarity, so a tag scores high when it
+/// is both common among the neighbours and carried by the closest ones. Tags in
+/// `exclude` (already on the phrase) are skipped. When no neighbour has a usable
+/// similarity, every neighbour votes equally.
+pub fn rank_tags(
+    neighbours: &[TagNeighbour],
+    exclude: &[String],
+    limit: usize,
+) -> Vec<TagSuggestion> {
+    let weight = |n: &TagNeighbour| {
+        if n.similarity.is_finite() {
+            n.similarity.max(0.0)
+        } else {
+            0.0
+        }
+    };
+    let total: f64 = neighbours.iter().map(weight).sum();
+    let uniform = total <= f64::EPSILON;
+
+    let mut votes: HashMap<&str, (f64, usize)> = HashMap::new();
+    for neighbour in neighbours {
+        let vote = if uniform { 1.0 } else { weight(neighbour) };
+        for tag in &neighbour.tags {
+            if exclude.contains(tag) {
+                continue;
+            }
+            let entry = votes.entry(tag.as_str()).or_default();
+            entry.0 += vote;
+            entry.1 += 1;
+        }
+    }
+
+    let denominator = if uniform {
+        neighbours.len() as f64
+    } else {
+        total
+    };
+    let mut suggestions: Vec<TagSuggestion> = votes
+        .into_iter()
+        .map(|(tag, (vote, count))| TagSuggestion {
+            tag: tag.to_string(),
+            score: vote / denominator,
+            count,
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.count.cmp(&a.count))
+            .then_with(|| a.tag.cmp(&b.tag))
+    });
+    suggestions.truncate(limit);
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn neighbour(similarity: f64, tags: &[&str]) -> TagNeighbour {
+        TagNeighbour {
+            id: Uuid::new_v4(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            similarity,
+        }
+    }
+
+    #[test]
+    fn closer_neighbours_weigh_more() {
+        let neighbours = vec![
+            neighbour(0.9, &["weather"]),
+            neighbour(0.2, &["city"]),
+            neighbour(0.2, &["city"]),
+        ];
+        let ranked = rank_tags(&neighbours, &[], 10);
+        assert_eq!(ranked[0].tag, "weather");
+        assert_eq!(ranked[0].count, 1);
+        assert_eq!(ranked[1].tag, "city");
+        assert_eq!(ranked[1].count, 2);
+        assert!((ranked[0].score - 0.9 / 1.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn frequent_tags_win_at_equal_similarity() {
+        let neighbours = vec![
+            neighbour(0.5, &["novel", "rain"]),
+            neighbour(0.5, &["novel"]),
+        ];
+        let ranked = rank_tags(&neighbours, &[], 10);
+        assert_eq!(ranked[0].tag, "novel");
+        assert!((ranked[0].score - 1.0).abs() < 1e-9);
+        assert!((ranked[1].score - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn excluded_tags_and_limit() {
+        let neighbours = vec![neighbour(0.8, &["novel", "rain", "night"])];
+        let ranked = rank_tags(&neighbours, &["novel".to_string()], 1);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].tag, "night"); // ties broken alphabetically
+    }
+
+    #[test]
+    fn unusable_similarities_fall_back_to_frequency() {
+        let neighbours = vec![
+            neighbour(f64::NAN, &["a"]),
+            neighbour(f64::NAN, &["a", "b"]),
+            neighbour(-0.3, &["b"]),
+            neighbour(0.0, &["a"]),
+        ];
+        let ranked = rank_tags(&neighbours, &[], 10);
+        assert_eq!(ranked[0].tag, "a");
+        assert!((ranked[0].score - 0.75).abs() < 1e-9);
+        assert!((ranked[1].score - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_neighbours_no_suggestions() {
+        assert!(rank_tags(&[], &[], 5).is_empty());
+    }
+}
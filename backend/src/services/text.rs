@@ -0,0 +1,243 @@
+//! Text normalization and match highlighting for text search.
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::canonical_combining_class;
+
+use crate::models::phrase::{Highlight, MatchRange, Phrase};
+use crate::services::query::{Field, Term};
+
+/// Chars of context kept on each side of the first match in a snippet.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Folds width (NFKC), katakana to hiragana and case, so that `ｶﾌｶ`, `カフカ` and `かふか`
+/// compare equal. Mirrors the `eemee_normalize` SQL function used by the trigram indexes,
+/// which lowercases with the ICU root collation so both apply the Unicode case mapping.
+pub fn normalize(text: &str) -> String {
+    normalize_with_offsets(text).0.into_iter().collect()
+}
+
+fn fold(c: char) -> impl Iterator<Item = char> {
+    let c = match c {
+        // ァ..ヶ sit exactly 0x60 above ぁ..ゖ
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    };
+    c.to_lowercase()
+}
+
+/// Marks that NFKC may merge into the preceding char, including the half-width (semi-)voiced
+/// sound marks of half-width katakana, which are not combining characters themselves.
+fn is_mark(c: char) -> bool {
+    canonical_combining_class(c) != 0 || matches!(c, '\u{FF9E}' | '\u{FF9F}')
+}
+
+/// Normalized chars of `text`, each paired with the range of original char indices it came from.
+fn normalize_with_offsets(text: &str) -> (Vec<char>, Vec<(usize, usize)>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut normalized = Vec::with_capacity(chars.len());
+    let mut offsets = Vec::with_capacity(chars.len());
+
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = start + 1;
+        while end < chars.len() && is_mark(chars[end]) {
+            end += 1;
+        }
+        let cluster: String = chars[start..end].iter().collect();
+        for c in cluster.nfkc().flat_map(fold) {
+            normalized.push(c);
+            offsets.push((start, end));
+        }
+        start = end;
+    }
+
+    (normalized, offsets)
+}
+
+/// Occurrences of `needle` in `haystack`, as ranges of original char indices.
+fn find_ranges(haystack: &[char], offsets: &[(usize, usize)], needle: &str) -> Vec<(usize, usize)> {
+    let needle: Vec<char> = normalize(needle.trim()).chars().collect();
+    let mut ranges = Vec::new();
+    if needle.is_empty() {
+        return ranges;
+    }
+
+    let mut i = 0;
+    while i + needle.len() <= haystack.len() {
+        if haystack[i..i + needle.len()] == needle[..] {
+            ranges.push((offsets[i].0, offsets[i + needle.len() - 1].1));
+            i += needle.len();
+        } else {
+            i += 1;
+        }
+    }
+    ranges
+}
+
+/// Finds every occurrence of any of `needles` in `text`, comparing normalized forms, and
+/// returns a snippet around the first one. `None` when no needle occurs as a substring
+/// (for example a trigram-only, typo-tolerant match).
+pub fn highlight(field: &'static str, text: &str, needles: &[&str]) -> Option<Highlight> {
+    let (haystack, offsets) = normalize_with_offsets(text);
+    let mut ranges: Vec<(usize, usize)> = needles
+        .iter()
+        .flat_map(|needle| find_ranges(&haystack, &offsets, needle))
+        .collect();
+    ranges.sort_unstable();
+
+    // Merge overlapping matches of different needles
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (s, e) in ranges {
+        match merged.last_mut() {
+            Some(last) if s <= last.1 => last.1 = last.1.max(e),
+            _ => merged.push((s, e)),
+        }
+    }
+    let (first_start, first_end) = *merged.first()?;
+
+    let chars: Vec<char> = text.chars().collect();
+    let start = first_start.saturating_sub(SNIPPET_CONTEXT);
+    let end = (first_end + SNIPPET_CONTEXT).min(chars.len());
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < chars.len() { "…" } else { "" };
+    let shift = prefix.chars().count();
+
+    let snippet = format!(
+        "{prefix}{}{suffix}",
+        chars[start..end].iter().collect::<String>()
+    );
+    let matches = merged
+        .into_iter()
+        .filter(|&(_, e)| e <= end)
+        .map(|(s, e)| MatchRange {
+            start: s - start + shift,
+            end: e - start + shift,
+        })
+        .collect();
+
+    Some(Highlight {
+        field,
+        snippet,
+        matches,
+    })
+}
+
+/// Highlights for every field of a phrase containing one of the query terms, honouring
+/// field prefixes.
+pub fn highlight_phrase(phrase: &Phrase, terms: &[&Term]) -> Vec<Highlight> {
+    let needles = |field: Field| -> Vec<&str> {
+        terms
+            .iter()
+            .filter(|term| term.field.is_none_or(|f| f == field))
+            .map(|term| term.value.as_str())
+            .collect()
+    };
+
+    let mut highlights = Vec::new();
+    let phrase_needles = needles(Field::Phrase);
+    highlights.extend(highlight("phrase", &phrase.phrase, &phrase_needles));
+    let meaning_needles = needles(Field::Meaning);
+    for meaning in &phrase.meanings {
+        highlights.extend(highlight("meaning", meaning, &meaning_needles));
+    }
+    let tag_needles = needles(Field::Tag);
+    for tag in &phrase.tags {
+        highlights.extend(highlight("tag", tag, &tag_needles));
+    }
+    if let Some(source) = &phrase.source {
+        highlights.extend(highlight("source", source, &needles(Field::Source)));
+    }
+    if let Some(memo) = &phrase.memo {
+        highlights.extend(highlight("memo", memo, &needles(Field::Memo)));
+    }
+    highlights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::phrase::PhraseStatus;
+    use crate::services::query;
+
+    fn ranges(h: &Highlight) -> Vec<(usize, usize)> {
+        h.matches.iter().map(|m| (m.start, m.end)).collect()
+    }
+
+    #[test]
+    fn normalize_folds_width_kana_and_case() {
+        assert_eq!(normalize("ｶﾌｶ"), "かふか");
+        assert_eq!(normalize("カフカ"), "かふか");
+        assert_eq!(normalize("ｶﾞｯｺｳ"), "がっこう");
+        assert_eq!(normalize("Ｒａｉｎ"), "rain");
+        assert_eq!(normalize("Murakami"), "murakami");
+        assert_eq!(normalize("ー"), "ー"); // prolonged sound mark is left alone
+    }
+
+    #[test]
+    fn highlight_maps_back_to_original_chars() {
+        let h = highlight("phrase", "海辺のカフカ", &["ｶﾌｶ"]).unwrap();
+        assert_eq!(h.snippet, "海辺のカフカ");
+        assert_eq!(ranges(&h), vec![(3, 6)]);
+    }
+
+    #[test]
+    fn highlight_half_width_source_text() {
+        // ｶﾞ is two chars in the original but one after normalization
+        let h = highlight("memo", "ｶﾞｯｺｳへ行く", &["がっこう"]).unwrap();
+        assert_eq!(ranges(&h), vec![(0, 5)]);
+    }
+
+    #[test]
+    fn highlight_finds_all_occurrences_case_insensitively() {
+        let h = highlight("meaning", "Rain, rain, go away", &["RAIN"]).unwrap();
+        assert_eq!(ranges(&h), vec![(0, 4), (6, 10)]);
+    }
+
+    #[test]
+    fn highlight_trims_long_text_around_first_match() {
+        let text = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+        let h = highlight("memo", &text, &["needle"]).unwrap();
+        assert!(h.snippet.starts_with('…') && h.snippet.ends_with('…'));
+        assert_eq!(h.snippet.chars().count(), 1 + 40 + 6 + 40 + 1);
+        assert_eq!(ranges(&h), vec![(41, 47)]);
+    }
+
+    #[test]
+    fn highlight_none_without_substring_match() {
+        assert_eq!(highlight("phrase", "everywhere", &["evrywhere"]), None);
+        assert_eq!(highlight("phrase", "everywhere", &["  "]), None);
+    }
+
+    #[test]
+    fn highlight_phrase_covers_all_fields() {
+        let now = chrono::Utc::now();
+        let phrase = Phrase {
+            id: uuid::Uuid::new_v4(),
+            phrase: "the smell of rain".to_string(),
+            meanings: vec!["petrichor".to_string(), "after the rain".to_string()],
+            status: PhraseStatus::Ready,
+            source: Some("Rain Diaries".to_string()),
+            tags: vec!["rain".to_string(), "weather".to_string()],
+            memo: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let fields = |q: &str| -> Vec<&str> {
+            let query = query::parse(q).unwrap();
+            highlight_phrase(&phrase, &query.positive_terms())
+                .iter()
+                .map(|h| h.field)
+                .collect()
+        };
+        assert_eq!(fields("rain"), vec!["phrase", "meaning", "tag", "source"]);
+        assert_eq!(fields("source:rain"), vec!["source"]);
+        assert_eq!(fields("tag:weather -rain"), vec!["tag"]);
+    }
+
+    #[test]
+    fn highlight_merges_matches_of_several_terms() {
+        let h = highlight("phrase", "the smell of rain", &["smell", "ell of", "rain"]).unwrap();
+        assert_eq!(ranges(&h), vec![(4, 12), (13, 17)]);
+    }
+}
@@ -0,0 +1,139 @@
+//! Deleting a phrase moves it to the trash: a snapshot of the phrase with its meanings,
+//! embeddings, collection memberships and links, kept out of `phrases` so nothing else
+//! has to skip it. `eemee-admin trash` lists, restores and vacuums it.
+
+use std::slice;
+
+use sqlx::PgPool;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::backup::BackupLink;
+use crate::models::trash::{TrashEntry, TrashedMembership, TrashedPhrase};
+use crate::services::backup;
+
+#[tracing::instrument(skip_all, fields(%id))]
+pub async fn trash_phrase(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let Some(phrase) = backup::read_phrase(&mut tx, id).await? else {
+        return Err(AppError::NotFound);
+    };
+    let collections = sqlx::query_as::<_, TrashedMembership>(
+        "SELECT collection_id, added_at FROM collection_phrases WHERE phrase_id = $1",
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let links = sqlx::query_as::<_, BackupLink>(
+        "SELECT id, from_phrase_id, to_phrase_id, link_type, note, created_at
+         FROM phrase_links WHERE from_phrase_id = $1 OR to_phrase_id = $1",
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let snapshot = TrashedPhrase {
+        phrase,
+        collections,
+        links,
+    };
+
+    // A restored backup can bring back a phrase whose older copy is still in the trash
+    sqlx::query(
+        "INSERT INTO trash (id, snapshot) VALUES ($1, $2)
+         ON CONFLICT (id) DO UPDATE SET snapshot = EXCLUDED.snapshot, deleted_at = now()",
+    )
+    .bind(id)
+    .bind(Json(&snapshot))
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM phrases WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Oldest first.
+pub async fn list_trash(pool: &PgPool) -> Result<Vec<TrashEntry>, AppError> {
+    let entries = sqlx::query_as::<_, TrashEntry>(
+        "SELECT id, snapshot -> 'phrase' ->> 'phrase' AS phrase, deleted_at
+         FROM trash ORDER BY deleted_at, id",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(entries)
+}
+
+/// Puts a phrase back with its meanings and embeddings. It rejoins the collections that
+/// still exist, at the end, and regains the links whose other phrase still exists.
+pub async fn restore_phrase(pool: &PgPool, id: Uuid) -> Result<TrashedPhrase, AppError> {
+    let mut tx = pool.begin().await?;
+    let Some(Json(snapshot)) = sqlx::query_scalar::<_, Json<TrashedPhrase>>(
+        "DELETE FROM trash WHERE id = $1 RETURNING snapshot",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Err(AppError::NotFound);
+    };
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM phrases WHERE id = $1)")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if exists {
+        return Err(AppError::BadRequest(
+            format!("Phrase {id} is already in the library").into(),
+        ));
+    }
+
+    backup::restore_phrases(&mut tx, slice::from_ref(&snapshot.phrase)).await?;
+    for membership in &snapshot.collections {
+        sqlx::query(
+            "INSERT INTO collection_phrases (collection_id, phrase_id, position, added_at)
+             SELECT c.id, $2,
+                    COALESCE((SELECT MAX(position) + 1 FROM collection_phrases
+                              WHERE collection_id = c.id), 0),
+                    $3
+             FROM collections c WHERE c.id = $1",
+        )
+        .bind(membership.collection_id)
+        .bind(id)
+        .bind(membership.added_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+    for link in &snapshot.links {
+        sqlx::query(
+            "INSERT INTO phrase_links
+                 (id, from_phrase_id, to_phrase_id, link_type, note, created_at)
+             SELECT $1, $2, $3, $4, $5, $6
+             WHERE EXISTS (SELECT 1 FROM phrases WHERE id = $2)
+               AND EXISTS (SELECT 1 FROM phrases WHERE id = $3)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(link.id)
+        .bind(link.from_phrase_id)
+        .bind(link.to_phrase_id)
+        .bind(link.link_type)
+        .bind(&link.note)
+        .bind(link.created_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(snapshot)
+}
+
+/// Deletes for good what has been in the trash for at least `older_than_days`, returning
+/// how many phrases went.
+pub async fn vacuum(pool: &PgPool, older_than_days: u32) -> Result<u64, AppError> {
+    let result =
+        sqlx::query("DELETE FROM trash WHERE deleted_at <= now() - make_interval(days => $1)")
+            .bind(older_than_days as i32)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,190 @@
+//! Ledger of embedding API calls in `embedding_usage`, and the token budgets checked
+//! against it. Every call through [`MeteredEmbedder`] is recorded, background jobs
+//! included; budgets are only enforced before embedding on a request, so meanings that
+//! were already saved still get embedded.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use chrono::{DateTime, Datelike, NaiveTime, Utc};
+use sqlx::PgPool;
+
+use crate::config::LimitsConfig;
+use crate::error::AppError;
+use crate::models::usage::{EmbeddingUsage, TokenTotals};
+use crate::request_id;
+use crate::services::embedding::{Embedder, Embedding};
+
+/// Records the tokens of every successful call in the ledger.
+pub struct MeteredEmbedder {
+    inner: Arc<dyn Embedder>,
+    pool: PgPool,
+}
+
+impl MeteredEmbedder {
+    pub fn new(inner: Arc<dyn Embedder>, pool: PgPool) -> Self {
+        Self { inner, pool }
+    }
+}
+
+impl Embedder for MeteredEmbedder {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn provider(&self) -> &str {
+        self.inner.provider()
+    }
+
+    fn embed<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<Embedding, AppError>> + Send + 'a>> {
+        Box::pin(async move {
+            let embedding = self.inner.embed(text).await?;
+            let request_id = request_id::current();
+            // The call is paid for either way; a lost ledger row must not fail it
+            if let Err(e) = record(&self.pool, self.model(), embedding.tokens, request_id).await {
+                tracing::warn!("Cannot record embedding usage: {e}");
+            }
+            Ok(embedding)
+        })
+    }
+
+    // Checks cost nothing, so there is nothing to record
+    fn check(&self) -> Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + '_>> {
+        self.inner.check()
+    }
+}
+
+pub async fn record(
+    pool: &PgPool,
+    model: &str,
+    tokens: i64,
+    request_id: Option<String>,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO embedding_usage (model, tokens, request_id) VALUES ($1, $2, $3)")
+        .bind(model)
+        .bind(tokens)
+        .bind(request_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Tokens spent since the start of the current UTC day and month.
+pub async fn totals(pool: &PgPool) -> Result<TokenTotals, AppError> {
+    let (day, month) = period_starts(Utc::now());
+    let totals = sqlx::query_as::<_, TokenTotals>(
+        "SELECT COALESCE(SUM(tokens) FILTER (WHERE created_at >= $1), 0)::bigint AS today,
+                COALESCE(SUM(tokens), 0)::bigint AS this_month
+         FROM embedding_usage
+         WHERE created_at >= $2",
+    )
+    .bind(day)
+    .bind(month)
+    .fetch_one(pool)
+    .await?;
+    Ok(totals)
+}
+
+pub async fn usage(pool: &PgPool, limits: &LimitsConfig) -> Result<EmbeddingUsage, AppError> {
+    Ok(EmbeddingUsage {
+        totals: totals(pool).await?,
+        daily_budget: limits.daily_token_budget,
+        monthly_budget: limits.monthly_token_budget,
+    })
+}
+
+/// Refuses to embed once a budget is used up. The check comes before the call, so the
+/// last request of a period may overshoot a budget by its own tokens.
+pub async fn check_budget(pool: &PgPool, limits: &LimitsConfig) -> Result<(), AppError> {
+    if limits.daily_token_budget.is_none() && limits.monthly_token_budget.is_none() {
+        return Ok(());
+    }
+    match exceeded_budget(totals(pool).await?, limits) {
+        Some(message) => Err(AppError::BudgetExceeded(message)),
+        None => Ok(()),
+    }
+}
+
+/// Describes the first budget that `totals` has used up, if any.
+pub fn exceeded_budget(totals: TokenTotals, limits: &LimitsConfig) -> Option<String> {
+    let used_up = |spent: i64, budget: Option<u64>| {
+        budget.filter(|&budget| u64::try_from(spent).unwrap_or(0) >= budget)
+    };
+    if let Some(budget) = used_up(totals.this_month, limits.monthly_token_budget) {
+        return Some(format!(
+            "the monthly embedding budget of {budget} tokens is used up until the 1st (UTC)"
+        ));
+    }
+    used_up(totals.today, limits.daily_token_budget).map(|budget| {
+        format!("the daily embedding budget of {budget} tokens is used up until midnight (UTC)")
+    })
+}
+
+/// Starts of the UTC day and month containing `now`.
+fn period_starts(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let today = now.date_naive();
+    let first = today.with_day(1).expect("every month has a 1st");
+    (
+        today.and_time(NaiveTime::MIN).and_utc(),
+        first.and_time(NaiveTime::MIN).and_utc(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(daily: Option<u64>, monthly: Option<u64>) -> LimitsConfig {
+        LimitsConfig {
+            per_user_per_minute: 0,
+            global_per_minute: 0,
+            daily_token_budget: daily,
+            monthly_token_budget: monthly,
+        }
+    }
+
+    fn spent(today: i64, this_month: i64) -> TokenTotals {
+        TokenTotals { today, this_month }
+    }
+
+    #[test]
+    fn no_budget_is_never_exceeded() {
+        assert_eq!(
+            exceeded_budget(spent(1 << 40, 1 << 40), &limits(None, None)),
+            None
+        );
+    }
+
+    #[test]
+    fn daily_budget_is_exceeded_once_reached() {
+        let limits = limits(Some(1000), None);
+        assert_eq!(exceeded_budget(spent(999, 5000), &limits), None);
+        let message = exceeded_budget(spent(1000, 5000), &limits).unwrap();
+        assert!(
+            message.starts_with("the daily embedding budget of 1000 tokens"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn monthly_budget_is_reported_first() {
+        let limits = limits(Some(1000), Some(3000));
+        let message = exceeded_budget(spent(1200, 3000), &limits).unwrap();
+        assert!(
+            message.starts_with("the monthly embedding budget"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn periods_start_at_utc_midnight() {
+        let now = "2025-03-14T15:09:26Z".parse().unwrap();
+        let (day, month) = period_starts(now);
+        assert_eq!(day.to_rfc3339(), "2025-03-14T00:00:00+00:00");
+        assert_eq!(month.to_rfc3339(), "2025-03-01T00:00:00+00:00");
+    }
+}
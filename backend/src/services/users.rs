@@ -0,0 +1,81 @@
+//! Accounts allowed to log in besides `allowed_emails` in the configuration, which can
+//! only change with a restart. Emails are compared lowercased.
+
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::user::AllowedUser;
+
+/// Trimmed and lowercased; rejects anything that is not shaped like an address.
+pub fn normalize_email(email: &str) -> Result<String, AppError> {
+    let email = email.trim().to_lowercase();
+    match email.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && !domain.is_empty() => Ok(email),
+        _ => Err(AppError::BadRequest(
+            format!("Not an email address: {email}").into(),
+        )),
+    }
+}
+
+pub async fn list_allowed_users(pool: &PgPool) -> Result<Vec<AllowedUser>, AppError> {
+    let users = sqlx::query_as::<_, AllowedUser>("SELECT * FROM allowed_users ORDER BY email")
+        .fetch_all(pool)
+        .await?;
+    Ok(users)
+}
+
+/// Returns whether the user was added, i.e. was not already allowed.
+pub async fn add_allowed_user(pool: &PgPool, email: &str) -> Result<bool, AppError> {
+    let email = normalize_email(email)?;
+    let result =
+        sqlx::query("INSERT INTO allowed_users (email) VALUES ($1) ON CONFLICT DO NOTHING")
+            .bind(email)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Existing sessions stay logged in until they expire or log out.
+pub async fn remove_allowed_user(pool: &PgPool, email: &str) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM allowed_users WHERE email = $1")
+        .bind(normalize_email(email)?)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// Whether `email` may log in, by the configured list or the `allowed_users` table.
+pub async fn is_allowed(
+    pool: &PgPool,
+    configured: &[String],
+    email: &str,
+) -> Result<bool, AppError> {
+    if configured.iter().any(|e| e == email) {
+        return Ok(true);
+    }
+    let allowed =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM allowed_users WHERE email = lower($1))")
+            .bind(email)
+            .fetch_one(pool)
+            .await?;
+    Ok(allowed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_emails() {
+        assert_eq!(
+            normalize_email("  Someone@Example.COM ").unwrap(),
+            "someone@example.com"
+        );
+        assert!(normalize_email("someone").is_err());
+        assert!(normalize_email("@example.com").is_err());
+        assert!(normalize_email("someone@").is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! Opt-in approximate nearest neighbour index over `phrase_meanings.meaning_embedding_half`.
+
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::vector_index::{BuildIndexRequest, IndexKind, VectorIndexStatus};
+
+pub const INDEX_NAME: &str = "idx_phrase_meanings_embedding_ann";
+/// A rebuild is built under this name, then swapped in, so search keeps the old index
+/// until the new one is ready.
+const BUILD_INDEX_NAME: &str = "idx_phrase_meanings_embedding_ann_build";
+/// The replaced index, briefly, between the swap and its drop.
+const OLD_INDEX_NAME: &str = "idx_phrase_meanings_embedding_ann_old";
+
+const DEFAULT_M: i32 = 16;
+const DEFAULT_EF_CONSTRUCTION: i32 = 64;
+
+/// `CREATE INDEX` statement for a build request, under the build name. Parameters are
+/// validated here because DDL cannot take bind parameters.
+pub fn index_ddl(req: &BuildIndexRequest, meanings: i64) -> Result<String, AppError> {
+    let with = match req.kind {
+        IndexKind::Hnsw => {
+            let m = req.m.unwrap_or(DEFAULT_M);
+            let ef_construction = req.ef_construction.unwrap_or(DEFAULT_EF_CONSTRUCTION);
+            if !(2..=100).contains(&m) {
+                return Err(AppError::BadRequest("m must be between 2 and 100".into()));
+            }
+            if !(4..=1000).contains(&ef_construction) || ef_construction < 2 * m {
+                return Err(AppError::BadRequest(
+                    "ef_construction must be between 4 and 1000 and at least 2 * m".into(),
+                ));
+            }
+            format!("m = {m}, ef_construction = {ef_construction}")
+        }
+        IndexKind::Ivfflat => {
+            let lists = req
+                .lists
+                .unwrap_or_else(|| (meanings / 1000).clamp(1, 32768) as i32);
+            if !(1..=32768).contains(&lists) {
+                return Err(AppError::BadRequest(
+                    "lists must be between 1 and 32768".into(),
+                ));
+            }
+            format!("lists = {lists}")
+        }
+    };
+
+    Ok(format!(
+        "CREATE INDEX CONCURRENTLY {BUILD_INDEX_NAME} ON phrase_meanings
+         USING {} (meaning_embedding_half halfvec_cosine_ops) WITH ({with})",
+        req.kind.as_str()
+    ))
+}
+
+pub async fn index_status(pool: &PgPool) -> Result<VectorIndexStatus, AppError> {
+    let meanings: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM phrase_meanings")
+        .fetch_one(pool)
+        .await?;
+
+    let row: Option<(String, String, i64, bool)> = sqlx::query_as(
+        "SELECT am.amname::text, pg_get_indexdef(c.oid), pg_relation_size(c.oid), i.indisvalid
+         FROM pg_class c
+         JOIN pg_index i ON i.indexrelid = c.oid
+         JOIN pg_am am ON am.oid = c.relam
+         WHERE c.relname = $1",
+    )
+    .bind(INDEX_NAME)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(match row {
+        Some((kind, definition, size_bytes, valid)) => VectorIndexStatus {
+            exists: true,
+            kind: IndexKind::parse(&kind),
+            valid,
+            definition: Some(definition),
+            size_bytes: Some(size_bytes),
+            meanings,
+        },
+        None => VectorIndexStatus {
+            exists: false,
+            kind: None,
+            valid: false,
+            definition: None,
+            size_bytes: None,
+            meanings,
+        },
+    })
+}
+
+/// Builds the index, replacing any existing one. Runs concurrently so writes are not
+/// blocked, and under another name: the existing index serves searches until the new one
+/// is swapped in, and a failed or abandoned build leaves it untouched.
+pub async fn build_index(
+    pool: &PgPool,
+    req: &BuildIndexRequest,
+) -> Result<VectorIndexStatus, AppError> {
+    let meanings: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM phrase_meanings")
+        .fetch_one(pool)
+        .await?;
+    let ddl = index_ddl(req, meanings)?;
+
+    // Leftovers of a build that was interrupted, possibly INVALID
+    drop_named(pool, BUILD_INDEX_NAME).await?;
+    drop_named(pool, OLD_INDEX_NAME).await?;
+    if let Err(e) = sqlx::query(&ddl).execute(pool).await {
+        if let Err(cleanup) = drop_named(pool, BUILD_INDEX_NAME).await {
+            tracing::warn!("Cannot drop the failed vector index build: {cleanup}");
+        }
+        return Err(e.into());
+    }
+
+    // Renames only take a brief lock, unlike dropping the old index inline
+    let mut tx = pool.begin().await?;
+    sqlx::query(&format!(
+        "ALTER INDEX IF EXISTS {INDEX_NAME} RENAME TO {OLD_INDEX_NAME}"
+    ))
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(&format!(
+        "ALTER INDEX {BUILD_INDEX_NAME} RENAME TO {INDEX_NAME}"
+    ))
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    drop_named(pool, OLD_INDEX_NAME).await?;
+    tracing::info!(
+        "Built {} vector index over {meanings} meanings",
+        req.kind.as_str()
+    );
+
+    index_status(pool).await
+}
+
+pub async fn drop_index(pool: &PgPool) -> Result<(), AppError> {
+    drop_named(pool, INDEX_NAME).await
+}
+
+async fn drop_named(pool: &PgPool, name: &str) -> Result<(), AppError> {
+    sqlx::query(&format!("DROP INDEX CONCURRENTLY IF EXISTS {name}"))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(kind: IndexKind) -> BuildIndexRequest {
+        BuildIndexRequest {
+            kind,
+            m: None,
+            ef_construction: None,
+            lists: None,
+        }
+    }
+
+    #[test]
+    fn hnsw_ddl_uses_defaults() {
+        let ddl = index_ddl(&request(IndexKind::Hnsw), 10).unwrap();
+        assert!(ddl.starts_with(&format!("CREATE INDEX CONCURRENTLY {BUILD_INDEX_NAME} ")));
+        assert!(ddl.contains("USING hnsw (meaning_embedding_half halfvec_cosine_ops)"));
+        assert!(ddl.contains("m = 16, ef_construction = 64"));
+    }
+
+    #[test]
+    fn ivfflat_lists_scale_with_meanings() {
+        let ddl = index_ddl(&request(IndexKind::Ivfflat), 50).unwrap();
+        assert!(ddl.contains("USING ivfflat"));
+        assert!(ddl.contains("lists = 1"));
+
+        let ddl = index_ddl(&request(IndexKind::Ivfflat), 25_000).unwrap();
+        assert!(ddl.contains("lists = 25"));
+    }
+
+    #[test]
+    fn invalid_parameters_rejected() {
+        let mut req = request(IndexKind::Hnsw);
+        req.m = Some(1);
+        assert!(matches!(index_ddl(&req, 0), Err(AppError::BadRequest(_))));
+
+        let mut req = request(IndexKind::Hnsw);
+        req.m = Some(32);
+        req.ef_construction = Some(40);
+        assert!(matches!(index_ddl(&req, 0), Err(AppError::BadRequest(_))));
+
+        let mut req = request(IndexKind::Ivfflat);
+        req.lists = Some(0);
+        assert!(matches!(index_ddl(&req, 0), Err(AppError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,81 @@
+//! Vector arithmetic for combining several queries and example phrases into one search.
+
+/// `v` scaled to unit length; a zero vector stays zero.
+pub fn normalize(v: &[f32]) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm <= f32::EPSILON {
+        return v.to_vec();
+    }
+    v.iter().map(|x| x / norm).collect()
+}
+
+/// Component-wise mean of unit-length copies of `vectors`, so every meaning of an example
+/// phrase counts equally regardless of magnitude. Empty for no vectors.
+pub fn mean_direction(vectors: &[&[f32]]) -> Vec<f32> {
+    let Some(first) = vectors.first() else {
+        return Vec::new();
+    };
+    let mut sum = vec![0.0_f32; first.len()];
+    for v in vectors {
+        for (acc, x) in sum.iter_mut().zip(normalize(v)) {
+            *acc += x;
+        }
+    }
+    sum.iter().map(|x| x / vectors.len() as f32).collect()
+}
+
+/// Weighted sum of unit-length copies of the vectors. Cosine distance ignores magnitude, so
+/// this points towards the positive vectors and away from the negative ones.
+pub fn weighted_centroid(vectors: &[(Vec<f32>, f32)]) -> Vec<f32> {
+    let Some((first, _)) = vectors.first() else {
+        return Vec::new();
+    };
+    let mut sum = vec![0.0_f32; first.len()];
+    for (v, weight) in vectors {
+        for (acc, x) in sum.iter_mut().zip(normalize(v)) {
+            *acc += weight * x;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn normalize_scales_to_unit_length() {
+        assert_close(&normalize(&[3.0, 4.0]), &[0.6, 0.8]);
+        assert_close(&normalize(&[0.0, 0.0]), &[0.0, 0.0]);
+    }
+
+    #[test]
+    fn mean_direction_ignores_magnitude() {
+        assert_close(&mean_direction(&[&[10.0, 0.0], &[0.0, 1.0]]), &[0.5, 0.5]);
+        assert!(mean_direction(&[]).is_empty());
+    }
+
+    #[test]
+    fn weighted_centroid_moves_away_from_negatives() {
+        let centroid = weighted_centroid(&[
+            (vec![2.0, 0.0, 0.0], 1.0),
+            (vec![0.0, 1.0, 0.0], 1.0),
+            (vec![0.0, 0.0, 5.0], -0.5),
+        ]);
+        assert_close(&centroid, &[1.0, 1.0, -0.5]);
+    }
+
+    #[test]
+    fn single_vector_keeps_its_direction() {
+        assert_close(&weighted_centroid(&[(vec![0.0, 2.0], 1.0)]), &[0.0, 1.0]);
+        // Zero vectors (e.g. from a stub embedder) pass through unchanged
+        assert_close(&weighted_centroid(&[(vec![0.0, 0.0], 1.0)]), &[0.0, 0.0]);
+    }
+}
@@ -0,0 +1,121 @@
+//! Graceful shutdown. On SIGTERM or SIGINT the server stops accepting connections and
+//! background tasks are told to stop through a [`CancellationToken`]; in-flight requests
+//! and tasks then get a bounded time to finish before the process exits anyway.
+
+use std::future::IntoFuture;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Completes on the first SIGTERM or SIGINT.
+pub async fn signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for SIGINT");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+/// Runs `server`, which must stop once `token` is cancelled, then waits for `tasks`.
+/// Both must be done within `timeout` of the cancellation; returns whether they were.
+pub async fn drain<S>(
+    server: S,
+    tasks: Vec<JoinHandle<()>>,
+    token: &CancellationToken,
+    timeout: Duration,
+) -> bool
+where
+    S: IntoFuture<Output = std::io::Result<()>>,
+{
+    let finished = async {
+        if let Err(e) = server.await {
+            tracing::error!("Server error: {e}");
+        }
+        // The server may also stop on its own; the tasks must stop with it
+        token.cancel();
+        for task in tasks {
+            if let Err(e) = task.await {
+                tracing::error!("Background task failed: {e}");
+            }
+        }
+    };
+    let deadline = async {
+        token.cancelled().await;
+        tokio::time::sleep(timeout).await;
+    };
+
+    tokio::select! {
+        _ = finished => true,
+        _ = deadline => {
+            tracing::warn!("Shutdown timed out after {timeout:?}; abandoning in-flight work");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+
+    use super::*;
+
+    /// A stand-in server for tests that stops when `token` is cancelled.
+    fn server_until(token: &CancellationToken) -> impl Future<Output = std::io::Result<()>> {
+        let token = token.clone();
+        async move {
+            token.cancelled().await;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn drains_tasks_that_stop_on_cancel() {
+        let token = CancellationToken::new();
+        let task = tokio::spawn({
+            let token = token.clone();
+            async move { token.cancelled().await }
+        });
+        token.cancel();
+
+        let drained = drain(
+            server_until(&token),
+            vec![task],
+            &token,
+            Duration::from_secs(5),
+        )
+        .await;
+        assert!(drained);
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_tasks_after_timeout() {
+        let token = CancellationToken::new();
+        let stuck = tokio::spawn(std::future::pending::<()>());
+        token.cancel();
+
+        let drained = drain(
+            server_until(&token),
+            vec![stuck],
+            &token,
+            Duration::from_millis(20),
+        )
+        .await;
+        assert!(!drained);
+    }
+}
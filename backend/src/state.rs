@@ -1,4 +1,6 @@
+use crate::config::{HealthConfig, LimitsConfig};
 use crate::services::embedding::Embedder;
+use crate::services::rate_limit::RateLimiter;
 use oauth2::basic::BasicClient;
 use oauth2::{EndpointNotSet, EndpointSet};
 use sqlx::PgPool;
@@ -13,6 +15,9 @@ pub struct AppState {
     pub embedding: Arc<dyn Embedder>,
     pub oauth_client: OAuthClient,
     pub allowed_emails: Vec<String>,
+    pub health: HealthConfig,
+    pub limits: LimitsConfig,
+    pub rate_limiter: Arc<RateLimiter>,
 }
 
 impl AppState {
@@ -21,12 +26,21 @@ impl AppState {
         embedding: Arc<dyn Embedder>,
         oauth_client: OAuthClient,
         allowed_emails: Vec<String>,
+        health: HealthConfig,
+        limits: LimitsConfig,
     ) -> Arc<Self> {
+        let rate_limiter = Arc::new(RateLimiter::new(
+            limits.per_user_per_minute,
+            limits.global_per_minute,
+        ));
         Arc::new(Self {
             pool,
             embedding,
             oauth_client,
             allowed_emails,
+            health,
+            limits,
+            rate_limiter,
         })
     }
 }
@@ -0,0 +1,406 @@
+mod common;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use eemee_backend::cli::{
+    self, Command, Context, ExportCommand, ImportCommand, TokensCommand, TrashCommand, UsersCommand,
+};
+use eemee_backend::error::AppError;
+use eemee_backend::models::backup::RestoreMode;
+use eemee_backend::models::phrase::MarkdownGroup;
+use eemee_backend::services::{tokens, users};
+use serde_json::json;
+use sqlx::PgPool;
+
+const CLIPPINGS: &str = "The Remains of the Day (Kazuo Ishiguro)
+- Your Highlight on page 12 | Location 170-172 | Added on Sunday, March 3, 2019 10:21:33 PM
+
+a sense of dignity
+==========
+";
+
+fn context(pool: &PgPool) -> Context {
+    Context {
+        pool: pool.clone(),
+        embedder: Some(Arc::new(common::FakeEmbedder)),
+        allowed_emails: vec!["owner@example.com".to_string()],
+    }
+}
+
+/// A path in a fresh temporary directory, removed by the caller.
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("eemee-admin-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+/// Returns the id; its embedding job stays queued.
+async fn create_phrase(pool: &PgPool, phrase: &str) -> String {
+    let app = common::build_test_app_without_workers(pool.clone());
+    let body = json!({"phrase": phrase, "meanings": ["a meaning"], "tags": ["idiom"]});
+    let (status, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    assert_eq!(status, 200, "{created}");
+    created["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn users_are_added_listed_and_removed() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let ctx = context(&pool);
+
+    let add = || {
+        Command::Users(UsersCommand::Add {
+            email: "New@Example.com".to_string(),
+        })
+    };
+    assert_eq!(
+        cli::run(add(), &ctx).await.unwrap(),
+        "Allowed New@Example.com to log in"
+    );
+    assert!(
+        cli::run(add(), &ctx)
+            .await
+            .unwrap()
+            .contains("already allowed")
+    );
+    assert!(
+        users::is_allowed(&pool, &[], "new@example.com")
+            .await
+            .unwrap()
+    );
+
+    let list = cli::run(Command::Users(UsersCommand::List), &ctx)
+        .await
+        .unwrap();
+    let lines: Vec<&str> = list.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "owner@example.com\t(configuration)");
+    assert!(lines[1].starts_with("new@example.com\tadded "));
+
+    let remove = |email: &str| {
+        Command::Users(UsersCommand::Remove {
+            email: email.to_string(),
+        })
+    };
+    cli::run(remove("new@example.com"), &ctx).await.unwrap();
+    assert!(
+        !users::is_allowed(&pool, &[], "new@example.com")
+            .await
+            .unwrap()
+    );
+    assert!(matches!(
+        cli::run(remove("new@example.com"), &ctx).await,
+        Err(AppError::NotFound)
+    ));
+    assert!(matches!(
+        cli::run(remove("owner@example.com"), &ctx).await,
+        Err(AppError::BadRequest(_))
+    ));
+
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn tokens_are_created_listed_and_revoked() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let ctx = context(&pool);
+
+    let create = |email: &str| {
+        Command::Tokens(TokensCommand::Create {
+            email: email.to_string(),
+            name: "backups".to_string(),
+        })
+    };
+    assert!(matches!(
+        cli::run(create("stranger@example.com"), &ctx).await,
+        Err(AppError::BadRequest(_))
+    ));
+    let report = cli::run(create("Owner@Example.com"), &ctx).await.unwrap();
+    let secret = report.lines().last().unwrap();
+    assert_eq!(
+        tokens::authenticate(&pool, secret)
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("owner@example.com")
+    );
+
+    let list = cli::run(Command::Tokens(TokensCommand::List), &ctx)
+        .await
+        .unwrap();
+    let fields: Vec<&str> = list.split('\t').collect();
+    assert_eq!(fields[1..3], ["owner@example.com", "backups"]);
+    assert!(fields[3].contains(", used "), "{list}");
+    assert!(!list.contains(secret));
+
+    let id = fields[0].parse().unwrap();
+    cli::run(Command::Tokens(TokensCommand::Revoke { id }), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(tokens::authenticate(&pool, secret).await.unwrap(), None);
+    assert!(matches!(
+        cli::run(Command::Tokens(TokensCommand::Revoke { id }), &ctx).await,
+        Err(AppError::NotFound)
+    ));
+
+    common::teardown_test_db(&db_name).await;
+}
+
+async fn count(pool: &PgPool, query: &str) -> i64 {
+    sqlx::query_scalar(query).fetch_one(pool).await.unwrap()
+}
+
+#[tokio::test]
+async fn trashed_phrases_are_restored_or_vacuumed() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let ctx = context(&pool);
+    let glad = create_phrase(&pool, "glad").await;
+    let happy = create_phrase(&pool, "happy").await;
+    common::drain_jobs_with(&pool, &common::FakeEmbedder).await;
+    let send = |request| {
+        common::send_json_request(common::build_test_app_authenticated(pool.clone()), request)
+    };
+    let (_, collection) = send(common::json_post(
+        "/api/collections",
+        &json!({"name": "moods"}),
+    ))
+    .await;
+    let collection = collection["id"].as_str().unwrap();
+    send(common::json_post(
+        &format!("/api/collections/{collection}/phrases"),
+        &json!({"phrase_id": happy}),
+    ))
+    .await;
+    send(common::json_post(
+        &format!("/api/phrases/{happy}/links"),
+        &json!({"target_id": glad, "link_type": "synonym"}),
+    ))
+    .await;
+
+    let (status, _) = send(common::delete_request(&format!("/api/phrases/{happy}"))).await;
+    assert_eq!(status, 200);
+    let (status, _) = send(common::get_request(&format!("/api/phrases/{happy}"))).await;
+    assert_eq!(status, 404);
+    let list = cli::run(Command::Trash(TrashCommand::List), &ctx)
+        .await
+        .unwrap();
+    assert!(
+        list.starts_with(&format!("{happy}\thappy\tdeleted ")),
+        "{list}"
+    );
+
+    let id: uuid::Uuid = happy.parse().unwrap();
+    let report = cli::run(Command::Trash(TrashCommand::Restore { id }), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(report, "Restored \"happy\"");
+    let (status, _) = send(common::get_request(&format!("/api/phrases/{happy}"))).await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        count(&pool, "SELECT COUNT(*) FROM collection_phrases").await,
+        1
+    );
+    assert_eq!(count(&pool, "SELECT COUNT(*) FROM phrase_links").await, 1);
+    assert_eq!(
+        count(
+            &pool,
+            "SELECT COUNT(*) FROM phrase_meanings WHERE embedding_status = 'ready'"
+        )
+        .await,
+        2,
+        "embeddings restored without re-embedding"
+    );
+    assert_eq!(count(&pool, "SELECT COUNT(*) FROM trash").await, 0);
+
+    send(common::delete_request(&format!("/api/phrases/{happy}"))).await;
+    let vacuum = |older_than_days| Command::Trash(TrashCommand::Vacuum { older_than_days });
+    assert_eq!(
+        cli::run(vacuum(30), &ctx).await.unwrap(),
+        "Deleted 0 phrases trashed at least 30 days ago"
+    );
+    assert_eq!(
+        cli::run(vacuum(0), &ctx).await.unwrap(),
+        "Deleted 1 phrases trashed at least 0 days ago"
+    );
+    assert!(matches!(
+        cli::run(Command::Trash(TrashCommand::Restore { id }), &ctx).await,
+        Err(AppError::NotFound)
+    ));
+
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn migrate_is_idempotent() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let report = cli::run(Command::Migrate, &context(&pool)).await.unwrap();
+    assert_eq!(report, "Applied 0 migrations");
+
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn stats_count_the_library() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let ctx = context(&pool);
+    create_phrase(&pool, "rain check").await;
+    create_phrase(&pool, "hit the sack").await;
+    common::drain_jobs_with(&pool, &common::FakeEmbedder).await;
+
+    let file = temp_path("My Clippings.txt");
+    std::fs::write(&file, CLIPPINGS).unwrap();
+    let report = cli::run(
+        Command::Import(ImportCommand::Kindle { file: file.clone() }),
+        &ctx,
+    )
+    .await
+    .unwrap();
+    assert_eq!(report, "Imported 1 drafts (0 duplicates, 0 skipped)");
+
+    let report = cli::run(Command::Stats { json: true }, &ctx).await.unwrap();
+    let stats: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(stats["phrases"], 3);
+    assert_eq!(stats["drafts"], 1);
+    assert_eq!(stats["meanings"], 2);
+    assert_eq!(stats["pending_meanings"], 0);
+    assert_eq!(
+        stats["embedding_models"],
+        json!([{"model": common::TEST_MODEL, "meanings": 2}])
+    );
+
+    let report = cli::run(Command::Stats { json: false }, &ctx)
+        .await
+        .unwrap();
+    assert!(
+        report.starts_with("Phrases         3 (1 drafts)"),
+        "{report}"
+    );
+
+    std::fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn reembed_requeues_meanings_from_other_models() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let ctx = context(&pool);
+    create_phrase(&pool, "rain check").await;
+    create_phrase(&pool, "hit the sack").await;
+    common::drain_jobs_with(&pool, &common::FakeEmbedder).await;
+    sqlx::query(
+        "UPDATE phrase_meanings SET embedding_model = 'old-model'
+         WHERE phrase_id = (SELECT id FROM phrases WHERE phrase = 'rain check')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let report = cli::run(
+        Command::Reembed {
+            all: false,
+            run: false,
+        },
+        &ctx,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        report,
+        format!("Queued 1 meanings for {}", common::TEST_MODEL)
+    );
+    let pending: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM phrase_meanings WHERE embedding_status = 'pending'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(pending, 1);
+
+    let report = cli::run(
+        Command::Reembed {
+            all: true,
+            run: true,
+        },
+        &ctx,
+    )
+    .await
+    .unwrap();
+    assert!(report.ends_with("Ran 2 embedding jobs"), "{report}");
+    let models: Vec<String> =
+        sqlx::query_scalar("SELECT DISTINCT embedding_model FROM phrase_meanings")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(models, [common::TEST_MODEL]);
+
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn exports_phrases_and_round_trips_a_backup() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let ctx = context(&pool);
+    create_phrase(&pool, "rain check").await;
+    common::drain_jobs_with(&pool, &common::FakeEmbedder).await;
+
+    let csv = temp_path("phrases.csv");
+    let report = cli::run(
+        Command::Export(ExportCommand::Phrases {
+            output: csv.clone(),
+            format: "csv".to_string(),
+            group: MarkdownGroup::Phrase,
+            from: None,
+            to: None,
+            collection: None,
+            tags: vec!["idiom".to_string()],
+            source: None,
+        }),
+        &ctx,
+    )
+    .await
+    .unwrap();
+    assert!(report.starts_with("Exported 1 phrases to "), "{report}");
+    let contents = std::fs::read_to_string(&csv).unwrap();
+    assert!(contents.starts_with("id,phrase,meanings"));
+    assert!(contents.contains("rain check"));
+
+    let backup = csv.with_file_name("backup.json");
+    cli::run(
+        Command::Export(ExportCommand::Backup {
+            output: backup.clone(),
+        }),
+        &ctx,
+    )
+    .await
+    .unwrap();
+    sqlx::query("DELETE FROM phrases")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let report = cli::run(
+        Command::Import(ImportCommand::Backup {
+            file: backup,
+            mode: RestoreMode::Replace,
+        }),
+        &ctx,
+    )
+    .await
+    .unwrap();
+    assert!(
+        report.starts_with("Restored (Replace): 1 phrases, 1 meanings"),
+        "{report}"
+    );
+    let ready: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM phrase_meanings WHERE embedding_status = 'ready'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(ready, 1, "embeddings restored without re-embedding");
+
+    std::fs::remove_dir_all(csv.parent().unwrap()).unwrap();
+    common::teardown_test_db(&db_name).await;
+}
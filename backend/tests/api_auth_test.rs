@@ -1,5 +1,7 @@
 mod common;
 
+use eemee_backend::services::{tokens, users};
+
 #[tokio::test]
 async fn unauthenticated_request_to_phrases_returns_401() {
     let (pool, db_name) = common::setup_test_db().await;
@@ -69,3 +71,56 @@ async fn auth_me_authenticated() {
     pool.close().await;
     common::teardown_test_db(&db_name).await;
 }
+
+fn bearer_request(uri: &str, token: &str) -> axum::http::Request<axum::body::Body> {
+    let mut request = common::get_request(uri);
+    request.headers_mut().insert(
+        axum::http::header::AUTHORIZATION,
+        format!("Bearer {token}").parse().unwrap(),
+    );
+    request
+}
+
+#[tokio::test]
+async fn api_tokens_authenticate_their_allowed_user() {
+    let (pool, db_name) = common::setup_test_db().await;
+    users::add_allowed_user(&pool, "script@example.com")
+        .await
+        .unwrap();
+    let (token, secret) = tokens::create_token(&pool, "script@example.com", "backups")
+        .await
+        .unwrap();
+    let status = |token: String| {
+        let app = common::build_test_app(pool.clone());
+        async move {
+            common::send_request(app, bearer_request("/api/phrases", &token))
+                .await
+                .0
+        }
+    };
+
+    assert_eq!(status(secret.clone()).await, 200);
+    let used: Option<chrono::DateTime<chrono::Utc>> =
+        sqlx::query_scalar("SELECT last_used_at FROM api_tokens")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(used.is_some());
+
+    assert_eq!(status(format!("{secret}x")).await, 401);
+    assert_eq!(status("not-a-token".to_string()).await, 401);
+
+    // Tokens stop working with their user's access
+    users::remove_allowed_user(&pool, "script@example.com")
+        .await
+        .unwrap();
+    assert_eq!(status(secret.clone()).await, 401);
+    users::add_allowed_user(&pool, "script@example.com")
+        .await
+        .unwrap();
+    tokens::revoke_token(&pool, token.id).await.unwrap();
+    assert_eq!(status(secret).await, 401);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use serde_json::json;
+
+async fn create_phrase(pool: &sqlx::PgPool, phrase: &str, meanings: &[&str]) -> String {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": phrase, "meanings": meanings, "tags": ["t"]});
+    let (_, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    created["id"].as_str().unwrap().to_string()
+}
+
+async fn backup(pool: &sqlx::PgPool) -> serde_json::Value {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, body) =
+        common::send_json_request(app, common::get_request("/api/admin/backup")).await;
+    assert_eq!(status, 200);
+    body
+}
+
+async fn restore(
+    pool: &sqlx::PgPool,
+    mode: &str,
+    archive: &serde_json::Value,
+) -> (axum::http::StatusCode, serde_json::Value) {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let uri = format!("/api/admin/restore?mode={mode}");
+    common::send_json_request(app, common::json_post(&uri, archive)).await
+}
+
+async fn phrase_names(pool: &sqlx::PgPool) -> Vec<String> {
+    sqlx::query_scalar("SELECT phrase FROM phrases ORDER BY phrase")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+/// Seeds two linked phrases in a collection, plus a saved search.
+async fn seed_library(pool: &sqlx::PgPool) -> (String, String) {
+    let rain = create_phrase(pool, "rain", &["water falling", "a shower"]).await;
+    let sun = create_phrase(pool, "sun", &["a star"]).await;
+    common::set_phrase_embedding(pool, &rain, &[(7, 0.5)]).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"target_id": sun, "link_type": "antonym", "note": "weather"});
+    let uri = format!("/api/phrases/{rain}/links");
+    let (status, _) = common::send_json_request(app, common::json_post(&uri, &body)).await;
+    assert_eq!(status, 200);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, collection) = common::send_json_request(
+        app,
+        common::json_post("/api/collections", &json!({"name": "weather"})),
+    )
+    .await;
+    for id in [&sun, &rain] {
+        let app = common::build_test_app_authenticated(pool.clone());
+        let uri = format!(
+            "/api/collections/{}/phrases",
+            collection["id"].as_str().unwrap()
+        );
+        common::send_json_request(app, common::json_post(&uri, &json!({"phrase_id": id}))).await;
+    }
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"name": "wet", "mode": "text", "query": "rain"});
+    common::send_json_request(app, common::json_post("/api/saved-searches", &body)).await;
+
+    (rain, sun)
+}
+
+#[tokio::test]
+async fn backup_includes_embeddings_and_relations() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let (rain, sun) = seed_library(&pool).await;
+
+    let archive = backup(&pool).await;
+    assert_eq!(archive["version"], 1);
+    let phrases = archive["phrases"].as_array().unwrap();
+    assert_eq!(phrases.len(), 2);
+
+    let rain_entry = phrases.iter().find(|p| p["id"] == rain.as_str()).unwrap();
+    let meanings = rain_entry["meanings"].as_array().unwrap();
+    assert_eq!(meanings.len(), 2);
+    assert_eq!(meanings[0]["meaning"], "water falling");
+    assert_eq!(meanings[0]["embedding_model"], common::TEST_MODEL);
+    assert_eq!(meanings[0]["embedding"].as_array().unwrap().len(), 3072);
+    assert_eq!(meanings[0]["embedding"][7], 0.5);
+
+    assert_eq!(archive["links"][0]["from_phrase_id"], rain.as_str());
+    assert_eq!(archive["links"][0]["link_type"], "antonym");
+    let members: Vec<&str> = archive["collections"][0]["phrases"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["phrase_id"].as_str().unwrap())
+        .collect();
+    assert_eq!(members, vec![sun.as_str(), rain.as_str()]);
+    assert_eq!(archive["saved_searches"][0]["query"], "rain");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn replace_restores_into_an_empty_library() {
+    let (source, source_db) = common::setup_test_db().await;
+    let (rain, _) = seed_library(&source).await;
+    let archive = backup(&source).await;
+
+    let (target, target_db) = common::setup_test_db().await;
+    create_phrase(&target, "stale", &["to be replaced"]).await;
+
+    let (status, summary) = restore(&target, "replace", &archive).await;
+    assert_eq!(status, 200, "{summary}");
+    assert_eq!(summary["phrases"], 2);
+    assert_eq!(summary["meanings"], 3);
+    assert_eq!(summary["links"], 1);
+    assert_eq!(phrase_names(&target).await, vec!["rain", "sun"]);
+
+    // Ids, embeddings, links and collections survive unchanged
+    let restored = backup(&target).await;
+    assert_eq!(restored["phrases"], archive["phrases"]);
+    assert_eq!(restored["collections"], archive["collections"]);
+    assert_eq!(restored["links"], archive["links"]);
+    assert_eq!(restored["saved_searches"], archive["saved_searches"]);
+
+    let app = common::build_test_app_authenticated(target.clone());
+    let (status, detail) =
+        common::send_json_request(app, common::get_request(&format!("/api/phrases/{rain}"))).await;
+    assert_eq!(status, 200);
+    assert_eq!(detail["links"][0]["note"], "weather");
+
+    source.close().await;
+    target.close().await;
+    common::teardown_test_db(&source_db).await;
+    common::teardown_test_db(&target_db).await;
+}
+
+#[tokio::test]
+async fn merge_keeps_phrases_missing_from_the_backup() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let (rain, _) = seed_library(&pool).await;
+    let archive = backup(&pool).await;
+
+    // Edit after the backup, and add a phrase the backup does not know
+    let app = common::build_test_app_authenticated(pool.clone());
+    let uri = format!("/api/phrases/{rain}");
+    common::send_json_request(app, common::json_put(&uri, &json!({"phrase": "drizzle"}))).await;
+    create_phrase(&pool, "fog", &["low cloud"]).await;
+
+    let (status, _) = restore(&pool, "merge", &archive).await;
+    assert_eq!(status, 200);
+    assert_eq!(phrase_names(&pool).await, vec!["fog", "rain", "sun"]);
+
+    // Restoring twice changes nothing
+    let (status, _) = restore(&pool, "merge", &archive).await;
+    assert_eq!(status, 200);
+    assert_eq!(phrase_names(&pool).await, vec!["fog", "rain", "sun"]);
+    let links: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM phrase_links")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(links, 1);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn restore_rejects_incompatible_archives_atomically() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed_library(&pool).await;
+    let archive = backup(&pool).await;
+
+    let mut newer = archive.clone();
+    newer["version"] = json!(2);
+    let (status, body) = restore(&pool, "replace", &newer).await;
+    assert_eq!(status, 400);
+    assert!(body["error"].as_str().unwrap().contains("version 2"));
+
+    let mut other_model = archive.clone();
+    other_model["phrases"][0]["meanings"][0]["embedding_model"] = json!("another-model");
+    let (status, _) = restore(&pool, "replace", &other_model).await;
+    assert_eq!(status, 400);
+
+    let mut dangling = archive.clone();
+    dangling["links"][0]["to_phrase_id"] = json!(uuid::Uuid::new_v4());
+    let (status, _) = restore(&pool, "replace", &dangling).await;
+    assert_eq!(status, 400);
+
+    // Nothing was deleted by the rejected replaces
+    assert_eq!(phrase_names(&pool).await, vec!["rain", "sun"]);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -0,0 +1,352 @@
+mod common;
+
+use serde_json::json;
+
+async fn create_phrase(pool: &sqlx::PgPool, phrase: &str) -> String {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": phrase, "meanings": [format!("meaning of {phrase}")]});
+    let (_, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    created["id"].as_str().unwrap().to_string()
+}
+
+async fn create_collection(pool: &sqlx::PgPool, name: &str) -> String {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"name": name, "description": "curated"});
+    let (status, created) =
+        common::send_json_request(app, common::json_post("/api/collections", &body)).await;
+    assert_eq!(status, 200);
+    created["id"].as_str().unwrap().to_string()
+}
+
+async fn add_phrase(pool: &sqlx::PgPool, collection_id: &str, body: serde_json::Value) {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::json_post(&format!("/api/collections/{collection_id}/phrases"), &body),
+    )
+    .await;
+    assert_eq!(status, 200);
+}
+
+fn member_phrases(detail: &serde_json::Value) -> Vec<String> {
+    detail["phrases"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["phrase"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn create_and_list_collections() {
+    let (pool, db_name) = common::setup_test_db().await;
+    create_collection(&pool, "essay").await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/collections")).await;
+    assert_eq!(status, 200);
+    let collections = json.as_array().unwrap();
+    assert_eq!(collections.len(), 1);
+    assert_eq!(collections[0]["name"], "essay");
+    assert_eq!(collections[0]["description"], "curated");
+    assert_eq!(collections[0]["phrase_count"], 0);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn create_collection_empty_name_rejected() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let app = common::build_test_app_authenticated(pool.clone());
+
+    let (status, _) = common::send_json_request(
+        app,
+        common::json_post("/api/collections", &json!({"name": "  "})),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn add_phrases_keeps_order_and_positions() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let first = create_phrase(&pool, "first").await;
+    let second = create_phrase(&pool, "second").await;
+    let third = create_phrase(&pool, "third").await;
+    let collection = create_collection(&pool, "essay").await;
+
+    add_phrase(&pool, &collection, json!({"phrase_id": first})).await;
+    add_phrase(&pool, &collection, json!({"phrase_id": third})).await;
+    add_phrase(
+        &pool,
+        &collection,
+        json!({"phrase_id": second, "position": 1}),
+    )
+    .await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/collections/{collection}")),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(json["phrase_count"], 3);
+    assert_eq!(member_phrases(&json), vec!["first", "second", "third"]);
+    assert_eq!(json["phrases"][2]["position"], 2);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn add_duplicate_phrase_rejected() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let phrase = create_phrase(&pool, "only").await;
+    let collection = create_collection(&pool, "essay").await;
+    add_phrase(&pool, &collection, json!({"phrase_id": phrase})).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::json_post(
+            &format!("/api/collections/{collection}/phrases"),
+            &json!({"phrase_id": phrase}),
+        ),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn add_unknown_phrase_returns_404() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let collection = create_collection(&pool, "essay").await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::json_post(
+            &format!("/api/collections/{collection}/phrases"),
+            &json!({"phrase_id": uuid::Uuid::new_v4()}),
+        ),
+    )
+    .await;
+    assert_eq!(status, 404);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn reorder_collection() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let first = create_phrase(&pool, "first").await;
+    let second = create_phrase(&pool, "second").await;
+    let collection = create_collection(&pool, "essay").await;
+    add_phrase(&pool, &collection, json!({"phrase_id": first})).await;
+    add_phrase(&pool, &collection, json!({"phrase_id": second})).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::json_put(
+            &format!("/api/collections/{collection}/phrases"),
+            &json!({"phrase_ids": [second, first]}),
+        ),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(member_phrases(&json), vec!["second", "first"]);
+
+    // Must be a permutation of the current members
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::json_put(
+            &format!("/api/collections/{collection}/phrases"),
+            &json!({"phrase_ids": [second]}),
+        ),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn remove_phrase_from_collection() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let first = create_phrase(&pool, "first").await;
+    let second = create_phrase(&pool, "second").await;
+    let collection = create_collection(&pool, "essay").await;
+    add_phrase(&pool, &collection, json!({"phrase_id": first})).await;
+    add_phrase(&pool, &collection, json!({"phrase_id": second})).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::delete_request(&format!("/api/collections/{collection}/phrases/{first}")),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(member_phrases(&json), vec!["second"]);
+    assert_eq!(json["phrases"][0]["position"], 0);
+
+    // The phrase itself is untouched
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) =
+        common::send_json_request(app, common::get_request(&format!("/api/phrases/{first}"))).await;
+    assert_eq!(status, 200);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn deleting_phrase_cascades_membership() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let first = create_phrase(&pool, "first").await;
+    let second = create_phrase(&pool, "second").await;
+    let collection = create_collection(&pool, "essay").await;
+    add_phrase(&pool, &collection, json!({"phrase_id": first})).await;
+    add_phrase(&pool, &collection, json!({"phrase_id": second})).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    common::send_json_request(
+        app,
+        common::delete_request(&format!("/api/phrases/{first}")),
+    )
+    .await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/collections/{collection}")),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(json["phrase_count"], 1);
+    assert_eq!(member_phrases(&json), vec!["second"]);
+    assert_eq!(json["phrases"][0]["position"], 0);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn update_and_delete_collection() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let collection = create_collection(&pool, "essay").await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::json_put(
+            &format!("/api/collections/{collection}"),
+            &json!({"name": "thesis"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(json["name"], "thesis");
+    assert_eq!(json["description"], "curated"); // unchanged
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::delete_request(&format!("/api/collections/{collection}")),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/collections/{collection}")),
+    )
+    .await;
+    assert_eq!(status, 404);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn text_search_filtered_by_collection() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let inside = create_phrase(&pool, "rain on the window").await;
+    create_phrase(&pool, "rain in the city").await;
+    let collection = create_collection(&pool, "weather").await;
+    add_phrase(&pool, &collection, json!({"phrase_id": inside})).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::get_request(&format!(
+            "/api/search/text?q=rain&collection_id={collection}"
+        )),
+    )
+    .await;
+    assert_eq!(status, 200);
+    let results = json.as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["phrase"], "rain on the window");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn semantic_search_filtered_by_collection() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let inside = create_phrase(&pool, "inside").await;
+    create_phrase(&pool, "outside").await;
+    let collection = create_collection(&pool, "picked").await;
+    add_phrase(&pool, &collection, json!({"phrase_id": inside})).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"query": "anything", "collection_id": collection});
+    let (status, json) =
+        common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+    assert_eq!(status, 200);
+    let results = json.as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["phrase"], "inside");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn export_filtered_by_collection() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let inside = create_phrase(&pool, "inside").await;
+    create_phrase(&pool, "outside").await;
+    let collection = create_collection(&pool, "picked").await;
+    add_phrase(&pool, &collection, json!({"phrase_id": inside})).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, body) = common::send_request(
+        app,
+        common::get_request(&format!("/api/export?collection_id={collection}")),
+    )
+    .await;
+    assert_eq!(status, 200);
+    let phrases: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(phrases.len(), 1);
+    assert_eq!(phrases[0]["phrase"], "inside");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::Request;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+fn preflight(origin: &str) -> Request<Body> {
+    Request::options("/api/phrases")
+        .header("origin", origin)
+        .header("access-control-request-method", "POST")
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn configured_origins_pass_preflight() {
+    // Preflight requests never reach the database
+    let pool = PgPool::connect_lazy("postgres://unused").unwrap();
+    let mut config = common::test_config();
+    config.cors.origins = vec!["http://localhost:5173".to_string()];
+
+    let app = common::build_test_app_with_config(pool.clone(), &config);
+    let response = app
+        .oneshot(preflight("http://localhost:5173"))
+        .await
+        .unwrap();
+    let headers = response.headers();
+    assert_eq!(
+        headers["access-control-allow-origin"],
+        "http://localhost:5173"
+    );
+    assert_eq!(headers["access-control-allow-credentials"], "true");
+
+    let app = common::build_test_app_with_config(pool, &config);
+    let response = app
+        .oneshot(preflight("https://evil.example"))
+        .await
+        .unwrap();
+    assert!(
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn no_cors_headers_by_default() {
+    let pool = PgPool::connect_lazy("postgres://unused").unwrap();
+    let app = common::build_test_app(pool);
+    let response = app
+        .oneshot(preflight("http://localhost:5173"))
+        .await
+        .unwrap();
+    assert!(
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none()
+    );
+}
@@ -1,6 +1,7 @@
 mod common;
 
 use serde_json::json;
+use sqlx::ConnectOptions;
 
 #[tokio::test]
 async fn export_json_default() {
@@ -110,3 +111,192 @@ async fn export_empty_database() {
     pool.close().await;
     common::teardown_test_db(&db_name).await;
 }
+
+async fn seed(pool: &sqlx::PgPool, phrase: &str, tags: &[&str], created_at: &str) {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": phrase, "meanings": ["m"], "tags": tags});
+    let (_, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    sqlx::query("UPDATE phrases SET created_at = $2::timestamptz WHERE id = $1::uuid")
+        .bind(created["id"].as_str().unwrap())
+        .bind(created_at)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+async fn export_ndjson(pool: &sqlx::PgPool, params: &str) -> Vec<serde_json::Value> {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let uri = format!("/api/export?format=ndjson{params}");
+    let response = tower::ServiceExt::oneshot(app, common::get_request(&uri))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let content_type = response.headers()["content-type"].to_str().unwrap();
+    assert!(content_type.contains("application/x-ndjson"));
+
+    let body = http_body_util::BodyExt::collect(response.into_body())
+        .await
+        .unwrap()
+        .to_bytes();
+    String::from_utf8_lossy(&body)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn export_ndjson_newest_first() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed(&pool, "older", &[], "2025-01-01T00:00:00Z").await;
+    seed(&pool, "newer", &[], "2025-03-01T00:00:00Z").await;
+
+    let phrases = export_ndjson(&pool, "").await;
+    let names: Vec<&str> = phrases
+        .iter()
+        .map(|p| p["phrase"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["newer", "older"]);
+    assert_eq!(phrases[0]["meanings"], json!(["m"]));
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn export_filters_by_tags_and_date_range() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed(&pool, "jan", &["idiom"], "2025-01-15T00:00:00Z").await;
+    seed(&pool, "feb", &["idiom"], "2025-02-15T00:00:00Z").await;
+    seed(&pool, "feb-slang", &["slang"], "2025-02-20T00:00:00Z").await;
+    seed(&pool, "mar", &["idiom"], "2025-03-15T00:00:00Z").await;
+
+    let names = |phrases: Vec<serde_json::Value>| -> Vec<String> {
+        phrases
+            .iter()
+            .map(|p| p["phrase"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let range = "&from=2025-02-01T00:00:00Z&to=2025-03-01T00:00:00Z";
+    assert_eq!(
+        names(export_ndjson(&pool, range).await),
+        vec!["feb-slang", "feb"]
+    );
+    assert_eq!(
+        names(export_ndjson(&pool, &format!("{range}&tags=idiom")).await),
+        vec!["feb"]
+    );
+    assert_eq!(
+        names(export_ndjson(&pool, "&tags=idiom&from=2025-02-15T00:00:00Z").await),
+        vec!["mar", "feb"]
+    );
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn export_rejects_inverted_date_range() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_request(
+        app,
+        common::get_request("/api/export?from=2025-03-01T00:00:00Z&to=2025-02-01T00:00:00Z"),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn export_anki_package() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed(&pool, "kept", &["idiom"], "2025-01-01T00:00:00Z").await;
+    seed(&pool, "filtered out", &["slang"], "2025-01-02T00:00:00Z").await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let response = tower::ServiceExt::oneshot(
+        app,
+        common::get_request("/api/export?format=anki&tags=idiom"),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    let content_disp = response.headers()["content-disposition"].to_str().unwrap();
+    assert!(content_disp.contains("eemee.apkg"));
+    let body = http_body_util::BodyExt::collect(response.into_body())
+        .await
+        .unwrap()
+        .to_bytes();
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(body)).unwrap();
+    let mut collection = Vec::new();
+    std::io::Read::read_to_end(
+        &mut archive.by_name("collection.anki2").unwrap(),
+        &mut collection,
+    )
+    .unwrap();
+    let path = std::env::temp_dir().join(format!("{db_name}.anki2"));
+    std::fs::write(&path, collection).unwrap();
+
+    let mut conn = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(&path)
+        .connect()
+        .await
+        .unwrap();
+    let notes: Vec<(String, String)> = sqlx::query_as("SELECT sfld, tags FROM notes")
+        .fetch_all(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(notes, vec![("kept".to_string(), " idiom ".to_string())]);
+    sqlx::Connection::close(conn).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn export_markdown_zip() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed(&pool, "rain check", &["idiom"], "2025-01-01T00:00:00Z").await;
+    seed(
+        &pool,
+        "under the weather",
+        &["idiom"],
+        "2025-01-02T00:00:00Z",
+    )
+    .await;
+
+    for (group, expected) in [
+        (
+            "phrase",
+            vec!["eemee/rain check.md", "eemee/under the weather.md"],
+        ),
+        ("source", vec!["eemee/No source.md"]),
+    ] {
+        let app = common::build_test_app_authenticated(pool.clone());
+        let uri = format!("/api/export?format=markdown&group={group}");
+        let response = tower::ServiceExt::oneshot(app, common::get_request(&uri))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "application/zip");
+        let body = http_body_util::BodyExt::collect(response.into_body())
+            .await
+            .unwrap()
+            .to_bytes();
+
+        let archive = zip::ZipArchive::new(std::io::Cursor::new(body)).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(names, expected);
+    }
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
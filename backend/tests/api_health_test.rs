@@ -0,0 +1,92 @@
+mod common;
+
+use std::sync::Arc;
+
+#[tokio::test]
+async fn live_needs_no_login_or_dependencies() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let app = common::build_test_app(pool.clone());
+
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/health/live")).await;
+    assert_eq!(status, 200);
+    assert_eq!(json["status"], "alive");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn ready_reports_each_dependency() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let app = common::build_test_app(pool.clone());
+
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/health/ready")).await;
+    assert_eq!(status, 200, "{json}");
+    assert_eq!(json["status"], "ready");
+    assert_eq!(json["checks"]["database"]["status"], "ok");
+    assert_eq!(json["checks"]["migrations"]["status"], "ok");
+    assert_eq!(json["checks"]["embedding"]["status"], "skipped");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn ready_fails_with_pending_migrations() {
+    let (pool, db_name) = common::setup_test_db().await;
+    sqlx::query(
+        "DELETE FROM _sqlx_migrations
+         WHERE version = (SELECT max(version) FROM _sqlx_migrations)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let app = common::build_test_app(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/health/ready")).await;
+    assert_eq!(status, 503);
+    assert_eq!(json["status"], "not_ready");
+    assert_eq!(json["checks"]["database"]["status"], "ok");
+    assert_eq!(json["checks"]["migrations"]["status"], "failed");
+    let error = json["checks"]["migrations"]["error"].as_str().unwrap();
+    assert!(error.starts_with("1 migration(s) pending"), "{error}");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn ready_probes_the_embedder_when_enabled() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let mut config = common::test_config();
+    config.health.probe_embedder = true;
+
+    let app = common::build_test_app_with_config(pool.clone(), &config);
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/health/ready")).await;
+    assert_eq!(status, 200, "{json}");
+    assert_eq!(json["checks"]["embedding"]["status"], "ok");
+    // The probe is not an embedding call, so nothing is billed
+    let recorded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM embedding_usage")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(recorded, 0);
+
+    let app = common::build_test_app_with(pool.clone(), Arc::new(common::FailingEmbedder), &config);
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/health/ready")).await;
+    assert_eq!(status, 503);
+    assert_eq!(json["checks"]["embedding"]["status"], "failed");
+    // Provider errors can carry account details; the public endpoint doesn't repeat them
+    assert_eq!(
+        json["checks"]["embedding"]["error"],
+        "Embedding request failed"
+    );
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request};
+use serde_json::json;
+
+const CLIPPINGS: &str = "\u{feff}The Remains of the Day (Kazuo Ishiguro)
+- Your Highlight on page 12 | Location 170-172 | Added on Sunday, March 3, 2019 10:21:33 PM
+
+a sense of dignity
+==========
+The Remains of the Day (Kazuo Ishiguro)
+- Your Note on Location 172 | Added on Sunday, March 3, 2019 10:22:00 PM
+
+my own note
+==========
+ノルウェイの森 (村上春樹)
+- 位置No. 120-121のハイライト |作成日: 2019年3月4日月曜日 9:05:00
+
+死は生の対極としてではなく
+==========
+";
+
+async fn import(pool: &sqlx::PgPool, clippings: &str) -> serde_json::Value {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/import/kindle")
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(Body::from(clippings.to_string()))
+        .unwrap();
+    let (status, body) = common::send_json_request(app, request).await;
+    assert_eq!(status, 200, "{body}");
+    body
+}
+
+#[tokio::test]
+async fn imports_highlights_as_drafts_once() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let summary = import(&pool, CLIPPINGS).await;
+    assert_eq!(summary["imported"], 2);
+    assert_eq!(summary["duplicates"], 0);
+    assert_eq!(summary["skipped"], 1);
+
+    let id = summary["phrase_ids"][0].as_str().unwrap();
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, phrase) =
+        common::send_json_request(app, common::get_request(&format!("/api/phrases/{id}"))).await;
+    assert_eq!(status, 200);
+    assert_eq!(phrase["phrase"], "a sense of dignity");
+    assert_eq!(phrase["source"], "The Remains of the Day (Kazuo Ishiguro)");
+    assert_eq!(phrase["memo"], "Kindle: page 12, location 170-172");
+    assert_eq!(phrase["meanings"], json!([]));
+    assert_eq!(phrase["created_at"], "2019-03-03T22:21:33Z");
+
+    let again = import(&pool, CLIPPINGS).await;
+    assert_eq!(again["imported"], 0);
+    assert_eq!(again["duplicates"], 2);
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM phrases")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn drafts_are_searchable_once_they_have_a_meaning() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let summary = import(&pool, CLIPPINGS).await;
+    let id = summary["phrase_ids"][0].as_str().unwrap().to_string();
+
+    let search = |pool: sqlx::PgPool| async move {
+        let app = common::build_test_app_authenticated(pool);
+        let body = json!({"query": "dignity", "limit": 10});
+        let (status, results) =
+            common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+        assert_eq!(status, 200);
+        results.as_array().unwrap().len()
+    };
+    assert_eq!(search(pool.clone()).await, 0);
+
+    // A draft cannot be used as an example until it has a meaning
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"examples": [{"phrase_id": id}]});
+    let (status, _) =
+        common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+    assert_eq!(status, 400);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"meanings": ["self-respect"]});
+    let (status, phrase) =
+        common::send_json_request(app, common::json_put(&format!("/api/phrases/{id}"), &body))
+            .await;
+    assert_eq!(status, 200);
+    assert_eq!(phrase["meanings"], json!(["self-respect"]));
+    assert_eq!(search(pool.clone()).await, 1);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
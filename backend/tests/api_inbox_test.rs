@@ -0,0 +1,77 @@
+mod common;
+
+use serde_json::json;
+
+async fn create_phrase(pool: &sqlx::PgPool, body: serde_json::Value) -> String {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    assert_eq!(status, 200, "{created}");
+    created["id"].as_str().unwrap().to_string()
+}
+
+async fn inbox(pool: &sqlx::PgPool, query: &str) -> Vec<String> {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request(&format!("/api/inbox{query}"))).await;
+    assert_eq!(status, 200);
+    json.as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["phrase"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn semantic_search(pool: &sqlx::PgPool) -> Vec<String> {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"query": "anything", "limit": 10});
+    let (status, json) =
+        common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+    assert_eq!(status, 200);
+    json.as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["phrase"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn drafts_wait_in_the_inbox_until_they_have_a_meaning() {
+    let (pool, db_name) = common::setup_test_db().await;
+    create_phrase(&pool, json!({"phrase": "complete", "meanings": ["done"]})).await;
+    let draft = create_phrase(
+        &pool,
+        json!({"phrase": "on the fence", "meanings": [], "source": "podcast"}),
+    )
+    .await;
+    create_phrase(&pool, json!({"phrase": "at a loss", "meanings": []})).await;
+
+    assert_eq!(inbox(&pool, "").await, vec!["at a loss", "on the fence"]);
+    assert_eq!(inbox(&pool, "?source=podcast").await, vec!["on the fence"]);
+    assert_eq!(inbox(&pool, "?limit=1").await, vec!["at a loss"]);
+    assert_eq!(semantic_search(&pool).await, vec!["complete"]);
+
+    let embedded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM phrase_meanings")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(embedded, 1);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"meanings": ["undecided"]});
+    let (status, promoted) = common::send_json_request(
+        app,
+        common::json_put(&format!("/api/phrases/{draft}"), &body),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(promoted["meanings"], json!(["undecided"]));
+
+    assert_eq!(inbox(&pool, "").await, vec!["at a loss"]);
+    let mut found = semantic_search(&pool).await;
+    found.sort();
+    assert_eq!(found, vec!["complete", "on the fence"]);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -0,0 +1,231 @@
+mod common;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use eemee_backend::error::AppError;
+use eemee_backend::services::embedding::{Embedder, Embedding};
+use eemee_backend::services::jobs::{self, MAX_ATTEMPTS};
+use serde_json::json;
+use sqlx::PgPool;
+use tokio_util::sync::CancellationToken;
+
+async fn create_phrase(pool: &sqlx::PgPool, phrase: &str) -> serde_json::Value {
+    let app = common::build_test_app_without_workers(pool.clone());
+    let body = json!({"phrase": phrase, "meanings": ["first", "second"]});
+    let (status, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    assert_eq!(status, 200, "{created}");
+    created
+}
+
+async fn get_json(pool: &sqlx::PgPool, uri: &str) -> serde_json::Value {
+    let app = common::build_test_app_without_workers(pool.clone());
+    let (status, json) = common::send_json_request(app, common::get_request(uri)).await;
+    assert_eq!(status, 200, "{json}");
+    json
+}
+
+async fn search_count(pool: &sqlx::PgPool) -> usize {
+    let app = common::build_test_app_without_workers(pool.clone());
+    let body = json!({"query": "anything"});
+    let (status, json) =
+        common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+    assert_eq!(status, 200, "{json}");
+    json.as_array().unwrap().len()
+}
+
+/// Checks that the job being embedded is not handed out again, then queues every meaning
+/// afresh as an edit would, before answering like [`common::FakeEmbedder`].
+struct RequeuingEmbedder(PgPool);
+
+impl Embedder for RequeuingEmbedder {
+    fn model(&self) -> &str {
+        common::TEST_MODEL
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    fn embed<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<Embedding, AppError>> + Send + 'a>> {
+        Box::pin(async move {
+            assert!(!jobs::run_next(&self.0, &common::FailingEmbedder).await?);
+            jobs::requeue_meanings(&self.0, common::TEST_MODEL, true).await?;
+            common::FakeEmbedder.embed(text).await
+        })
+    }
+}
+
+/// Makes jobs waiting out a retry delay due now.
+async fn skip_retry_delay(pool: &sqlx::PgPool) {
+    sqlx::query("UPDATE embedding_jobs SET run_at = now()")
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn phrases_are_saved_pending_and_searchable_once_embedded() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let created = create_phrase(&pool, "rain check").await;
+    assert_eq!(created["status"], "pending");
+    assert_eq!(created["meanings"], json!(["first", "second"]));
+    assert_eq!(search_count(&pool).await, 0);
+
+    assert_eq!(
+        common::drain_jobs_with(&pool, &common::FakeEmbedder).await,
+        2
+    );
+    let id = created["id"].as_str().unwrap();
+    let phrase = get_json(&pool, &format!("/api/phrases/{id}")).await;
+    assert_eq!(phrase["status"], "ready");
+    assert_eq!(search_count(&pool).await, 1);
+
+    let models: Vec<String> = sqlx::query_scalar("SELECT embedding_model FROM phrase_meanings")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(models, vec![common::TEST_MODEL; 2]);
+    assert_eq!(get_json(&pool, "/api/admin/jobs").await, json!([]));
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn failing_jobs_retry_then_dead_letter_until_retried() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let created = create_phrase(&pool, "under the weather").await;
+    let id = created["id"].as_str().unwrap();
+
+    common::drain_jobs_with(&pool, &common::FailingEmbedder).await;
+    let queued = get_json(&pool, "/api/admin/jobs?status=queued").await;
+    assert_eq!(queued.as_array().unwrap().len(), 2);
+    assert_eq!(queued[0]["attempts"], 1);
+    assert!(
+        queued[0]["last_error"]
+            .as_str()
+            .unwrap()
+            .contains("service unavailable")
+    );
+    // Not due again until the retry delay has passed
+    assert_eq!(
+        common::drain_jobs_with(&pool, &common::FailingEmbedder).await,
+        0
+    );
+
+    for _ in 1..MAX_ATTEMPTS {
+        skip_retry_delay(&pool).await;
+        common::drain_jobs_with(&pool, &common::FailingEmbedder).await;
+    }
+    let dead = get_json(&pool, "/api/admin/jobs?status=dead").await;
+    let dead = dead.as_array().unwrap();
+    assert_eq!(dead.len(), 2);
+    assert_eq!(dead[0]["attempts"], MAX_ATTEMPTS);
+    assert_eq!(dead[0]["phrase_id"], id);
+    let phrase = get_json(&pool, &format!("/api/phrases/{id}")).await;
+    assert_eq!(phrase["status"], "failed");
+
+    // Dead jobs are left alone by the workers
+    skip_retry_delay(&pool).await;
+    assert_eq!(
+        common::drain_jobs_with(&pool, &common::FakeEmbedder).await,
+        0
+    );
+
+    for job in dead {
+        let app = common::build_test_app_without_workers(pool.clone());
+        let uri = format!("/api/admin/jobs/{}/retry", job["id"].as_str().unwrap());
+        let (status, _) = common::send_json_request(app, common::json_post(&uri, &json!({}))).await;
+        assert_eq!(status, 200);
+    }
+    let phrase = get_json(&pool, &format!("/api/phrases/{id}")).await;
+    assert_eq!(phrase["status"], "pending");
+    assert_eq!(
+        common::drain_jobs_with(&pool, &common::FakeEmbedder).await,
+        2
+    );
+    let phrase = get_json(&pool, &format!("/api/phrases/{id}")).await;
+    assert_eq!(phrase["status"], "ready");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn retrying_an_unknown_job_is_not_found() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let app = common::build_test_app_without_workers(pool.clone());
+    let uri = format!("/api/admin/jobs/{}/retry", uuid::Uuid::new_v4());
+    let (status, _) = common::send_json_request(app, common::json_post(&uri, &json!({}))).await;
+    assert_eq!(status, 404);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn workers_embed_then_stop_on_cancel() {
+    let (pool, db_name) = common::setup_test_db().await;
+    create_phrase(&pool, "rain check").await;
+
+    let token = CancellationToken::new();
+    let workers = jobs::spawn_workers(
+        pool.clone(),
+        Arc::new(common::FakeEmbedder),
+        2,
+        token.clone(),
+    );
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while search_count(&pool).await == 0 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("workers embedded the meanings");
+
+    token.cancel();
+    for worker in workers {
+        tokio::time::timeout(Duration::from_secs(5), worker)
+            .await
+            .expect("worker stopped")
+            .unwrap();
+    }
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn results_for_jobs_queued_again_mid_call_are_dropped() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let created = create_phrase(&pool, "rain check").await;
+
+    assert!(
+        jobs::run_next(&pool, &RequeuingEmbedder(pool.clone()))
+            .await
+            .unwrap()
+    );
+    let id = created["id"].as_str().unwrap();
+    let phrase = get_json(&pool, &format!("/api/phrases/{id}")).await;
+    assert_eq!(phrase["status"], "pending");
+    let queued = get_json(&pool, "/api/admin/jobs?status=queued").await;
+    assert_eq!(queued[0]["attempts"], 0);
+
+    assert_eq!(
+        common::drain_jobs_with(&pool, &common::FakeEmbedder).await,
+        2
+    );
+    let phrase = get_json(&pool, &format!("/api/phrases/{id}")).await;
+    assert_eq!(phrase["status"], "ready");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
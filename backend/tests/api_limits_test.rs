@@ -0,0 +1,121 @@
+mod common;
+
+use axum::http::StatusCode;
+use eemee_backend::services::usage;
+use serde_json::json;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn embedding_routes_are_rate_limited_per_user() {
+    // k = 0 is refused before anything reaches the database
+    let pool = PgPool::connect_lazy("postgres://unused").unwrap();
+    let mut config = common::test_config();
+    config.limits.per_user_per_minute = 2;
+    let app = common::build_test_app_without_workers_with_config(pool, &config);
+    let body = json!({"meanings": ["light rain"], "k": 0});
+
+    for _ in 0..2 {
+        let (status, _) =
+            common::send_json_request(app.clone(), common::json_post("/api/tags/suggest", &body))
+                .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let response = app
+        .oneshot(common::json_post("/api/tags/suggest", &body))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["retry-after"], "30");
+}
+
+#[tokio::test]
+async fn embedding_calls_are_recorded_in_the_ledger() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let app = common::build_test_app_authenticated(pool.clone());
+
+    let response = app
+        .oneshot(common::json_post(
+            "/api/search/semantic",
+            &json!({"query": "water falling from the sky"}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let request_id = response.headers()["x-request-id"].to_str().unwrap();
+
+    let (model, tokens, recorded_id): (String, i64, Option<String>) =
+        sqlx::query_as("SELECT model, tokens, request_id FROM embedding_usage")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(model, common::TEST_MODEL);
+    assert_eq!(tokens, 5);
+    assert_eq!(recorded_id.as_deref(), Some(request_id));
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn semantic_search_is_refused_once_the_budget_is_used_up() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let mut config = common::test_config();
+    config.limits.daily_token_budget = Some(10);
+    usage::record(&pool, common::TEST_MODEL, 10, None)
+        .await
+        .unwrap();
+
+    let app = common::build_test_app_without_workers_with_config(pool.clone(), &config);
+    let (status, json) = common::send_json_request(
+        app.clone(),
+        common::json_post("/api/search/semantic", &json!({"query": "rain"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+    assert!(
+        json["error"]
+            .as_str()
+            .unwrap()
+            .contains("daily embedding budget of 10 tokens"),
+        "{json}"
+    );
+
+    // Text search does not embed, so it keeps working
+    let (status, _) =
+        common::send_json_request(app, common::get_request("/api/search/text?q=rain")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn usage_reports_totals_and_budgets() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let mut config = common::test_config();
+    config.limits.monthly_token_budget = Some(1000);
+    usage::record(&pool, common::TEST_MODEL, 12, None)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO embedding_usage (model, tokens, created_at)
+         VALUES ('old-model', 500, now() - interval '400 days')",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let app = common::build_test_app_without_workers_with_config(pool.clone(), &config);
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/admin/usage")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        json,
+        json!({"today": 12, "this_month": 12, "daily_budget": null, "monthly_budget": 1000})
+    );
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -0,0 +1,205 @@
+mod common;
+
+use serde_json::json;
+
+async fn create_phrase(pool: &sqlx::PgPool, phrase: &str) -> String {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": phrase, "meanings": [format!("meaning of {phrase}")]});
+    let (_, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    created["id"].as_str().unwrap().to_string()
+}
+
+async fn link(
+    pool: &sqlx::PgPool,
+    from: &str,
+    to: &str,
+    link_type: &str,
+) -> (axum::http::StatusCode, serde_json::Value) {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"target_id": to, "link_type": link_type, "note": "noted"});
+    common::send_json_request(
+        app,
+        common::json_post(&format!("/api/phrases/{from}/links"), &body),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn create_link_and_show_on_both_phrases() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let happy = create_phrase(&pool, "happy").await;
+    let glad = create_phrase(&pool, "glad").await;
+
+    let (status, json) = link(&pool, &happy, &glad, "synonym").await;
+    assert_eq!(status, 200);
+    assert_eq!(json["link_type"], "synonym");
+    assert_eq!(json["direction"], "outgoing");
+    assert_eq!(json["phrase"]["phrase"], "glad");
+    assert_eq!(json["note"], "noted");
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request(&format!("/api/phrases/{glad}"))).await;
+    assert_eq!(status, 200);
+    assert_eq!(json["phrase"], "glad");
+    let links = json["links"].as_array().unwrap();
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0]["direction"], "incoming");
+    assert_eq!(links[0]["phrase"]["id"], happy.as_str());
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn create_link_rejects_self_duplicate_and_unknown_type() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let happy = create_phrase(&pool, "happy").await;
+    let sad = create_phrase(&pool, "sad").await;
+
+    let (status, _) = link(&pool, &happy, &happy, "synonym").await;
+    assert_eq!(status, 400);
+
+    let (status, _) = link(&pool, &happy, &sad, "antonym").await;
+    assert_eq!(status, 200);
+    let (status, _) = link(&pool, &happy, &sad, "antonym").await;
+    assert_eq!(status, 400);
+
+    let (status, _) = link(&pool, &happy, &sad, "cousin").await;
+    assert_eq!(status, 422);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn create_link_to_unknown_phrase_returns_404() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let happy = create_phrase(&pool, "happy").await;
+
+    let (status, _) = link(&pool, &happy, &uuid::Uuid::new_v4().to_string(), "see_also").await;
+    assert_eq!(status, 404);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn delete_link() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let happy = create_phrase(&pool, "happy").await;
+    let glad = create_phrase(&pool, "glad").await;
+    let (_, created) = link(&pool, &happy, &glad, "synonym").await;
+    let link_id = created["id"].as_str().unwrap();
+
+    // Deletable from either end
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::delete_request(&format!("/api/phrases/{glad}/links/{link_id}")),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, json) =
+        common::send_json_request(app, common::get_request(&format!("/api/phrases/{happy}"))).await;
+    assert!(json["links"].as_array().unwrap().is_empty());
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::delete_request(&format!("/api/phrases/{happy}/links/{link_id}")),
+    )
+    .await;
+    assert_eq!(status, 404);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn deleting_phrase_cascades_links() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let happy = create_phrase(&pool, "happy").await;
+    let glad = create_phrase(&pool, "glad").await;
+    link(&pool, &happy, &glad, "synonym").await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    common::send_json_request(app, common::delete_request(&format!("/api/phrases/{glad}"))).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, json) =
+        common::send_json_request(app, common::get_request(&format!("/api/phrases/{happy}"))).await;
+    assert!(json["links"].as_array().unwrap().is_empty());
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn graph_respects_depth() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let a = create_phrase(&pool, "a").await;
+    let b = create_phrase(&pool, "b").await;
+    let c = create_phrase(&pool, "c").await;
+    create_phrase(&pool, "unrelated").await;
+    link(&pool, &a, &b, "synonym").await;
+    link(&pool, &c, &b, "variation").await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request(&format!("/api/phrases/{a}/graph")))
+            .await;
+    assert_eq!(status, 200);
+    assert_eq!(json["nodes"].as_array().unwrap().len(), 2);
+    assert_eq!(json["edges"].as_array().unwrap().len(), 1);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/phrases/{a}/graph?depth=2")),
+    )
+    .await;
+    assert_eq!(status, 200);
+    let nodes = json["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(nodes[0]["phrase"], "a");
+    assert_eq!(nodes[0]["depth"], 0);
+    assert_eq!(nodes[2]["phrase"], "c");
+    assert_eq!(nodes[2]["depth"], 2);
+    let edges = json["edges"].as_array().unwrap();
+    assert_eq!(edges.len(), 2);
+    assert_eq!(edges[1]["from"], c.as_str());
+    assert_eq!(edges[1]["link_type"], "variation");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn graph_invalid_depth_and_unknown_phrase() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let a = create_phrase(&pool, "a").await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/phrases/{a}/graph?depth=10")),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let fake_id = uuid::Uuid::new_v4();
+    let (status, _) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/phrases/{fake_id}/graph")),
+    )
+    .await;
+    assert_eq!(status, 404);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
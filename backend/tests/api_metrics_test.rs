@@ -0,0 +1,52 @@
+mod common;
+
+use serde_json::json;
+use sqlx::PgPool;
+
+#[tokio::test]
+async fn metrics_are_not_served_unless_exposed() {
+    let pool = PgPool::connect_lazy("postgres://unused").unwrap();
+    let app = common::build_test_app(pool);
+    let (status, _) = common::send_request(app, common::get_request("/metrics")).await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test]
+async fn metrics_cover_requests_searches_and_library() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": "rain", "meanings": ["water falling"]});
+    let (status, _) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    assert_eq!(status, 200);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"query": "weather"});
+    let (status, _) =
+        common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+    assert_eq!(status, 200);
+
+    // Served outside /api, without a login
+    let mut config = common::test_config();
+    config.expose_metrics = true;
+    let app = common::build_test_app_with_config(pool.clone(), &config);
+    let (status, body) = common::send_request(app, common::get_request("/metrics")).await;
+    assert_eq!(status, 200);
+    let text = String::from_utf8(body.to_vec()).unwrap();
+
+    for expected in [
+        "eemee_http_requests_total{method=\"POST\",route=\"/api/phrases\",status=\"200\"} 1",
+        "eemee_http_request_duration_seconds_count{method=\"POST\",route=\"/api/search/semantic\"} 1",
+        "eemee_searches_total{mode=\"semantic\"} 1",
+        "eemee_phrases 1",
+        "eemee_meanings{status=\"ready\"} 1",
+        "eemee_meanings{status=\"pending\"} 0",
+        "eemee_db_pool_connections{state=\"max\"}",
+    ] {
+        assert!(text.contains(expected), "missing {expected} in:\n{text}");
+    }
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
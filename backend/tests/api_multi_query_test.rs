@@ -0,0 +1,167 @@
+mod common;
+
+use serde_json::json;
+
+async fn create_phrase(pool: &sqlx::PgPool, phrase: &str, meanings: &[&str]) -> String {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": phrase, "meanings": meanings});
+    let (_, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    created["id"].as_str().unwrap().to_string()
+}
+
+async fn create_with_embedding(
+    pool: &sqlx::PgPool,
+    phrase: &str,
+    components: &[(usize, f32)],
+) -> String {
+    let id = create_phrase(pool, phrase, &[phrase]).await;
+    common::set_phrase_embedding(pool, &id, components).await;
+    id
+}
+
+async fn search(pool: &sqlx::PgPool, body: serde_json::Value) -> Vec<String> {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+    assert_eq!(status, 200, "{json}");
+    json.as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["phrase"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn examples_find_neighbours_and_are_excluded() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let north = create_with_embedding(&pool, "north", &[(0, 1.0)]).await;
+    create_with_embedding(&pool, "north by east", &[(0, 1.0), (1, 0.2)]).await;
+    create_with_embedding(&pool, "south", &[(0, -1.0)]).await;
+
+    let results = search(
+        &pool,
+        json!({"examples": [{"phrase_id": north}], "limit": 2}),
+    )
+    .await;
+    assert_eq!(results, vec!["north by east", "south"]);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn negative_example_steers_away() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let north = create_with_embedding(&pool, "north", &[(0, 1.0)]).await;
+    let east = create_with_embedding(&pool, "east", &[(1, 1.0)]).await;
+    let cold = create_with_embedding(&pool, "cold", &[(2, 1.0)]).await;
+    create_with_embedding(&pool, "cold northeast", &[(0, 1.0), (1, 1.0), (2, 0.3)]).await;
+    create_with_embedding(&pool, "northeast-ish", &[(0, 1.0), (1, 0.6)]).await;
+
+    // Like north and east
+    let body = json!({
+        "examples": [{"phrase_id": north}, {"phrase_id": east}],
+        "limit": 2,
+    });
+    assert_eq!(
+        search(&pool, body).await,
+        vec!["cold northeast", "northeast-ish"]
+    );
+
+    // ...but not cold
+    let body = json!({
+        "examples": [
+            {"phrase_id": north},
+            {"phrase_id": east},
+            {"phrase_id": cold, "weight": -1.0},
+        ],
+        "limit": 2,
+    });
+    assert_eq!(
+        search(&pool, body).await,
+        vec!["northeast-ish", "cold northeast"]
+    );
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn sum_rewards_closeness_to_each_vector() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let north = create_with_embedding(&pool, "north", &[(0, 1.0)]).await;
+    let east = create_with_embedding(&pool, "east", &[(1, 1.0)]).await;
+    create_with_embedding(&pool, "northeast", &[(0, 1.0), (1, 1.0)]).await;
+    // One meaning matches each example exactly
+    let both = create_phrase(&pool, "compass", &["pointing north", "pointing east"]).await;
+    common::set_meaning_embedding(&pool, &both, "pointing north", &[(0, 1.0)]).await;
+    common::set_meaning_embedding(&pool, &both, "pointing east", &[(1, 1.0)]).await;
+
+    let examples = json!([{"phrase_id": north}, {"phrase_id": east}]);
+    assert_eq!(
+        search(&pool, json!({"examples": examples, "limit": 1})).await,
+        vec!["northeast"]
+    );
+    assert_eq!(
+        search(
+            &pool,
+            json!({"examples": examples, "combine": "sum", "limit": 1})
+        )
+        .await,
+        vec!["compass"]
+    );
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn weighted_text_queries() {
+    let (pool, db_name) = common::setup_test_db().await;
+    create_phrase(&pool, "drizzle", &["light rain"]).await;
+    create_phrase(&pool, "blizzard", &["heavy snow"]).await;
+
+    let body = json!({
+        "queries": [{"text": "rain"}, {"text": "snow", "weight": 0.5}],
+        "limit": 5,
+        "record": true,
+    });
+    assert_eq!(search(&pool, body).await.len(), 2);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, history) =
+        common::send_json_request(app, common::get_request("/api/search/history")).await;
+    assert_eq!(history[0]["query"], "rain; snow (0.5)");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn invalid_query_combinations() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let id = create_phrase(&pool, "north", &["up"]).await;
+
+    for body in [
+        json!({}),
+        json!({"queries": [{"text": "rain", "weight": -1.0}]}),
+        json!({"query": "rain", "queries": [{"text": " "}]}),
+        json!({"query": "rain", "queries": [{"text": "snow", "weight": 0.0}]}),
+        json!({"examples": [{"phrase_id": id}], "combine": "sum", "ef_search": 40}),
+    ] {
+        let app = common::build_test_app_authenticated(pool.clone());
+        let (status, _) =
+            common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+        assert_eq!(status, 400, "{body}");
+    }
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"examples": [{"phrase_id": uuid::Uuid::new_v4()}]});
+    let (status, _) =
+        common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+    assert_eq!(status, 404);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode, header};
+use eemee_backend::routes;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+/// `(METHOD, path)` for each route in `routes::api_routes`, read back from the `Allow`
+/// header each route answers an unknown method with.
+async fn served_routes() -> BTreeSet<(String, String)> {
+    let pool = PgPool::connect_lazy("postgres://unused").unwrap();
+    let state = common::test_state(
+        pool.clone(),
+        Arc::new(common::FakeEmbedder),
+        &common::test_config(),
+    );
+    let app = common::build_test_app_without_workers(pool);
+    let probe = Method::from_bytes(b"PROBE").unwrap();
+
+    let mut routes = BTreeSet::new();
+    for (path, _) in routes::api_routes(&state) {
+        let uri: Vec<&str> = path
+            .split('/')
+            .map(|segment| {
+                if segment.starts_with('{') {
+                    "00000000-0000-0000-0000-000000000000"
+                } else {
+                    segment
+                }
+            })
+            .collect();
+        let request = Request::builder()
+            .method(probe.clone())
+            .uri(format!("/api{}", uri.join("/")))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED, "{path}");
+        let allow = response.headers()[header::ALLOW].to_str().unwrap();
+        for method in allow.split(',').map(str::trim) {
+            // Added by axum for every GET
+            if method != "HEAD" {
+                routes.insert((method.to_string(), path.to_string()));
+            }
+        }
+    }
+    routes
+}
+
+async fn fetch_spec() -> serde_json::Value {
+    // The document is static; no query reaches the database
+    let pool = PgPool::connect_lazy("postgres://unused").unwrap();
+    let app = common::build_test_app(pool);
+    let (status, spec) =
+        common::send_json_request(app, common::get_request("/api/openapi.json")).await;
+    assert_eq!(status, 200, "served without a login");
+    spec
+}
+
+#[tokio::test]
+async fn every_route_is_documented() {
+    let spec = fetch_spec().await;
+    let mut documented = BTreeSet::new();
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for method in item.as_object().unwrap().keys() {
+            documented.insert((method.to_uppercase(), path.clone()));
+        }
+    }
+
+    let declared = served_routes().await;
+    assert!(declared.len() > 40, "found too few routes: {declared:?}");
+    let missing: Vec<_> = declared.difference(&documented).collect();
+    assert!(
+        missing.is_empty(),
+        "routes missing from the spec: {missing:?}"
+    );
+    let stale: Vec<_> = documented.difference(&declared).collect();
+    assert!(stale.is_empty(), "spec documents unknown routes: {stale:?}");
+}
+
+#[tokio::test]
+async fn errors_use_the_app_error_shape() {
+    let spec = fetch_spec().await;
+    assert_eq!(spec["openapi"], "3.1.0");
+
+    let error = &spec["components"]["schemas"]["ErrorBody"];
+    assert_eq!(error["required"], serde_json::json!(["error"]));
+    assert!(error["properties"]["request_id"].is_object());
+
+    let get_phrase = &spec["paths"]["/phrases/{id}"]["get"];
+    for status in ["401", "404", "500"] {
+        assert_eq!(
+            get_phrase["responses"][status]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/ErrorBody",
+            "{status}"
+        );
+    }
+    assert_eq!(
+        get_phrase["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/PhraseDetail"
+    );
+    assert_eq!(
+        get_phrase["security"],
+        serde_json::json!([{ "session": [] }, { "token": [] }])
+    );
+    assert_eq!(
+        spec["components"]["securitySchemes"]["token"],
+        serde_json::json!({ "type": "http", "scheme": "bearer" })
+    );
+
+    // Public routes need no session
+    let ready = &spec["paths"]["/health/ready"]["get"];
+    assert!(ready["responses"]["401"].is_null());
+    assert!(ready["security"].is_null());
+}
+
+#[tokio::test]
+async fn query_structs_become_query_parameters() {
+    let spec = fetch_spec().await;
+    let params = spec["paths"]["/search/text"]["get"]["parameters"]
+        .as_array()
+        .unwrap();
+    let names: Vec<_> = params.iter().map(|p| p["name"].as_str().unwrap()).collect();
+    // Including the flattened `PhraseFilter`
+    assert_eq!(
+        names,
+        ["q", "limit", "record", "collection_id", "tags", "source"]
+    );
+    assert!(params.iter().all(|p| p["in"] == "query"));
+    assert_eq!(params[0]["required"], true);
+    assert_eq!(params[1]["required"], false, "has a serde default");
+}
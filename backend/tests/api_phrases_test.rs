@@ -77,13 +77,13 @@ async fn create_phrase_missing_required_field() {
 }
 
 #[tokio::test]
-async fn create_phrase_empty_meanings_rejected() {
+async fn create_phrase_blank_meaning_rejected() {
     let (pool, db_name) = common::setup_test_db().await;
     let app = common::build_test_app_authenticated(pool.clone());
 
     let body = json!({
         "phrase": "hello",
-        "meanings": []
+        "meanings": ["greeting", "  "]
     });
 
     let (status, _) =
@@ -0,0 +1,61 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::Request;
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+
+async fn send(app: axum::Router, request: Request<Body>) -> (String, serde_json::Value) {
+    let response = app.oneshot(request).await.unwrap();
+    let id = response.headers()["x-request-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (id, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn error_body_carries_the_request_id() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let uri = format!("/api/phrases/{}", uuid::Uuid::new_v4());
+    let (id, body) = send(app, common::get_request(&uri)).await;
+    assert!(uuid::Uuid::parse_str(&id).is_ok());
+    assert_eq!(body["error"], "Not found");
+    assert_eq!(body["request_id"], id);
+
+    // Errors raised by middleware, before any handler runs, carry it too
+    let app = common::build_test_app(pool.clone());
+    let (id, body) = send(app, common::get_request("/api/phrases")).await;
+    assert_eq!(body["error"], "Unauthorized");
+    assert_eq!(body["request_id"], id);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn incoming_request_id_is_kept_when_valid() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let request = Request::get("/api/phrases")
+        .header("x-request-id", "lb-1234")
+        .body(Body::empty())
+        .unwrap();
+    let (id, _) = send(app, request).await;
+    assert_eq!(id, "lb-1234");
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let request = Request::get("/api/phrases")
+        .header("x-request-id", "not a valid id")
+        .body(Body::empty())
+        .unwrap();
+    let (id, _) = send(app, request).await;
+    assert!(uuid::Uuid::parse_str(&id).is_ok());
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -0,0 +1,232 @@
+mod common;
+
+use serde_json::json;
+
+async fn seed_phrases(pool: &sqlx::PgPool) {
+    for (phrase, meaning, tags) in [
+        ("petrichor", "the smell of rain", vec!["weather", "smell"]),
+        ("drizzle", "light rain", vec!["weather"]),
+        ("solitude", "being alone", vec!["feeling"]),
+    ] {
+        let app = common::build_test_app_authenticated(pool.clone());
+        let body = json!({"phrase": phrase, "meanings": [meaning], "tags": tags});
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    }
+}
+
+async fn create_saved_search(
+    pool: &sqlx::PgPool,
+    body: serde_json::Value,
+) -> (axum::http::StatusCode, serde_json::Value) {
+    let app = common::build_test_app_authenticated(pool.clone());
+    common::send_json_request(app, common::json_post("/api/saved-searches", &body)).await
+}
+
+async fn history(pool: &sqlx::PgPool) -> Vec<serde_json::Value> {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/search/history")).await;
+    assert_eq!(status, 200);
+    json.as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn searches_are_recorded_only_when_asked() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed_phrases(&pool).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    common::send_json_request(app, common::get_request("/api/search/text?q=rain")).await;
+    assert!(history(&pool).await.is_empty());
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    common::send_json_request(
+        app,
+        common::get_request("/api/search/text?q=rain&tags=weather&record=true"),
+    )
+    .await;
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"query": "loneliness", "limit": 1, "record": true});
+    common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+
+    let entries = history(&pool).await;
+    assert_eq!(entries.len(), 2);
+    // Newest first
+    assert_eq!(entries[0]["mode"], "semantic");
+    assert_eq!(entries[0]["query"], "loneliness");
+    assert_eq!(entries[0]["result_count"], 1);
+    assert_eq!(entries[1]["mode"], "text");
+    assert_eq!(entries[1]["result_count"], 2);
+    assert_eq!(entries[1]["filters"]["tags"], json!(["weather"]));
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) =
+        common::send_json_request(app, common::delete_request("/api/search/history")).await;
+    assert_eq!(status, 200);
+    assert!(history(&pool).await.is_empty());
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn saved_text_search_runs_with_its_filters() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed_phrases(&pool).await;
+
+    let body = json!({
+        "name": "rain smells",
+        "mode": "text",
+        "query": "rain",
+        "filters": {"tags": ["smell"]},
+    });
+    let (status, saved) = create_saved_search(&pool, body).await;
+    assert_eq!(status, 200);
+    assert_eq!(saved["mode"], "text");
+    assert_eq!(saved["has_embedding"], false);
+    let id = saved["id"].as_str().unwrap();
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/saved-searches/{id}/run?record=true")),
+    )
+    .await;
+    assert_eq!(status, 200);
+    let results = json.as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["phrase"], "petrichor");
+    assert!(results[0]["highlights"].is_array());
+
+    let entries = history(&pool).await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["query"], "rain");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn saved_semantic_search_caches_embedding() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed_phrases(&pool).await;
+
+    let body = json!({"name": "lonely", "mode": "semantic", "query": "loneliness"});
+    let (_, saved) = create_saved_search(&pool, body).await;
+    let id = saved["id"].as_str().unwrap();
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/saved-searches/{id}/run?limit=2")),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(json.as_array().unwrap().len(), 2);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/saved-searches/{id}")),
+    )
+    .await;
+    assert_eq!(json["has_embedding"], true);
+
+    // Renaming keeps the embedding, changing the query drops it
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, json) = common::send_json_request(
+        app,
+        common::json_put(
+            &format!("/api/saved-searches/{id}"),
+            &json!({"name": "alone"}),
+        ),
+    )
+    .await;
+    assert_eq!(json["name"], "alone");
+    assert_eq!(json["has_embedding"], true);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, json) = common::send_json_request(
+        app,
+        common::json_put(
+            &format!("/api/saved-searches/{id}"),
+            &json!({"query": "solitude"}),
+        ),
+    )
+    .await;
+    assert_eq!(json["query"], "solitude");
+    assert_eq!(json["has_embedding"], false);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn saved_search_validation() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let (status, _) =
+        create_saved_search(&pool, json!({"name": " ", "mode": "text", "query": "rain"})).await;
+    assert_eq!(status, 400);
+
+    let (status, json) = create_saved_search(
+        &pool,
+        json!({"name": "broken", "mode": "text", "query": "colour:red"}),
+    )
+    .await;
+    assert_eq!(status, 400);
+    assert_eq!(json["position"], json!({"start": 0, "end": 6}));
+
+    let (status, _) = create_saved_search(
+        &pool,
+        json!({"name": "x", "mode": "fuzzy", "query": "rain"}),
+    )
+    .await;
+    assert_eq!(status, 422);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn list_and_delete_saved_searches() {
+    let (pool, db_name) = common::setup_test_db().await;
+    create_saved_search(&pool, json!({"name": "b", "mode": "text", "query": "rain"})).await;
+    let (_, saved) = create_saved_search(
+        &pool,
+        json!({"name": "a", "mode": "semantic", "query": "rain"}),
+    )
+    .await;
+    let id = saved["id"].as_str().unwrap();
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/saved-searches")).await;
+    assert_eq!(status, 200);
+    let names: Vec<&str> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["a", "b"]);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::delete_request(&format!("/api/saved-searches/{id}")),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/saved-searches/{id}/run")),
+    )
+    .await;
+    assert_eq!(status, 404);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -121,6 +121,166 @@ async fn text_search_no_match() {
     common::teardown_test_db(&db_name).await;
 }
 
+#[tokio::test]
+async fn text_search_matches_half_width_katakana() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": "海辺のカフカ", "meanings": ["Kafka on the Shore"]});
+    common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+
+    for q in ["%EF%BD%B6%EF%BE%8C%EF%BD%B6", "%E3%81%8B%E3%81%B5%E3%81%8B"] {
+        // ｶﾌｶ and かふか
+        let app = common::build_test_app_authenticated(pool.clone());
+        let (status, json) =
+            common::send_json_request(app, common::get_request(&format!("/api/search/text?q={q}")))
+                .await;
+        assert_eq!(status, 200);
+        let results = json.as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        let highlight = &results[0]["highlights"][0];
+        assert_eq!(highlight["field"], "phrase");
+        assert_eq!(highlight["matches"][0], json!({"start": 3, "end": 6}));
+    }
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn text_search_tolerates_typos() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed_phrases(&pool).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/search/text?q=evrywhere")).await;
+    assert_eq!(status, 200);
+    let results = json.as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["phrase"], "ubiquitous");
+    assert!(results[0]["score"].as_f64().unwrap() < 0.9);
+    // Fuzzy matches have nothing to highlight
+    assert!(results[0]["highlights"].as_array().unwrap().is_empty());
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn text_search_ranks_phrase_above_memo() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": "a quiet afternoon", "meanings": ["calm"], "memo": "heard while it was drizzling"});
+    common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": "drizzling", "meanings": ["raining lightly"]});
+    common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/search/text?q=drizzl")).await;
+    assert_eq!(status, 200);
+    let results = json.as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["phrase"], "drizzling");
+    assert_eq!(results[0]["score"], 1.0);
+    assert_eq!(results[1]["phrase"], "a quiet afternoon");
+    assert_eq!(results[1]["highlights"][0]["field"], "memo");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn text_search_treats_wildcards_literally() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed_phrases(&pool).await;
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": "100% sure", "meanings": ["certain"]});
+    common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/search/text?q=%25")).await;
+    assert_eq!(status, 200);
+    let results = json.as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["phrase"], "100% sure");
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+async fn text_search_phrases(pool: &sqlx::PgPool, q: &str) -> Vec<String> {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let encoded = q.replace(' ', "%20").replace('"', "%22");
+    let uri = format!("/api/search/text?q={encoded}");
+    let (status, json) = common::send_json_request(app, common::get_request(&uri)).await;
+    assert_eq!(status, 200, "query {q}: {json}");
+    let mut phrases: Vec<String> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["phrase"].as_str().unwrap().to_string())
+        .collect();
+    phrases.sort();
+    phrases
+}
+
+#[tokio::test]
+async fn text_search_query_language() {
+    let (pool, db_name) = common::setup_test_db().await;
+    seed_phrases(&pool).await;
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": "fleeting", "meanings": ["passing swiftly"], "tags": ["vocabulary"], "memo": "seen in a Murakami novel"});
+    common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+
+    assert_eq!(
+        text_search_phrases(&pool, "tag:vocabulary").await,
+        vec!["ephemeral", "fleeting", "ubiquitous"]
+    );
+    assert_eq!(
+        text_search_phrases(&pool, "tag:vocabulary -ubiquitous").await,
+        vec!["ephemeral", "fleeting"]
+    );
+    assert_eq!(
+        text_search_phrases(&pool, "serendipity OR source:gre").await,
+        vec!["ephemeral", "serendipity"]
+    );
+    assert_eq!(
+        text_search_phrases(&pool, "memo:murakami").await,
+        vec!["fleeting"]
+    );
+    assert_eq!(
+        text_search_phrases(&pool, "meaning:\"short time\"").await,
+        vec!["ephemeral"]
+    );
+    // tag: matches whole tags only, and quoted phrases are not typo tolerant
+    assert!(text_search_phrases(&pool, "tag:vocab").await.is_empty());
+    assert!(text_search_phrases(&pool, "\"evrywhere\"").await.is_empty());
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn text_search_parse_error_has_position() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::get_request("/api/search/text?q=rain%20colour:red"),
+    )
+    .await;
+    assert_eq!(status, 400);
+    assert!(json["error"].as_str().unwrap().contains("Unknown field"));
+    assert_eq!(json["position"], json!({"start": 5, "end": 11}));
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
 #[tokio::test]
 async fn semantic_search_returns_results() {
     let (pool, db_name) = common::setup_test_db().await;
@@ -137,3 +297,69 @@ async fn semantic_search_returns_results() {
     pool.close().await;
     common::teardown_test_db(&db_name).await;
 }
+
+#[tokio::test]
+async fn semantic_search_diversity_skips_near_duplicates() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    // Three near-copies of one sense and a less relevant, different one
+    for (phrase, components) in [
+        ("copy one", vec![(0, 1.0), (1, 0.05)]),
+        ("copy two", vec![(0, 1.0), (1, 0.06)]),
+        ("copy three", vec![(0, 1.0), (1, 0.07)]),
+        ("other sense", vec![(0, 0.6), (2, 0.8)]),
+    ] {
+        let app = common::build_test_app_authenticated(pool.clone());
+        let body = json!({"phrase": phrase, "meanings": [phrase]});
+        let (_, created) =
+            common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+        common::set_phrase_embedding(&pool, created["id"].as_str().unwrap(), &components).await;
+    }
+
+    let search = |body: serde_json::Value| {
+        let pool = pool.clone();
+        async move {
+            let embedder = common::FixedEmbedder::with_components(&[(0, 1.0)]);
+            let app =
+                common::build_test_app_authenticated_with(pool, std::sync::Arc::new(embedder));
+            let (status, json) =
+                common::send_json_request(app, common::json_post("/api/search/semantic", &body))
+                    .await;
+            assert_eq!(status, 200);
+            json.as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["phrase"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    assert_eq!(
+        search(json!({"query": "q", "limit": 2})).await,
+        vec!["copy one", "copy two"]
+    );
+    assert_eq!(
+        search(json!({"query": "q", "limit": 2, "diversity": 0.7})).await,
+        vec!["copy one", "other sense"]
+    );
+    assert_eq!(
+        search(json!({"query": "q", "limit": 2, "diversity": 0.0})).await,
+        vec!["copy one", "copy two"]
+    );
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"query": "q", "diversity": 1.5});
+    let (status, _) =
+        common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+    assert_eq!(status, 400);
+    for limit in [0, -1, 101, i64::MAX] {
+        let app = common::build_test_app_authenticated(pool.clone());
+        let body = json!({"query": "q", "limit": limit, "diversity": 0.5});
+        let (status, _) =
+            common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+        assert_eq!(status, 400, "limit {limit}");
+    }
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use serde_json::json;
+
+async fn create_phrase(pool: &sqlx::PgPool, body: serde_json::Value) -> String {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    created["id"].as_str().unwrap().to_string()
+}
+
+/// Seeds a query phrase and three candidates at decreasing similarity to it.
+async fn seed(pool: &sqlx::PgPool) -> String {
+    let rain = create_phrase(
+        pool,
+        json!({"phrase": "rain", "meanings": ["water falling"], "tags": ["weather"]}),
+    )
+    .await;
+    let drizzle = create_phrase(
+        pool,
+        json!({"phrase": "drizzle", "meanings": ["light rain"], "tags": ["weather"], "source": "Almanac"}),
+    )
+    .await;
+    let storm = create_phrase(
+        pool,
+        json!({"phrase": "storm", "meanings": ["violent weather"], "tags": ["weather", "loud"]}),
+    )
+    .await;
+    let sunshine = create_phrase(
+        pool,
+        json!({"phrase": "sunshine", "meanings": ["light from the sun"]}),
+    )
+    .await;
+
+    common::set_phrase_embedding(pool, &rain, &[(0, 1.0)]).await;
+    common::set_phrase_embedding(pool, &drizzle, &[(0, 0.9), (1, 0.1)]).await;
+    common::set_phrase_embedding(pool, &storm, &[(0, 0.6), (1, 0.4)]).await;
+    common::set_phrase_embedding(pool, &sunshine, &[(1, 1.0)]).await;
+    rain
+}
+
+fn phrases(json: &serde_json::Value) -> Vec<&str> {
+    json.as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["phrase"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn similar_ranks_by_stored_vectors_and_excludes_self() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let rain = seed(&pool).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/phrases/{rain}/similar")),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(phrases(&json), vec!["drizzle", "storm", "sunshine"]);
+
+    let scores: Vec<f64> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["score"].as_f64().unwrap())
+        .collect();
+    assert!(scores[0] > scores[1] && scores[1] > scores[2]);
+    assert!(scores[2].abs() < 1e-6); // orthogonal
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn similar_respects_limit_and_filters() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let rain = seed(&pool).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/phrases/{rain}/similar?limit=1")),
+    )
+    .await;
+    assert_eq!(phrases(&json), vec!["drizzle"]);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/phrases/{rain}/similar?tags=weather,loud")),
+    )
+    .await;
+    assert_eq!(phrases(&json), vec!["storm"]);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/phrases/{rain}/similar?source=almanac")),
+    )
+    .await;
+    assert_eq!(phrases(&json), vec!["drizzle"]);
+
+    // LIKE wildcards in the filter match themselves
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, json) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/phrases/{rain}/similar?source=_")),
+    )
+    .await;
+    assert_eq!(phrases(&json), Vec::<&str>::new());
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn similar_unknown_phrase_returns_404() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let fake_id = uuid::Uuid::new_v4();
+    let (status, _) = common::send_json_request(
+        app,
+        common::get_request(&format!("/api/phrases/{fake_id}/similar")),
+    )
+    .await;
+    assert_eq!(status, 404);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
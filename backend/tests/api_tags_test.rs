@@ -0,0 +1,110 @@
+mod common;
+
+use serde_json::json;
+
+async fn create_phrase(pool: &sqlx::PgPool, body: serde_json::Value) -> String {
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    created["id"].as_str().unwrap().to_string()
+}
+
+fn tags(json: &serde_json::Value) -> Vec<&str> {
+    json.as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["tag"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn suggest_from_draft_meanings() {
+    let (pool, db_name) = common::setup_test_db().await;
+    create_phrase(
+        &pool,
+        json!({"phrase": "drizzle", "meanings": ["light rain"], "tags": ["weather", "rain"]}),
+    )
+    .await;
+    create_phrase(
+        &pool,
+        json!({"phrase": "storm", "meanings": ["violent weather"], "tags": ["weather"]}),
+    )
+    .await;
+
+    // The fake embedder gives every vector the same (undefined) similarity,
+    // so the ranking falls back to tag frequency.
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"meanings": ["water falling from the sky"]});
+    let (status, json) =
+        common::send_json_request(app, common::json_post("/api/tags/suggest", &body)).await;
+    assert_eq!(status, 200);
+    assert_eq!(tags(&json), vec!["weather", "rain"]);
+    assert_eq!(json[0]["count"], 2);
+    assert_eq!(json[0]["score"], 1.0);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn suggest_for_existing_phrase_uses_stored_vectors() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let rain = create_phrase(
+        &pool,
+        json!({"phrase": "rain", "meanings": ["water falling"], "tags": ["weather"]}),
+    )
+    .await;
+    let drizzle = create_phrase(
+        &pool,
+        json!({"phrase": "drizzle", "meanings": ["light rain"], "tags": ["weather", "soft"]}),
+    )
+    .await;
+    let sunshine = create_phrase(
+        &pool,
+        json!({"phrase": "sunshine", "meanings": ["light from the sun"], "tags": ["bright"]}),
+    )
+    .await;
+    common::set_phrase_embedding(&pool, &rain, &[(0, 1.0)]).await;
+    common::set_phrase_embedding(&pool, &drizzle, &[(0, 0.9), (1, 0.1)]).await;
+    common::set_phrase_embedding(&pool, &sunshine, &[(0, 0.2), (1, 0.8)]).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase_id": rain});
+    let (status, json) =
+        common::send_json_request(app, common::json_post("/api/tags/suggest", &body)).await;
+    assert_eq!(status, 200);
+    // "weather" is already on the phrase and is not suggested again
+    assert_eq!(tags(&json), vec!["soft", "bright"]);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn suggest_requires_input() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) =
+        common::send_json_request(app, common::json_post("/api/tags/suggest", &json!({}))).await;
+    assert_eq!(status, 400);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"meanings": vec!["rain"; 11]});
+    let (status, json) =
+        common::send_json_request(app, common::json_post("/api/tags/suggest", &body)).await;
+    assert_eq!(status, 400);
+    assert!(
+        json["error"].as_str().unwrap().contains("At most 10"),
+        "{json}"
+    );
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase_id": uuid::Uuid::new_v4()});
+    let (status, _) =
+        common::send_json_request(app, common::json_post("/api/tags/suggest", &body)).await;
+    assert_eq!(status, 404);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
@@ -6,33 +6,139 @@ use std::sync::Arc;
 
 use axum::Router;
 use axum::body::Body;
+use axum::extract::State;
 use axum::http::{self, Request, StatusCode};
 use axum::middleware;
-use axum::routing::{get, post};
-use eemee_backend::auth;
+use eemee_backend::app;
+use eemee_backend::config::Config;
 use eemee_backend::error::AppError;
-use eemee_backend::routes;
-use eemee_backend::services::embedding::Embedder;
+use eemee_backend::services::embedding::{Embedder, Embedding};
+use eemee_backend::services::jobs;
+use eemee_backend::services::usage::MeteredEmbedder;
 use eemee_backend::state::AppState;
 use http_body_util::BodyExt;
-use oauth2::basic::BasicClient;
-use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl};
 use pgvector::Vector;
 use sqlx::PgPool;
 use tower::ServiceExt;
-use tower_sessions::cookie::SameSite;
-use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer};
+use tower_sessions::MemoryStore;
+
+/// Model name reported by the test embedders.
+pub const TEST_MODEL: &str = "test-embedding";
+
+/// Test embedders bill one token per word of the text.
+pub fn test_embedding(vector: Vec<f32>, text: &str) -> Embedding {
+    Embedding {
+        vector: Vector::from(vector),
+        tokens: text.split_whitespace().count() as i64,
+    }
+}
 
 /// Fake embedder that returns zero vectors of dimension 3072.
 pub struct FakeEmbedder;
 
 impl Embedder for FakeEmbedder {
+    fn model(&self) -> &str {
+        TEST_MODEL
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    fn embed<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<Embedding, AppError>> + Send + 'a>> {
+        Box::pin(async move { Ok(test_embedding(vec![0.0_f32; 3072], text)) })
+    }
+}
+
+/// Embedder for an embedding API that is down.
+pub struct FailingEmbedder;
+
+impl Embedder for FailingEmbedder {
+    fn model(&self) -> &str {
+        TEST_MODEL
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
     fn embed<'a>(
         &'a self,
         _text: &'a str,
-    ) -> Pin<Box<dyn Future<Output = Result<Vector, AppError>> + Send + 'a>> {
-        Box::pin(async { Ok(Vector::from(vec![0.0_f32; 3072])) })
+    ) -> Pin<Box<dyn Future<Output = Result<Embedding, AppError>> + Send + 'a>> {
+        Box::pin(async { Err(AppError::Embedding("service unavailable".to_string())) })
+    }
+
+    fn check(&self) -> Pin<Box<dyn Future<Output = Result<(), AppError>> + Send + '_>> {
+        Box::pin(async { Err(AppError::Embedding("service unavailable".to_string())) })
+    }
+}
+
+/// A 3072-dim vector that is zero except for the given `(axis, value)` components.
+pub fn sparse_vector(components: &[(usize, f32)]) -> Vec<f32> {
+    let mut values = vec![0.0_f32; 3072];
+    for &(axis, value) in components {
+        values[axis] = value;
     }
+    values
+}
+
+/// Embedder that returns the same vector for every text, for tests that control the query.
+pub struct FixedEmbedder(pub Vec<f32>);
+
+impl FixedEmbedder {
+    pub fn with_components(components: &[(usize, f32)]) -> Self {
+        FixedEmbedder(sparse_vector(components))
+    }
+}
+
+impl Embedder for FixedEmbedder {
+    fn model(&self) -> &str {
+        TEST_MODEL
+    }
+
+    fn provider(&self) -> &str {
+        "test"
+    }
+
+    fn embed<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<Embedding, AppError>> + Send + 'a>> {
+        Box::pin(async move { Ok(test_embedding(self.0.clone(), text)) })
+    }
+}
+
+/// Overwrites every meaning embedding of a phrase with a vector that is zero except for
+/// the given `(axis, value)` components, so tests can control similarity.
+pub async fn set_phrase_embedding(pool: &PgPool, phrase_id: &str, components: &[(usize, f32)]) {
+    sqlx::query("UPDATE phrase_meanings SET meaning_embedding = $1 WHERE phrase_id = $2")
+        .bind(Vector::from(sparse_vector(components)))
+        .bind(uuid::Uuid::parse_str(phrase_id).unwrap())
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+/// Like [`set_phrase_embedding`], for the one meaning of a phrase with the given text.
+pub async fn set_meaning_embedding(
+    pool: &PgPool,
+    phrase_id: &str,
+    meaning: &str,
+    components: &[(usize, f32)],
+) {
+    sqlx::query(
+        "UPDATE phrase_meanings SET meaning_embedding = $1 WHERE phrase_id = $2 AND meaning = $3",
+    )
+    .bind(Vector::from(sparse_vector(components)))
+    .bind(uuid::Uuid::parse_str(phrase_id).unwrap())
+    .bind(meaning)
+    .execute(pool)
+    .await
+    .unwrap();
 }
 
 /// Creates a unique test database, runs migrations, returns the pool.
@@ -108,74 +214,109 @@ pub async fn teardown_test_db(db_name: &str) {
     admin_pool.close().await;
 }
 
-fn dummy_oauth_client() -> eemee_backend::state::OAuthClient {
-    let client_id = ClientId::new("test-client-id".to_string());
-    let client_secret = ClientSecret::new("test-client-secret".to_string());
-    let auth_url = AuthUrl::new("https://example.com/auth".to_string()).unwrap();
-    let token_url = TokenUrl::new("https://example.com/token".to_string()).unwrap();
-    let redirect_url = RedirectUrl::new("http://localhost:3000/callback".to_string()).unwrap();
+/// Configuration for test apps, with placeholder credentials. Tests change fields to
+/// build variants.
+pub fn test_config() -> Config {
+    let file = r#"
+        static_dir = "/nonexistent"
+
+        [database]
+        url = "postgres://unused"
+
+        [oauth]
+        client_id = "test-client-id"
+        client_secret = "test-client-secret"
+        auth_url = "https://example.com/auth"
+        token_url = "https://example.com/token"
+        redirect_url = "http://localhost:3000/callback"
+
+        [embedding]
+        api_key = "unused"
+
+        # Tests that exercise the limits set them; the rest make requests freely
+        [limits]
+        per_user_per_minute = 0
+        global_per_minute = 0
+    "#;
+    Config::from_sources(Some(file), |_| None).unwrap()
+}
 
-    BasicClient::new(client_id)
-        .set_client_secret(client_secret)
-        .set_auth_uri(auth_url)
-        .set_token_uri(token_url)
-        .set_redirect_uri(redirect_url)
+pub fn test_state(pool: PgPool, embedding: Arc<dyn Embedder>, config: &Config) -> Arc<AppState> {
+    let embedding = Arc::new(MeteredEmbedder::new(embedding, pool.clone()));
+    AppState::new(
+        pool,
+        embedding,
+        config.oauth.client(),
+        config.allowed_emails.clone(),
+        config.health.clone(),
+        config.limits.clone(),
+    )
 }
 
 /// Builds the full router without authentication.
 pub fn build_test_app(pool: PgPool) -> Router {
-    let embedding: Arc<dyn Embedder> = Arc::new(FakeEmbedder);
-    let state = AppState::new(pool, embedding, dummy_oauth_client(), vec![]);
-
-    let session_layer = SessionManagerLayer::new(MemoryStore::default())
-        .with_secure(false)
-        .with_same_site(SameSite::Lax)
-        .with_expiry(Expiry::OnInactivity(
-            tower_sessions::cookie::time::Duration::days(1),
-        ));
-
-    let auth_routes = Router::new()
-        .route("/google", get(auth::google_login))
-        .route("/callback", get(auth::google_callback))
-        .route("/logout", post(auth::logout))
-        .route("/me", get(auth::me))
-        .with_state(state.clone());
+    build_test_app_with_config(pool, &test_config())
+}
 
-    let api = Router::new()
-        .nest("/auth", auth_routes)
-        .merge(routes::api_router(state))
-        .layer(middleware::from_fn(auth::middleware::require_auth));
+/// Like [`build_test_app`], with a custom configuration.
+pub fn build_test_app_with_config(pool: PgPool, config: &Config) -> Router {
+    build_test_app_with(pool, Arc::new(FakeEmbedder), config)
+}
 
-    Router::new().nest("/api", api).layer(session_layer)
+/// Like [`build_test_app`], with a custom embedder and configuration.
+pub fn build_test_app_with(pool: PgPool, embedding: Arc<dyn Embedder>, config: &Config) -> Router {
+    let state = test_state(pool, embedding, config);
+    app::build(state, config, MemoryStore::default())
 }
 
 /// Builds the full router with a pre-authenticated session.
 /// Uses a middleware that injects the email into the session before the auth check.
+/// Embedding jobs are run after every request, standing in for the background workers.
 pub fn build_test_app_authenticated(pool: PgPool) -> Router {
-    let embedding: Arc<dyn Embedder> = Arc::new(FakeEmbedder);
-    let state = AppState::new(pool, embedding, dummy_oauth_client(), vec![]);
+    build_test_app_authenticated_with(pool, Arc::new(FakeEmbedder))
+}
+
+/// Like [`build_test_app_authenticated`], with a custom embedder.
+pub fn build_test_app_authenticated_with(pool: PgPool, embedding: Arc<dyn Embedder>) -> Router {
+    let config = test_config();
+    let state = test_state(pool, embedding, &config);
+    authenticated_app(state.clone(), &config)
+        .layer(middleware::from_fn_with_state(state, drain_jobs))
+}
+
+/// Like [`build_test_app_authenticated`], but embedding jobs stay queued until
+/// [`drain_jobs_with`] runs them.
+pub fn build_test_app_without_workers(pool: PgPool) -> Router {
+    build_test_app_without_workers_with_config(pool, &test_config())
+}
 
-    let session_layer = SessionManagerLayer::new(MemoryStore::default())
-        .with_secure(false)
-        .with_same_site(SameSite::Lax)
-        .with_expiry(Expiry::OnInactivity(
-            tower_sessions::cookie::time::Duration::days(1),
-        ));
+/// Like [`build_test_app_without_workers`], with a custom configuration.
+pub fn build_test_app_without_workers_with_config(pool: PgPool, config: &Config) -> Router {
+    let state = test_state(pool, Arc::new(FakeEmbedder), config);
+    authenticated_app(state, config)
+}
 
-    let auth_routes = Router::new()
-        .route("/google", get(auth::google_login))
-        .route("/callback", get(auth::google_callback))
-        .route("/logout", post(auth::logout))
-        .route("/me", get(auth::me))
-        .with_state(state.clone());
+/// Runs every due embedding job with the given embedder.
+pub async fn drain_jobs_with(pool: &PgPool, embedder: &dyn Embedder) -> usize {
+    jobs::drain(pool, embedder).await.unwrap()
+}
 
-    let api = Router::new()
-        .nest("/auth", auth_routes)
-        .merge(routes::api_router(state))
-        .layer(middleware::from_fn(auth::middleware::require_auth))
-        .layer(middleware::from_fn(inject_test_session));
+async fn drain_jobs(
+    State(state): State<Arc<AppState>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let response = next.run(request).await;
+    jobs::drain(&state.pool, state.embedding.as_ref())
+        .await
+        .unwrap();
+    response
+}
 
-    Router::new().nest("/api", api).layer(session_layer)
+fn authenticated_app(state: Arc<AppState>, config: &Config) -> Router {
+    app::build_with(state, config, MemoryStore::default(), |api| {
+        api.layer(middleware::from_fn(inject_test_session))
+    })
 }
 
 async fn inject_test_session(
@@ -201,8 +342,7 @@ pub async fn send_json_request(
     request: Request<Body>,
 ) -> (StatusCode, serde_json::Value) {
     let (status, body) = send_request(app, request).await;
-    let json: serde_json::Value =
-        serde_json::from_slice(&body).unwrap_or_else(|_| serde_json::json!(null));
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap_or(serde_json::json!(null));
     (status, json)
 }
 
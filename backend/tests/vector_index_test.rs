@@ -0,0 +1,250 @@
+mod common;
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use eemee_backend::models::phrase::{AnnParams, PhraseFilter};
+use eemee_backend::models::vector_index::{BuildIndexRequest, IndexKind};
+use eemee_backend::services::{db, vector_index};
+use pgvector::Vector;
+use serde_json::json;
+use uuid::Uuid;
+
+const DIMENSIONS: usize = 3072;
+const CLUSTERS: usize = 20;
+const PHRASES: usize = 1000;
+const QUERIES: usize = 20;
+const K: i64 = 10;
+const MIN_RECALL: f64 = 0.9;
+
+/// Deterministic xorshift generator so the benchmark data is reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+    }
+
+    fn vector(&mut self) -> Vec<f32> {
+        (0..DIMENSIONS).map(|_| self.next_f32()).collect()
+    }
+
+    /// `center` plus uniform noise of the given amplitude.
+    fn near(&mut self, center: &[f32], noise: f32) -> Vec<f32> {
+        center.iter().map(|c| c + noise * self.next_f32()).collect()
+    }
+}
+
+/// Seeds clustered embeddings, which is closer to real meanings than uniform noise,
+/// and returns query vectors drawn near random cluster centers.
+async fn seed(pool: &sqlx::PgPool) -> Vec<Vector> {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    let centers: Vec<Vec<f32>> = (0..CLUSTERS).map(|_| rng.vector()).collect();
+
+    for i in 0..PHRASES {
+        let embedding = rng.near(&centers[i % CLUSTERS], 0.8);
+        let phrase = db::create_phrase(
+            pool,
+            &format!("phrase {i}"),
+            &[format!("meaning {i}")],
+            None,
+            &[],
+            None,
+        )
+        .await
+        .unwrap();
+        // Stands in for the embedding worker
+        sqlx::query(
+            "UPDATE phrase_meanings
+             SET meaning_embedding = $1, embedding_model = $2, embedding_status = 'ready'
+             WHERE phrase_id = $3",
+        )
+        .bind(Vector::from(embedding))
+        .bind(common::TEST_MODEL)
+        .bind(phrase.id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+    sqlx::query("DELETE FROM embedding_jobs")
+        .execute(pool)
+        .await
+        .unwrap();
+
+    (0..QUERIES)
+        .map(|q| Vector::from(rng.near(&centers[(q * 7) % CLUSTERS], 0.8)))
+        .collect()
+}
+
+/// Mean recall@K of approximate search against exact search, and total time of each.
+async fn measure(
+    pool: &sqlx::PgPool,
+    queries: &[Vector],
+    params: AnnParams,
+) -> (f64, Duration, Duration) {
+    let filter = PhraseFilter::default();
+    let (mut recall, mut exact_time, mut approx_time) = (0.0, Duration::ZERO, Duration::ZERO);
+
+    for query in queries {
+        let started = Instant::now();
+        let exact = db::semantic_search(pool, query, K, &filter).await.unwrap();
+        exact_time += started.elapsed();
+
+        let started = Instant::now();
+        let approx = db::semantic_search_approximate(pool, query, K, &filter, params)
+            .await
+            .unwrap();
+        approx_time += started.elapsed();
+
+        let expected: HashSet<Uuid> = exact.iter().map(|r| r.id).collect();
+        let found = approx.iter().filter(|r| expected.contains(&r.id)).count();
+        recall += found as f64 / expected.len() as f64;
+    }
+
+    (recall / queries.len() as f64, exact_time, approx_time)
+}
+
+#[tokio::test]
+async fn hnsw_recall_against_exact_search() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let queries = seed(&pool).await;
+
+    let req = BuildIndexRequest {
+        kind: IndexKind::Hnsw,
+        m: None,
+        ef_construction: None,
+        lists: None,
+    };
+    let status = vector_index::build_index(&pool, &req).await.unwrap();
+    assert!(status.exists && status.valid);
+
+    let params = AnnParams {
+        ef_search: Some(100),
+        probes: None,
+    };
+    let (recall, exact_time, approx_time) = measure(&pool, &queries, params).await;
+    assert!(
+        recall >= MIN_RECALL,
+        "hnsw: recall@{K} {recall:.3} below {MIN_RECALL} (exact {exact_time:?}, approximate \
+         {approx_time:?} over {QUERIES} queries)"
+    );
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn ivfflat_recall_against_exact_search() {
+    let (pool, db_name) = common::setup_test_db().await;
+    let queries = seed(&pool).await;
+
+    let req = BuildIndexRequest {
+        kind: IndexKind::Ivfflat,
+        m: None,
+        ef_construction: None,
+        lists: Some(10),
+    };
+    vector_index::build_index(&pool, &req).await.unwrap();
+
+    let params = AnnParams {
+        ef_search: None,
+        probes: Some(5),
+    };
+    let (recall, exact_time, approx_time) = measure(&pool, &queries, params).await;
+    assert!(
+        recall >= MIN_RECALL,
+        "ivfflat: recall@{K} {recall:.3} below {MIN_RECALL} (exact {exact_time:?}, approximate \
+         {approx_time:?} over {QUERIES} queries)"
+    );
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn admin_builds_and_drops_index() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) =
+        common::send_json_request(app, common::get_request("/api/admin/vector-index")).await;
+    assert_eq!(status, 200);
+    assert_eq!(json["exists"], false);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::json_post("/api/admin/vector-index", &json!({"kind": "hnsw", "m": 8})),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(json["exists"], true);
+    assert_eq!(json["kind"], "hnsw");
+    assert_eq!(json["valid"], true);
+    assert!(json["definition"].as_str().unwrap().contains("m='8'"));
+
+    // Rebuilding replaces the existing index, clearing what an interrupted build left
+    sqlx::query("CREATE INDEX idx_phrase_meanings_embedding_ann_build ON phrase_meanings (id)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, json) = common::send_json_request(
+        app,
+        common::json_post("/api/admin/vector-index", &json!({"kind": "ivfflat"})),
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(json["kind"], "ivfflat");
+    let names: Vec<String> = sqlx::query_scalar(
+        "SELECT indexname::text FROM pg_indexes
+         WHERE tablename = 'phrase_meanings' AND indexname LIKE '%embedding_ann%'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(names, [vector_index::INDEX_NAME]);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (status, _) =
+        common::send_json_request(app, common::delete_request("/api/admin/vector-index")).await;
+    assert_eq!(status, 200);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let (_, json) =
+        common::send_json_request(app, common::get_request("/api/admin/vector-index")).await;
+    assert_eq!(json["exists"], false);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}
+
+#[tokio::test]
+async fn semantic_search_with_ef_search() {
+    let (pool, db_name) = common::setup_test_db().await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"phrase": "drizzle", "meanings": ["light rain"]});
+    let (_, created) =
+        common::send_json_request(app, common::json_post("/api/phrases", &body)).await;
+    common::set_phrase_embedding(&pool, created["id"].as_str().unwrap(), &[(0, 1.0)]).await;
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"query": "rain", "ef_search": 40});
+    let (status, json) =
+        common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+    assert_eq!(status, 200);
+    assert_eq!(json.as_array().unwrap().len(), 1);
+
+    let app = common::build_test_app_authenticated(pool.clone());
+    let body = json!({"query": "rain", "ef_search": 0});
+    let (status, _) =
+        common::send_json_request(app, common::json_post("/api/search/semantic", &body)).await;
+    assert_eq!(status, 400);
+
+    pool.close().await;
+    common::teardown_test_db(&db_name).await;
+}